@@ -0,0 +1,118 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dsi_progress_logger::prelude::*;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+const THREADS: usize = 4;
+const UPDATES_PER_THREAD: usize = 1_000_000;
+
+/// Per-call cost of the update methods from a single thread.
+fn single_thread(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_thread");
+
+    // The cost of a plain increment, as a baseline for the update methods
+    let mut count = 0_usize;
+    group.bench_function("baseline", |b| b.iter(|| *black_box(&mut count) += 1));
+
+    let mut pl = ProgressLogger::default();
+    pl.start("");
+    group.bench_function("ProgressLogger::update", |b| {
+        b.iter(|| black_box(&mut pl).update())
+    });
+
+    let mut pl = ProgressLogger::default();
+    pl.start("");
+    group.bench_function("ProgressLogger::light_update", |b| {
+        b.iter(|| black_box(&mut pl).light_update())
+    });
+
+    let mut pl = ProgressLogger::default();
+    pl.start("");
+    group.bench_function("ProgressLogger::light_update_with::<1023>", |b| {
+        b.iter(|| black_box(&mut pl).light_update_with::<1023>())
+    });
+
+    let mut pl = Some(ProgressLogger::default());
+    pl.start("");
+    group.bench_function("Option<ProgressLogger>::light_update", |b| {
+        b.iter(|| black_box(&mut pl).light_update())
+    });
+
+    let mut pl: Option<ProgressLogger> = None;
+    group.bench_function("None::light_update", |b| {
+        b.iter(|| black_box(&mut pl).light_update())
+    });
+
+    let mut pl = ProgressLogger::default();
+    let counter = pl.counter();
+    pl.start("");
+    group.bench_function("ProgressLogger::counter", |b| {
+        b.iter(|| black_box(&counter).fetch_add(1, Ordering::Relaxed))
+    });
+
+    let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+    pl.lock().start("");
+    group.bench_function("ProgressLoggerHandle::update", |b| {
+        b.iter(|| black_box(&pl).update())
+    });
+
+    let mut pl = ProgressLogger::default();
+    let mut counter = pl.relaxed_counter();
+    pl.start("");
+    group.bench_function("RelaxedCounter::update", |b| {
+        b.iter(|| black_box(&mut counter).update())
+    });
+
+    group.finish();
+}
+
+/// Cost of updating from several threads at once.
+fn concurrent(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrent");
+    group.sample_size(20);
+
+    group.bench_function("ProgressLoggerHandle::update", |b| {
+        let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+        pl.lock().start("");
+        b.iter(|| {
+            thread::scope(|s| {
+                for _ in 0..THREADS {
+                    s.spawn(|| {
+                        for _ in 0..UPDATES_PER_THREAD {
+                            black_box(&pl).update();
+                        }
+                    });
+                }
+            })
+        })
+    });
+
+    group.bench_function("RelaxedCounter::update", |b| {
+        let mut pl = ProgressLogger::default();
+        let mut counters: Vec<_> = (0..THREADS).map(|_| pl.relaxed_counter()).collect();
+        pl.start("");
+        b.iter(|| {
+            thread::scope(|s| {
+                for counter in &mut counters {
+                    s.spawn(move || {
+                        for _ in 0..UPDATES_PER_THREAD {
+                            black_box(&mut *counter).update();
+                        }
+                    });
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, single_thread, concurrent);
+criterion_main!(benches);
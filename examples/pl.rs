@@ -8,7 +8,6 @@
 use dsi_progress_logger::*;
 use log::info;
 use std::thread;
-use stderrlog;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     stderrlog::new()
@@ -0,0 +1,112 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use std::time::{Duration, Instant};
+
+/// The number of items whose cost is measured before choosing the update
+/// method.
+const CALIBRATION_ITEMS: usize = 1024;
+
+/// The maximum cost per item for which
+/// [`light_update`](ProgressLog::light_update) is chosen.
+///
+/// With this cost, the time is checked by `light_update` at least once per
+/// second, that is, ten times per default log interval.
+const LIGHT_UPDATE_MAX_COST: Duration =
+    Duration::from_nanos(1_000_000_000 / (ProgressLogger::LIGHT_UPDATE_MASK as u64 + 1));
+
+/// The state of the choice between [`update`](crate::ProgressLog::update)
+/// and [`light_update`](crate::ProgressLog::light_update) of
+/// [`adaptive_update`](crate::ProgressLog::adaptive_update).
+///
+/// The first [`CALIBRATION_ITEMS`] items are counted with
+/// [`update_with_count`](crate::ProgressLog::update_with_count); then, if
+/// their average cost (including the update) is at most
+/// [`LIGHT_UPDATE_MAX_COST`], `light_update` is used for the remaining items.
+#[derive(Debug, Default)]
+pub(crate) struct UpdateSelector {
+    /// The number of items counted during calibration.
+    items: usize,
+    /// The time at which the first item was counted.
+    start: Option<Instant>,
+    /// Whether to use `light_update`.
+    pub(crate) light: bool,
+}
+
+impl UpdateSelector {
+    /// Record that an item has been counted with `update` at the given time,
+    /// and choose the update method at the end of the calibration.
+    #[inline]
+    pub(crate) fn calibrate(&mut self, now: Instant) {
+        let start = *self.start.get_or_insert(now);
+        self.items += 1;
+        if self.items == CALIBRATION_ITEMS {
+            self.light = now.saturating_duration_since(start)
+                <= LIGHT_UPDATE_MAX_COST * (CALIBRATION_ITEMS - 1) as u32;
+        }
+    }
+}
+
+impl ProgressLogger {
+    /// Count an item using the update method chosen so far by the selector.
+    #[inline(always)]
+    pub(crate) fn adaptive_update_impl(&mut self) {
+        if self.selector.light {
+            self.light_update_with::<{ Self::LIGHT_UPDATE_MASK }>();
+        } else {
+            // Each call counts exactly one item, regardless of sampling
+            self.update_with_count(1);
+            let now = self.now();
+            self.selector.calibrate(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_adaptive_update() {
+        let clock = crate::ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone());
+        pl.start("");
+        for _ in 0..2 * CALIBRATION_ITEMS {
+            pl.adaptive_update();
+        }
+        assert!(pl.selector.light);
+        assert_eq!(pl.count(), 2 * CALIBRATION_ITEMS);
+
+        pl.start("");
+        assert!(!pl.selector.light);
+        for _ in 0..2 * CALIBRATION_ITEMS {
+            clock.advance(LIGHT_UPDATE_MAX_COST * 2);
+            pl.adaptive_update();
+        }
+        assert!(!pl.selector.light);
+    }
+
+    #[test]
+    fn test_helpers() -> std::io::Result<()> {
+        let mut pl = ProgressLogger::default();
+        pl.start("");
+        let mut sum = 0;
+        pl.for_each_indexed(&[1, 2, 3], |i, x| sum += i * x);
+        assert_eq!(sum, 8);
+        assert_eq!(pl.count(), 3);
+        let mut lines = Vec::new();
+        pl.scan(Cursor::new("one\ntwo\r\nthree"), |line| {
+            lines.push(line.to_owned())
+        })?;
+        assert_eq!(lines, ["one", "two", "three"]);
+        assert_eq!(pl.count(), 6);
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+/**
+
+A transport moving the counts of the workers of a distributed computation
+to the process aggregating them.
+
+Counts are absolute, so transports may lose or duplicate messages: the
+aggregated count will be correct as soon as a later message gets through.
+This crate provides a [UDP transport](UdpTransport) and, with the `mpi`
+feature, an MPI transport (`MpiTransport`); other transports can be plugged
+in by implementing this trait.
+
+*/
+pub trait Transport {
+    /// Send to the aggregator the count of the worker with the given rank.
+    fn send(&self, rank: usize, count: usize) -> io::Result<()>;
+
+    /// Pass to the given closure the rank and the count of all pending
+    /// messages received by the aggregator, without blocking.
+    fn receive(&self, counts: &mut dyn FnMut(usize, usize)) -> io::Result<()>;
+}
+
+/// A [`Transport`] sending counts in UDP datagrams.
+#[derive(Debug)]
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    /// The size of a message: rank and count, as little-endian 64-bit
+    /// integers.
+    const MESSAGE_LEN: usize = 16;
+
+    /// Create the transport of a worker sending counts to the aggregator at
+    /// the given address.
+    pub fn worker(aggregator: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            socket: crate::utils::udp_socket(aggregator)?,
+        })
+    }
+
+    /// Create the transport of the aggregator, receiving counts at the given
+    /// address.
+    pub fn aggregator(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket })
+    }
+
+    /// Return the local address of the transport.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&self, rank: usize, count: usize) -> io::Result<()> {
+        let mut message = [0; Self::MESSAGE_LEN];
+        message[..8].copy_from_slice(&(rank as u64).to_le_bytes());
+        message[8..].copy_from_slice(&(count as u64).to_le_bytes());
+        self.socket.send(&message).map(|_| ())
+    }
+
+    fn receive(&self, counts: &mut dyn FnMut(usize, usize)) -> io::Result<()> {
+        let mut message = [0; Self::MESSAGE_LEN];
+        loop {
+            match self.socket.recv(&mut message) {
+                Ok(Self::MESSAGE_LEN) => {
+                    let rank = u64::from_le_bytes(message[..8].try_into().unwrap());
+                    let count = u64::from_le_bytes(message[8..].try_into().unwrap());
+                    counts(
+                        rank.try_into().unwrap_or(usize::MAX),
+                        count.try_into().unwrap_or(usize::MAX),
+                    );
+                }
+                Ok(_) => {} // Not one of our messages
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/**
+
+The progress of a worker of a distributed computation.
+
+The worker counts its items locally and periodically sends the count to the
+aggregator using a [`Transport`]. The worker does not log anything.
+
+# Examples
+
+```no_run
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::{UdpTransport, WorkerProgress};
+
+let rank = 3;
+let mut worker = WorkerProgress::new(UdpTransport::worker("127.0.0.1:9999")?, rank);
+for _ in 0..100 {
+    // do something on each pumpkin
+    worker.update();
+}
+worker.done()?;
+#     Ok(())
+# }
+```
+
+*/
+pub struct WorkerProgress<T> {
+    transport: T,
+    rank: usize,
+    count: usize,
+    send_interval: Duration,
+    next_send_time: Instant,
+}
+
+impl<T: Transport> WorkerProgress<T> {
+    /// Create a new worker with the given rank, sending its count through
+    /// the given transport.
+    pub fn new(transport: T, rank: usize) -> Self {
+        Self {
+            transport,
+            rank,
+            count: 0,
+            send_interval: Duration::from_secs(1),
+            next_send_time: Instant::now(),
+        }
+    }
+
+    /// Set the interval between two sends of the count. The default is one
+    /// second.
+    pub fn send_interval(&mut self, send_interval: Duration) -> &mut Self {
+        self.send_interval = send_interval;
+        self
+    }
+
+    /// Increase the count by one and send it if the send interval has
+    /// elapsed.
+    pub fn update(&mut self) {
+        self.update_with_count(1);
+    }
+
+    /// Increase the count by the given value and send it if the send
+    /// interval has elapsed.
+    ///
+    /// Errors of the transport are ignored, as the count will be sent again.
+    pub fn update_with_count(&mut self, count: usize) {
+        self.count = self.count.saturating_add(count);
+        let now = Instant::now();
+        if self.next_send_time <= now {
+            let _ = self.transport.send(self.rank, self.count);
+            self.next_send_time = now.checked_add(self.send_interval).unwrap_or(now);
+        }
+    }
+
+    /// Return the current count.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Send the final count.
+    pub fn done(&mut self) -> io::Result<()> {
+        self.transport.send(self.rank, self.count)
+    }
+}
+
+/**
+
+A [`ProgressLogger`] logging the global progress of a distributed
+computation.
+
+The aggregator (e.g., the process of rank 0) should [poll](#method.poll) the
+[`Transport`] periodically: the count of the logger is set to the sum of the
+last counts received from each [worker](WorkerProgress), and the logger logs
+if it is time to do so.
+
+The logger can be accessed directly through [`Deref`] and [`DerefMut`].
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::{AggregateProgress, UdpTransport};
+
+let mut pl = AggregateProgress::new(UdpTransport::aggregator("127.0.0.1:0")?);
+pl.item_name("pumpkin");
+pl.start("Smashing pumpkins on the cluster...");
+// until the workers are done
+pl.poll()?;
+pl.done();
+#     Ok(())
+# }
+```
+
+*/
+pub struct AggregateProgress<T> {
+    pl: ProgressLogger,
+    transport: T,
+    /// The last count received from each worker, indexed by rank.
+    counts: Vec<usize>,
+}
+
+impl<T: Transport> AggregateProgress<T> {
+    /// The largest rank accepted; messages with larger ranks are ignored.
+    pub const MAX_RANK: usize = (1 << 24) - 1;
+
+    /// Create a new aggregator receiving counts through the given transport.
+    pub fn new(transport: T) -> Self {
+        Self {
+            pl: ProgressLogger::default(),
+            transport,
+            counts: Vec::new(),
+        }
+    }
+
+    /// Receive the pending counts, update the count of the logger, and log
+    /// if it is time to do so.
+    pub fn poll(&mut self) -> io::Result<()> {
+        let counts = &mut self.counts;
+        let result = self.transport.receive(&mut |rank, count| {
+            if rank > Self::MAX_RANK {
+                return;
+            }
+            if rank >= counts.len() {
+                counts.resize(rank.saturating_add(1), 0);
+            }
+            counts[rank] = count;
+        });
+        self.pl.set_count(
+            self.counts
+                .iter()
+                .fold(0_usize, |sum, &count| sum.saturating_add(count)),
+        );
+        self.pl.update_with_count(0);
+        result
+    }
+
+    /// Return the last count received from each worker, indexed by rank.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Return the logger, consuming the aggregator.
+    pub fn into_inner(self) -> ProgressLogger {
+        self.pl
+    }
+}
+
+impl<T> Deref for AggregateProgress<T> {
+    type Target = ProgressLogger;
+
+    fn deref(&self) -> &ProgressLogger {
+        &self.pl
+    }
+}
+
+impl<T> DerefMut for AggregateProgress<T> {
+    fn deref_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_udp_aggregation() -> io::Result<()> {
+        let mut pl = AggregateProgress::new(UdpTransport::aggregator("127.0.0.1:0")?);
+        let addr = pl.transport.local_addr()?;
+        pl.start("");
+
+        let mut workers = (0..3)
+            .map(|rank| Ok(WorkerProgress::new(UdpTransport::worker(addr)?, rank)))
+            .collect::<io::Result<Vec<_>>>()?;
+        for (rank, worker) in workers.iter_mut().enumerate() {
+            worker.update_with_count(10 * (rank + 1));
+            worker.update();
+            worker.done()?;
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pl.count() != 63 && Instant::now() < deadline {
+            pl.poll()?;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pl.counts(), &[11, 21, 31]);
+        assert_eq!(pl.count(), 63);
+        Ok(())
+    }
+}
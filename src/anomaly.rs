@@ -0,0 +1,131 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+use std::fmt::{Formatter, Result};
+use std::time::Instant;
+
+/// The number of past interval speeds used to compute the typical speed.
+const ANOMALY_WINDOW: usize = 32;
+
+/// The minimum number of past interval speeds needed to flag anomalies.
+const ANOMALY_MIN_INTERVALS: usize = 3;
+
+impl ProgressLogger {
+    /// Set the factor beyond which the speed of a log interval is flagged as
+    /// anomalous.
+    ///
+    /// If the speed of the current log interval is smaller (or larger) than
+    /// the median speed of the last 32 log intervals divided (or multiplied)
+    /// by the given factor, the progress line is marked, as in `⚠ 5.2×
+    /// slower than typical`, so that throughput cliffs are easy to spot when
+    /// skimming logs. No anomaly is flagged until three intervals have been
+    /// logged. Factors that are not larger than one are ignored. The default
+    /// is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.anomaly_factor(Some(3.0));
+    /// pl.start("Crawling...");
+    /// for _ in 0..1000 {
+    ///     pl.light_update();
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn anomaly_factor(&mut self, factor: Option<f64>) -> &mut Self {
+        self.anomaly_factor = factor.filter(|factor| *factor > 1.0);
+        self
+    }
+
+    /// Return the speed of the current log interval, in items per second.
+    fn interval_speed(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_log_time);
+        self.count.saturating_sub(self.last_count) as f64 / elapsed.as_secs_f64()
+    }
+
+    /// Record the speed of the current log interval, if anomaly flagging is
+    /// enabled.
+    pub(crate) fn record_interval_speed(&mut self, now: Instant) {
+        if self.anomaly_factor.is_none() {
+            return;
+        }
+        let speed = self.interval_speed(now);
+        if speed.is_finite() {
+            if self.interval_speeds.len() == ANOMALY_WINDOW {
+                self.interval_speeds.pop_front();
+            }
+            self.interval_speeds.push_back(speed);
+        }
+    }
+
+    /// Format a marker if the speed of the current log interval deviates
+    /// from the median of the past ones by more than the anomaly factor.
+    pub(crate) fn fmt_anomaly(&self, f: &mut Formatter<'_>, now: Instant) -> Result {
+        let Some(factor) = self.anomaly_factor else {
+            return Ok(());
+        };
+        if self.interval_speeds.len() < ANOMALY_MIN_INTERVALS {
+            return Ok(());
+        }
+        let speed = self.interval_speed(now);
+        if !speed.is_finite() {
+            return Ok(());
+        }
+        let mut speeds = Vec::from_iter(self.interval_speeds.iter().copied());
+        speeds.sort_unstable_by(f64::total_cmp);
+        let median = speeds[speeds.len() / 2];
+        if speed * factor < median {
+            let ratio = median / speed;
+            if ratio.is_finite() {
+                f.write_fmt(format_args!("; ⚠ {:.1}× slower than typical", ratio))?;
+            } else {
+                f.write_str("; ⚠ stalled")?;
+            }
+        } else if speed > median * factor {
+            f.write_fmt(format_args!(
+                "; ⚠ {:.1}× faster than typical",
+                speed / median
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_anomaly_factor() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .log_interval(Duration::from_secs(1))
+            .anomaly_factor(Some(3.0));
+        pl.start("");
+        for _ in 0..3 {
+            clock.advance(Duration::from_secs(1));
+            pl.update_with_count(100);
+        }
+        assert!(!pl.to_string().contains('⚠'));
+        clock.advance(Duration::from_millis(500));
+        pl.update_with_count(10);
+        assert!(pl.to_string().ends_with("; ⚠ 5.0× slower than typical"));
+        clock.advance(Duration::from_millis(500));
+        pl.update();
+        clock.advance(Duration::from_millis(100));
+        pl.update_with_count(52);
+        assert!(pl.to_string().ends_with("; ⚠ 5.2× faster than typical"));
+        pl.done();
+        assert!(!pl.to_string().contains('⚠'));
+    }
+}
@@ -0,0 +1,365 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::handle::nanos_since;
+use crate::{Clock, ProgressLog, ProgressLogger};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A message from a [`BackgroundLogger`] to its thread.
+enum Command {
+    /// Log the given count, observed at the given time, if it is time to.
+    Log { count: usize, now: Instant },
+    /// Set the given count and call [`done`](ProgressLog::done).
+    Done { count: usize },
+    /// Set the given count and stop the thread.
+    Stop { count: usize },
+}
+
+/**
+
+A handle to a [`ProgressLogger`] owned by a background thread, which does all
+the work of logging.
+
+Refreshing the system information, formatting and emitting a progress line
+inside an update method can stall latency-sensitive loops. The update methods
+of this handle just increase a local count and, when it is time to log, send a
+tiny snapshot (the count and the current time) through a bounded queue to the
+thread, which refreshes, formats and emits the progress line. The update
+methods never block: if the queue is full, the snapshot is discarded, which
+is harmless as the count is cumulative.
+
+The time and the count of the next log are computed by the thread, as usual,
+and published to the handle through atomic variables; the thread then decides
+whether to log exactly as the logger would, honoring, for example,
+[`min_items_per_log`](ProgressLogger::min_items_per_log). Note that the count of the logger is
+set by the handle, so [counters](ProgressLogger::counter) attached to the
+logger must not be used.
+
+The thread is stopped when calling [`stop`](#method.stop) or
+[`done`](#method.done), or when the handle is dropped.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+
+let mut pl = ProgressLogger::default();
+pl.item_name("packet");
+pl.start("Forwarding packets...");
+let mut pl = pl.spawn_background(16);
+for _ in 0..1000 {
+    // forward a packet
+    pl.update();
+}
+pl.done();
+```
+
+*/
+pub struct BackgroundLogger {
+    /// The current count.
+    count: usize,
+    /// The sampling period of the logger.
+    sampling: usize,
+    /// The clock of the logger, if any.
+    clock: Option<Arc<dyn Clock>>,
+    /// The time of the next log, in nanoseconds from [`epoch`](#structfield.epoch).
+    next_log_time: Arc<AtomicU64>,
+    /// The origin of [`next_log_time`](#structfield.next_log_time).
+    epoch: Instant,
+    /// The count of the next log regardless of time, or [`usize::MAX`].
+    next_log_count: Arc<AtomicUsize>,
+    /// The sending side of the queue.
+    sender: SyncSender<Command>,
+    /// The thread logging progress, returning the logger when stopped.
+    thread: Option<JoinHandle<ProgressLogger>>,
+}
+
+impl BackgroundLogger {
+    /// Move the given logger into a new background thread, using a queue of
+    /// the given capacity (at least one).
+    pub fn new(mut pl: ProgressLogger, capacity: usize) -> Self {
+        let (sender, receiver) = sync_channel(capacity.max(1));
+        let epoch = pl.now();
+        let next_log_time = Arc::new(AtomicU64::new(u64::MAX));
+        let next_log_count = Arc::new(AtomicUsize::new(usize::MAX));
+        publish(
+            &pl,
+            pl.next_log_time,
+            epoch,
+            &next_log_time,
+            &next_log_count,
+        );
+        let count = pl.count;
+        let sampling = pl.sampling;
+        let clock = pl.clock.clone();
+        let thread = {
+            let next_log_time = next_log_time.clone();
+            let next_log_count = next_log_count.clone();
+            thread::spawn(move || {
+                for command in receiver {
+                    match command {
+                        Command::Log { count, now } => {
+                            pl.count = count;
+                            pl.log_if_at(now);
+                            // If the log has been deferred (e.g., because of
+                            // min_items_per_log), check again later
+                            let check_time = pl.next_check_time(now);
+                            publish(&pl, check_time, epoch, &next_log_time, &next_log_count);
+                        }
+                        Command::Done { count } => {
+                            pl.count = count;
+                            pl.done_with_count(count);
+                            break;
+                        }
+                        Command::Stop { count } => {
+                            pl.count = count;
+                            break;
+                        }
+                    }
+                }
+                pl
+            })
+        };
+        Self {
+            count,
+            sampling,
+            clock,
+            next_log_time,
+            epoch,
+            next_log_count,
+            sender,
+            thread: Some(thread),
+        }
+    }
+
+    /// Return the current count.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Increase the count by the [sampling period](ProgressLogger::sampling)
+    /// of the logger (usually one) and check whether it is time to log.
+    #[inline]
+    pub fn update(&mut self) {
+        self.update_with_count(self.sampling);
+    }
+
+    /// Increase the count by the given amount and check whether it is time to log.
+    #[inline]
+    pub fn update_with_count(&mut self, count: usize) {
+        self.count = self.count.saturating_add(count);
+        self.log_if();
+    }
+
+    /// Increase the count and, once every
+    /// [`LIGHT_UPDATE_MASK`](ProgressLogger::LIGHT_UPDATE_MASK) + 1 calls,
+    /// check whether it is time to log.
+    #[inline]
+    pub fn light_update(&mut self) {
+        self.count = self.count.saturating_add(1);
+        if (self.count & ProgressLogger::LIGHT_UPDATE_MASK) == 0 {
+            self.log_if();
+        }
+    }
+
+    /// Increase the count and, once every `MASK` + 1 calls, check whether it
+    /// is time to log.
+    ///
+    /// See [`ProgressLog::light_update_with`].
+    #[inline]
+    pub fn light_update_with<const MASK: usize>(&mut self) {
+        self.count = self.count.saturating_add(1);
+        if (self.count & MASK) == 0 {
+            self.log_if();
+        }
+    }
+
+    /// Stop the thread, call [`done`](ProgressLog::done) on the logger, and
+    /// wait for the final stats to be logged.
+    pub fn done(mut self) {
+        // If the thread has terminated, there is nothing to do
+        let _ = self.sender.send(Command::Done { count: self.count });
+        self.join();
+    }
+
+    /// Stop the thread and return the logger.
+    ///
+    /// # Panics
+    ///
+    /// If the thread panicked (e.g., in a logging backend), the panic is
+    /// propagated.
+    pub fn stop(mut self) -> ProgressLogger {
+        let _ = self.sender.send(Command::Stop { count: self.count });
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(pl)) => pl,
+            Some(Err(payload)) => std::panic::resume_unwind(payload),
+            // The thread is joined only by stop, done and drop
+            None => unreachable!(),
+        }
+    }
+
+    /// Send a snapshot to the thread if it is time to log.
+    #[inline]
+    fn log_if(&mut self) {
+        let now = match &self.clock {
+            Some(clock) => clock.now(),
+            None => Instant::now(),
+        };
+        let nanos = self.next_log_time.load(Ordering::Relaxed);
+        let next_log_count = self.next_log_count.load(Ordering::Relaxed);
+        let next_log_time = self.epoch.checked_add(Duration::from_nanos(nanos));
+        if next_log_time.is_some_and(|next_log_time| next_log_time <= now)
+            || self.count >= next_log_count
+        {
+            // Push the next check forward, so that we do not flood the queue
+            // until the thread publishes the time and the count of the next
+            // log
+            self.next_log_time.store(u64::MAX, Ordering::Relaxed);
+            self.next_log_count.store(usize::MAX, Ordering::Relaxed);
+            let command = Command::Log {
+                count: self.count,
+                now,
+            };
+            if self.sender.try_send(command).is_err() {
+                // The queue is full: we will try again at the next update
+                let _ = self.next_log_time.compare_exchange(
+                    u64::MAX,
+                    nanos,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+                let _ = self.next_log_count.compare_exchange(
+                    usize::MAX,
+                    next_log_count,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+            }
+        }
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            // The thread does not panic, except in logging backends
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for BackgroundLogger {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Command::Stop { count: self.count });
+        self.join();
+    }
+}
+
+/// Publish to the handle the given time at which it should check again
+/// whether it is time to log, and the count of the next log.
+///
+/// Updates cannot cause a log unless the logger is running.
+fn publish(
+    pl: &ProgressLogger,
+    check_time: Instant,
+    epoch: Instant,
+    next_log_time: &AtomicU64,
+    next_log_count: &AtomicUsize,
+) {
+    let (time, count) = if pl.is_running() {
+        (nanos_since(epoch, check_time), pl.next_log_count)
+    } else {
+        (u64::MAX, usize::MAX)
+    };
+    next_log_time.store(time, Ordering::Relaxed);
+    next_log_count.store(count, Ordering::Relaxed);
+}
+
+impl ProgressLogger {
+    /// Move this logger into a new [`BackgroundLogger`] with a queue of the
+    /// given capacity.
+    pub fn spawn_background(self, capacity: usize) -> BackgroundLogger {
+        BackgroundLogger::new(self, capacity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::testing::CapturedLogs;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[test]
+    fn test_background() {
+        let clock = ManualClock::new();
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone())
+            .log_interval(Duration::from_secs(10));
+        pl.start("");
+        let mut pl = pl.spawn_background(4);
+        for _ in 0..10 {
+            clock.advance(Duration::from_secs(5));
+            pl.update_with_count(10);
+            // Wait for the thread to publish the time of the next log
+            while pl.next_log_time.load(Ordering::Relaxed) == u64::MAX {
+                std::thread::yield_now();
+            }
+        }
+        assert_eq!(pl.count(), 100);
+        let pl = pl.stop();
+        assert_eq!(pl.count(), 100);
+        assert_eq!(logs.progress_lines(), 5);
+
+        let mut pl = pl.spawn_background(1);
+        pl.update();
+        pl.done();
+        assert_eq!(logs.final_count(), Some(101));
+    }
+
+    #[test]
+    fn test_background_panic() {
+        let mut pl = ProgressLogger::default();
+        pl.before_log(|_| panic!("backend failure"))
+            .log_interval(Duration::ZERO);
+        pl.start("");
+        let mut pl = pl.spawn_background(1);
+        pl.update();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pl.stop()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_background_min_items_per_log() {
+        let clock = ManualClock::new();
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .add_sink(logs.clone())
+            .log_interval(Duration::from_secs(10))
+            .min_items_per_log(100);
+        let idle = ProgressLogger::default().spawn_background(1);
+        assert_eq!(idle.next_log_time.load(Ordering::Relaxed), u64::MAX);
+        pl.start("");
+        let mut pl = pl.spawn_background(4);
+        for _ in 0..2 {
+            clock.advance(Duration::from_secs(10));
+            pl.update_with_count(50);
+            while pl.next_log_time.load(Ordering::Relaxed) == u64::MAX {
+                std::thread::yield_now();
+            }
+        }
+        let pl = pl.stop();
+        assert_eq!(logs.progress_lines(), 1);
+        assert_eq!(pl.count(), 100);
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{Record, RecordKind, Sample, Sink};
+use log::Level;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// An event sent by a [`ChannelSink`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// The logger has been started.
+    Started {
+        /// The name of an item.
+        item_name: String,
+        /// The wall-clock time at which the logger was started.
+        time: SystemTime,
+    },
+    /// The logger has logged its progress.
+    Progress {
+        /// The number of items.
+        count: usize,
+        /// The elapsed time since the logger was started.
+        elapsed: Duration,
+        /// The average speed in items per second, if defined.
+        speed: Option<f64>,
+        /// The speed in items per second during the last log interval, if
+        /// defined.
+        interval_speed: Option<f64>,
+        /// The expected number of updates, if known.
+        expected_updates: Option<usize>,
+        /// The estimated time to completion, if known.
+        time_to_end: Option<Duration>,
+        /// The resident memory of the process in bytes, if available.
+        memory: Option<u64>,
+    },
+    /// The logger is done.
+    Done {
+        /// The number of items.
+        count: usize,
+        /// The elapsed time between start and stop.
+        elapsed: Duration,
+        /// The average speed in items per second, if defined.
+        speed: Option<f64>,
+    },
+    /// A message has been passed to [`info`](crate::ProgressLog::info).
+    Message {
+        /// The level of the message.
+        level: Level,
+        /// The text of the message.
+        text: String,
+    },
+}
+
+/// A sender of [`ProgressEvent`]s, such as the sending side of a channel.
+///
+/// Senders must never block: events that cannot be sent (e.g., because the
+/// receiver has been dropped, or because a bounded channel is full) are
+/// discarded.
+pub trait EventSender: Send + Sync {
+    /// Send an event.
+    fn send_event(&self, event: ProgressEvent);
+}
+
+impl EventSender for mpsc::Sender<ProgressEvent> {
+    fn send_event(&self, event: ProgressEvent) {
+        let _ = self.send(event);
+    }
+}
+
+impl EventSender for mpsc::SyncSender<ProgressEvent> {
+    fn send_event(&self, event: ProgressEvent) {
+        let _ = self.try_send(event);
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl EventSender for crossbeam_channel::Sender<ProgressEvent> {
+    fn send_event(&self, event: ProgressEvent) {
+        let _ = self.try_send(event);
+    }
+}
+
+/**
+
+A [`Sink`] pushing typed [`ProgressEvent`]s into a channel.
+
+Graphical frontends and orchestrators can consume the events on their own
+thread, without parsing text or receiving callbacks from foreign threads. The
+sink accepts any [`EventSender`]: [`std::sync::mpsc`] senders are supported
+out of the box, and `crossbeam-channel` senders are supported with the
+`crossbeam` feature.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::{ChannelSink, ProgressEvent};
+use std::sync::mpsc;
+
+let (tx, rx) = mpsc::channel();
+let mut pl = ProgressLogger::default();
+pl.add_sink(ChannelSink::new(tx));
+pl.start("Smashing pumpkins...");
+pl.update_with_count(100);
+pl.done();
+assert!(matches!(
+    rx.try_iter().last(),
+    Some(ProgressEvent::Done { count: 100, .. })
+));
+```
+
+*/
+#[derive(Debug)]
+pub struct ChannelSink<S> {
+    sender: S,
+}
+
+impl<S: EventSender> ChannelSink<S> {
+    /// Create a new sink sending events through the given sender.
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+impl<S: EventSender> Sink for ChannelSink<S> {
+    fn emit(&self, record: &Record<'_>) {
+        if record.kind == RecordKind::Info {
+            self.sender.send_event(ProgressEvent::Message {
+                level: record.level,
+                text: record.args.to_string(),
+            });
+        }
+    }
+
+    fn sample(&self, sample: &Sample<'_>) {
+        self.sender.send_event(match sample.kind {
+            RecordKind::Start => ProgressEvent::Started {
+                item_name: sample.item_name.to_owned(),
+                time: sample.time,
+            },
+            RecordKind::Summary => ProgressEvent::Done {
+                count: sample.count,
+                elapsed: sample.elapsed,
+                speed: sample.speed,
+            },
+            _ => ProgressEvent::Progress {
+                count: sample.count,
+                elapsed: sample.elapsed,
+                speed: sample.speed,
+                interval_speed: sample.interval_speed,
+                expected_updates: sample.expected_updates,
+                time_to_end: sample.time_to_end,
+                memory: sample.memory,
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_events() {
+        let clock = ManualClock::new();
+        let (tx, rx) = mpsc::channel();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .expected_updates(Some(1000))
+            .add_sink(ChannelSink::new(tx));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(250);
+        pl.info(format_args!("Halfway"));
+        clock.advance(Duration::from_secs(10));
+        pl.done_with_count(500);
+        drop(pl);
+
+        let events: Vec<_> = rx.iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                ProgressEvent::Started {
+                    item_name: "item".to_owned(),
+                    time: std::time::UNIX_EPOCH,
+                },
+                ProgressEvent::Progress {
+                    count: 250,
+                    elapsed: Duration::from_secs(10),
+                    speed: Some(25.0),
+                    interval_speed: Some(25.0),
+                    expected_updates: Some(1000),
+                    time_to_end: Some(Duration::from_millis(29_880)),
+                    memory: None,
+                },
+                ProgressEvent::Message {
+                    level: Level::Info,
+                    text: "Halfway".to_owned(),
+                },
+                ProgressEvent::Done {
+                    count: 500,
+                    elapsed: Duration::from_secs(20),
+                    speed: Some(25.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_channel() {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(ChannelSink::new(tx));
+        pl.start("");
+        pl.info(format_args!("Dropped"));
+        pl.done();
+        drop(pl);
+        assert_eq!(rx.iter().count(), 1);
+    }
+}
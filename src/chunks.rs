@@ -0,0 +1,105 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLog;
+use std::iter::FusedIterator;
+use std::slice::Chunks;
+
+/**
+
+An iterator over the chunks of a slice updating a logger with the length of
+each chunk.
+
+A chunk is counted when the next chunk is requested, that is, after it has
+been processed; the last chunk processed is counted when the iterator is
+exhausted or dropped.
+
+See [`ProgressLog::chunks`].
+
+*/
+pub struct ProgressChunks<'a, 'b, T, P: ProgressLog> {
+    chunks: Chunks<'a, T>,
+    pl: &'b mut P,
+    /// The length of the last chunk returned, not yet counted.
+    pending: usize,
+}
+
+impl<'a, 'b, T, P: ProgressLog> ProgressChunks<'a, 'b, T, P> {
+    pub(crate) fn new(pl: &'b mut P, slice: &'a [T], chunk_size: usize) -> Self {
+        Self {
+            chunks: slice.chunks(chunk_size),
+            pl,
+            pending: 0,
+        }
+    }
+
+    /// Update the logger with the length of the last chunk returned.
+    fn flush(&mut self) {
+        if self.pending != 0 {
+            self.pl.update_with_count(self.pending);
+            self.pending = 0;
+        }
+    }
+}
+
+impl<'a, T, P: ProgressLog> Iterator for ProgressChunks<'a, '_, T, P> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        self.flush();
+        let chunk = self.chunks.next()?;
+        self.pending = chunk.len();
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<T, P: ProgressLog> ExactSizeIterator for ProgressChunks<'_, '_, T, P> {}
+
+impl<T, P: ProgressLog> FusedIterator for ProgressChunks<'_, '_, T, P> {}
+
+impl<T, P: ProgressLog> Drop for ProgressChunks<'_, '_, T, P> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::testing::CapturedLogs;
+
+    #[test]
+    fn test_chunks() {
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        let data: Vec<usize> = (0..1000).collect();
+        pl.start("");
+        let mut chunks = pl.chunks(&data, 300);
+        assert_eq!(chunks.len(), 4);
+        let mut sum = 0;
+        for chunk in &mut chunks {
+            sum += chunk.iter().sum::<usize>();
+        }
+        drop(chunks);
+        assert_eq!(sum, 499_500);
+        assert_eq!(pl.count(), 1000);
+
+        // Chunks processed before a break are counted
+        for (i, _) in pl.chunks(&data, 300).enumerate() {
+            if i == 1 {
+                break;
+            }
+        }
+        pl.done();
+        logs.assert_final_count(1600);
+    }
+}
@@ -0,0 +1,191 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+/**
+
+A source of time for a [`ProgressLogger`](crate::ProgressLogger).
+
+By default, a logger uses [`Instant::now`]; a different clock can be set with
+[`ProgressLogger::clock`](crate::ProgressLogger::clock).
+
+*/
+pub trait Clock: Send + Sync {
+    /// Return the current time.
+    fn now(&self) -> Instant;
+
+    /// Return the current wall-clock time.
+    ///
+    /// The default implementation returns [`SystemTime::now`].
+    fn system_now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// The system monotonic clock, that is, [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/**
+
+A coarse clock returning a time cached by a ticker thread.
+
+Reading the time of this clock is a single atomic load, which is much cheaper
+than [`Instant::now`]; the price is that the time advances only once per
+resolution, as a coarse monotonic clock (e.g., `CLOCK_MONOTONIC_COARSE` on
+Linux) would. It is useful for workloads calling
+[`update`](crate::ProgressLog::update) hundreds of millions of times, as the
+interval check becomes negligible, and the resolution needs only to be small
+with respect to the [log interval](crate::ProgressLog::log_interval).
+
+Clones of a coarse clock share the same ticker thread, which stops when the
+last clone is dropped.
+
+# Examples
+
+```rust
+# fn main() -> std::io::Result<()> {
+use dsi_progress_logger::prelude::*;
+use std::time::Duration;
+
+let mut pl = ProgressLogger::default();
+pl.clock(CoarseClock::new(Duration::from_millis(10))?);
+pl.start("Hashing...");
+for _ in 0..1000 {
+    pl.update();
+}
+pl.done();
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug, Clone)]
+pub struct CoarseClock(Arc<CoarseTime>);
+
+/// The state shared between the clones of a [`CoarseClock`] and its ticker.
+#[derive(Debug)]
+struct CoarseTime {
+    /// The time at which the clock was created.
+    base: Instant,
+    /// The nanoseconds elapsed since `base` at the last tick.
+    nanos: AtomicU64,
+}
+
+impl CoarseClock {
+    /// The minimum resolution of a coarse clock.
+    pub const MIN_RESOLUTION: Duration = Duration::from_millis(1);
+
+    /// Create a new coarse clock whose time is updated with the given
+    /// resolution, which is clamped to at least
+    /// [`MIN_RESOLUTION`](CoarseClock::MIN_RESOLUTION).
+    ///
+    /// Returns an error if the ticker thread cannot be spawned.
+    pub fn new(resolution: Duration) -> io::Result<Self> {
+        let resolution = resolution.max(Self::MIN_RESOLUTION);
+        let time = Arc::new(CoarseTime {
+            base: Instant::now(),
+            nanos: AtomicU64::new(0),
+        });
+        let weak: Weak<CoarseTime> = Arc::downgrade(&time);
+        std::thread::Builder::new()
+            .name("coarse-clock".into())
+            .spawn(move || loop {
+                std::thread::sleep(resolution);
+                let Some(time) = weak.upgrade() else {
+                    break;
+                };
+                let nanos = u64::try_from(time.base.elapsed().as_nanos()).unwrap_or(u64::MAX);
+                time.nanos.store(nanos, Ordering::Relaxed);
+            })?;
+        Ok(Self(time))
+    }
+}
+
+impl Clock for CoarseClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        self.0.base + Duration::from_nanos(self.0.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/**
+
+A clock that advances only when requested.
+
+Clones of a manual clock share the same time, so you can keep a clone to
+[`advance`](#method.advance) the time of a logger using the clock. The
+wall-clock time of a manual clock starts at the [Unix epoch](SystemTime::UNIX_EPOCH)
+and advances together with its monotonic time. Paired with
+[deterministic mode](crate::ProgressLogger::deterministic), it makes the output
+of a logger reproducible.
+
+*/
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<(Instant, SystemTime)>>);
+
+impl ManualClock {
+    /// Create a new manual clock starting at the current time.
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new((
+            Instant::now(),
+            SystemTime::UNIX_EPOCH,
+        ))))
+    }
+
+    /// Advance the clock by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if let (Some(instant), Some(system_time)) =
+            (now.0.checked_add(duration), now.1.checked_add(duration))
+        {
+            *now = (instant, system_time);
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).0
+    }
+
+    fn system_now(&self) -> SystemTime {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_coarse_clock() {
+        let clock = CoarseClock::new(Duration::ZERO).unwrap();
+        let start = clock.now();
+        std::thread::sleep(Duration::from_millis(50));
+        let now = clock.now();
+        assert!(now > start);
+        assert!(now <= Instant::now());
+    }
+}
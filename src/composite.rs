@@ -0,0 +1,244 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger, RecordKind};
+use log::Level;
+use std::fmt::{Display, Formatter, Result};
+use std::time::Duration;
+
+/// A subtask of a [`CompositeProgress`].
+#[derive(Debug, Clone)]
+struct Subtask {
+    /// The name of the subtask.
+    name: String,
+    /// The weight of the subtask.
+    weight: f64,
+    /// The completed fraction of the subtask, between 0 and 1.
+    fraction: f64,
+}
+
+/**
+
+Progress of an activity made of weighted subtasks.
+
+Each subtask is [registered](#method.subtask) with a weight representing its
+share of the whole activity (weights are normalized, so they do not need to
+sum to one), and reports the fraction of its own work that has been completed.
+The logger displays the overall percentage of completion and an estimate of
+the time to completion, solving the problem of activities made of phases of
+very different lengths.
+
+Timing, output and configuration are handled by an underlying
+[`ProgressLogger`], accessible through [`logger`](#method.logger).
+
+# Example
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+
+stderrlog::new().verbosity(2).init()?;
+let mut cp = CompositeProgress::new();
+let read = cp.subtask("read", 0.2);
+let sort = cp.subtask("sort", 0.7);
+let write = cp.subtask("write", 0.1);
+cp.start("Sorting pumpkins...");
+for i in 1..=100 {
+    cp.set_fraction(read, i as f64 / 100.0);
+}
+for i in 1..=100 {
+    cp.set_fraction(sort, i as f64 / 100.0);
+}
+for i in 1..=100 {
+    cp.set_fraction(write, i as f64 / 100.0);
+}
+cp.done();
+#     Ok(())
+# }
+```
+
+*/
+pub struct CompositeProgress {
+    /// The underlying logger.
+    pl: ProgressLogger,
+    /// The subtasks.
+    subtasks: Vec<Subtask>,
+    /// The index of the subtask that was updated last, if any.
+    current: Option<usize>,
+}
+
+impl Default for CompositeProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompositeProgress {
+    /// Create a new composite logger with no subtasks.
+    pub fn new() -> Self {
+        Self {
+            pl: ProgressLogger::default(),
+            subtasks: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Return the underlying logger, for configuration.
+    pub fn logger(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+
+    /// Register a subtask with the given name and weight, returning its index.
+    ///
+    /// Negative or non-finite weights are treated as zero.
+    pub fn subtask(&mut self, name: impl AsRef<str>, weight: f64) -> usize {
+        self.subtasks.push(Subtask {
+            name: name.as_ref().into(),
+            weight: if weight.is_finite() {
+                weight.max(0.0)
+            } else {
+                0.0
+            },
+            fraction: 0.0,
+        });
+        self.subtasks.len() - 1
+    }
+
+    /// Start the composite logger, displaying the given message.
+    ///
+    /// The fractions of all subtasks are reset to zero.
+    pub fn start(&mut self, msg: impl AsRef<str>) {
+        for subtask in &mut self.subtasks {
+            subtask.fraction = 0.0;
+        }
+        self.current = None;
+        self.pl.start(msg);
+    }
+
+    /// Set the completed fraction of a subtask and check whether it is time to log.
+    ///
+    /// The fraction is clamped between 0 and 1.
+    ///
+    /// # Panics
+    ///
+    /// If `subtask` is not the index of a registered subtask.
+    pub fn set_fraction(&mut self, subtask: usize, fraction: f64) {
+        self.subtasks[subtask].fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        self.current = Some(subtask);
+        if self.pl.should_log() {
+            self.log_now();
+        }
+    }
+
+    /// Force a log.
+    pub fn log_now(&mut self) {
+        let now = self.pl.now();
+        self.pl
+            .emit(Level::Info, RecordKind::Progress, format_args!("{}", self));
+        self.pl.last_log_time = now;
+        self.pl.next_log_time = self.pl.next_log_time_from(now);
+    }
+
+    /// Return the overall completed fraction, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        let total: f64 = self.subtasks.iter().map(|subtask| subtask.weight).sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.subtasks
+            .iter()
+            .map(|subtask| subtask.weight * subtask.fraction)
+            .sum::<f64>()
+            / total
+    }
+
+    /// Return the estimated time to completion, if the overall completed
+    /// fraction is positive.
+    pub fn time_to_end(&self) -> Option<Duration> {
+        let fraction = self.fraction();
+        let elapsed = self.pl.elapsed()?;
+        if fraction <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(elapsed.as_secs_f64() * (1.0 - fraction) / fraction).ok()
+    }
+
+    /// Stop the composite logger, print the [completion
+    /// message](crate::Messages::completed), and display the elapsed time.
+    pub fn done(&mut self) {
+        self.pl.stop();
+        if self.pl.completed_message {
+            self.pl.emit(
+                self.pl.done_level,
+                RecordKind::Completed,
+                format_args!("{}", self.pl.messages.completed),
+            );
+        }
+        if self.pl.done_summary {
+            if let Some(elapsed) = self.pl.elapsed() {
+                self.pl.emit(
+                    self.pl.done_level,
+                    RecordKind::Summary,
+                    format_args!(
+                        "{}: {}",
+                        self.pl.messages.elapsed,
+                        self.pl.fmt_duration(elapsed)
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl Display for CompositeProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.pl.start_time.is_none() {
+            return write!(f, "CompositeProgress not started");
+        }
+        let messages = &self.pl.messages;
+        write!(f, "{:.2}% {}", 100.0 * self.fraction(), messages.done)?;
+        match self.time_to_end() {
+            Some(time_to_end) => write!(
+                f,
+                ", {} {}",
+                self.pl.fmt_duration(time_to_end),
+                messages.to_end
+            )?,
+            None => write!(f, ", n/a {}", messages.to_end)?,
+        }
+        if let Some(subtask) = self.current.map(|current| &self.subtasks[current]) {
+            write!(f, "; {} {:.2}%", subtask.name, 100.0 * subtask.fraction)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ManualClock;
+
+    #[test]
+    fn test_composite() {
+        let clock = ManualClock::new();
+        let mut cp = CompositeProgress::new();
+        cp.logger().clock(clock.clone());
+        let a = cp.subtask("a", 1.0);
+        let b = cp.subtask("b", 3.0);
+        cp.start("");
+        cp.set_fraction(a, 1.0);
+        assert_eq!(cp.fraction(), 0.25);
+        clock.advance(Duration::from_secs(10));
+        cp.set_fraction(b, 0.5);
+        assert_eq!(cp.fraction(), 0.625);
+        assert_eq!(cp.time_to_end(), Some(Duration::from_secs(6)));
+        assert_eq!(cp.to_string(), "62.50% done, 6s to end; b 50.00%");
+    }
+}
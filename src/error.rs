@@ -0,0 +1,93 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/**
+
+An error of this crate.
+
+Logging never panics and never interrupts the instrumented code: errors
+happening while [sinks](crate::Sink) write their output are recorded, and can
+be retrieved later with
+[`ProgressLogger::check_sinks`](crate::ProgressLogger::check_sinks).
+
+*/
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProgressLoggerError {
+    /// An I/O error.
+    Io(io::Error),
+    /// An invalid or unsupported URL.
+    InvalidUrl(String),
+    /// An error of the NVIDIA Management Library.
+    #[cfg(feature = "nvml")]
+    Nvml(nvml_wrapper::error::NvmlError),
+    /// An SQLite error.
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+impl Display for ProgressLoggerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgressLoggerError::Io(error) => write!(f, "I/O error: {}", error),
+            ProgressLoggerError::InvalidUrl(url) => write!(f, "invalid URL: {}", url),
+            #[cfg(feature = "nvml")]
+            ProgressLoggerError::Nvml(error) => write!(f, "NVML error: {}", error),
+            #[cfg(feature = "sqlite")]
+            ProgressLoggerError::Sqlite(error) => write!(f, "SQLite error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ProgressLoggerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProgressLoggerError::Io(error) => Some(error),
+            ProgressLoggerError::InvalidUrl(_) => None,
+            #[cfg(feature = "nvml")]
+            ProgressLoggerError::Nvml(error) => Some(error),
+            #[cfg(feature = "sqlite")]
+            ProgressLoggerError::Sqlite(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for ProgressLoggerError {
+    fn from(error: io::Error) -> Self {
+        ProgressLoggerError::Io(error)
+    }
+}
+
+#[cfg(feature = "nvml")]
+impl From<nvml_wrapper::error::NvmlError> for ProgressLoggerError {
+    fn from(error: nvml_wrapper::error::NvmlError) -> Self {
+        ProgressLoggerError::Nvml(error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ProgressLoggerError {
+    fn from(error: rusqlite::Error) -> Self {
+        ProgressLoggerError::Sqlite(error)
+    }
+}
+
+impl From<ProgressLoggerError> for io::Error {
+    fn from(error: ProgressLoggerError) -> Self {
+        match error {
+            ProgressLoggerError::Io(error) => error,
+            ProgressLoggerError::InvalidUrl(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, error)
+            }
+            #[allow(unreachable_patterns)]
+            _ => io::Error::other(error),
+        }
+    }
+}
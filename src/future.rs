@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+/// The minimum time between two logs of a wrapped future.
+const MIN_WAIT: Duration = Duration::from_millis(1);
+
+impl ProgressLogger {
+    /// Await the given future, logging at each interval while it is pending
+    /// and displaying the final stats when it resolves.
+    ///
+    /// If the logger has not been started, it is started with no message.
+    /// This method requires the `tokio` feature, as the timer of the
+    /// [`tokio`] runtime is used to wake up the future at each interval.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let runtime = tokio::runtime::Builder::new_current_thread()
+    ///     .enable_time()
+    ///     .build()?;
+    /// let rows = runtime.block_on(async {
+    ///     let mut pl = ProgressLogger::default();
+    ///     pl.log_interval(Duration::from_millis(10));
+    ///     pl.start("Running a huge query...");
+    ///     pl.wrap_future(async {
+    ///         tokio::time::sleep(Duration::from_millis(50)).await;
+    ///         42
+    ///     })
+    ///     .await
+    /// });
+    /// assert_eq!(rows, 42);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn wrap_future<F: Future>(&mut self, fut: F) -> F::Output {
+        if self.start_time.is_none() || self.stop_time.is_some() {
+            self.start("");
+        }
+        let mut fut = pin!(fut);
+        let mut sleep = pin!(tokio::time::sleep(self.time_to_next_log().max(MIN_WAIT)));
+        let output = poll_fn(|cx| {
+            if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            if sleep.as_mut().poll(cx).is_ready() {
+                let now = self.now();
+                self.log(now);
+                // The sleep is measured with the timer of the runtime, so we
+                // convert the time to the next log, which is measured with
+                // the clock of the logger, into a deadline of the runtime
+                sleep
+                    .as_mut()
+                    .reset(tokio::time::Instant::now() + self.time_to_next_log().max(MIN_WAIT));
+                if sleep.as_mut().poll(cx).is_ready() {
+                    cx.waker().wake_by_ref();
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+        self.done();
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_zero_interval() -> Result<(), Box<dyn std::error::Error>> {
+        let logs = crate::testing::CapturedLogs::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone()).log_interval(Duration::ZERO);
+        pl.start("");
+        let output = runtime.block_on(pl.wrap_future(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            42
+        }));
+        assert_eq!(output, 42);
+        assert!(logs.progress_lines() >= 1);
+        assert!(logs.progress_lines() <= 25);
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+//! A property-based harness checking that formatting a logger never panics,
+//! whatever its internal state.
+
+use crate::report::DetailedReport;
+use crate::{Clock, ManualClock, ProgressLogger, TimeUnit};
+use proptest::prelude::*;
+use std::time::{Duration, Instant};
+
+/// Return an instant at the given signed offset, in milliseconds, from the
+/// given base, or the base if the result cannot be represented.
+fn offset(base: Instant, millis: i64) -> Instant {
+    let delta = Duration::from_millis(millis.unsigned_abs());
+    if millis >= 0 {
+        base.checked_add(delta)
+    } else {
+        base.checked_sub(delta)
+    }
+    .unwrap_or(base)
+}
+
+/// Counts, with a bias towards extreme values.
+fn count() -> impl Strategy<Value = usize> {
+    prop_oneof![
+        Just(0),
+        Just(1),
+        Just(usize::MAX),
+        Just(usize::MAX - 1),
+        0..1000_usize,
+        any::<usize>(),
+    ]
+}
+
+/// Durations, with a bias towards extreme values.
+fn duration() -> impl Strategy<Value = Duration> {
+    prop_oneof![
+        Just(Duration::ZERO),
+        Just(Duration::MAX),
+        Just(Duration::from_nanos(1)),
+        (0..100_000_u64).prop_map(Duration::from_millis),
+        any::<u64>().prop_map(Duration::from_nanos),
+        any::<u64>().prop_map(Duration::from_secs),
+    ]
+}
+
+/// Floating-point values, with a bias towards special values.
+fn float() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        Just(0.0),
+        Just(-0.0),
+        Just(1.0),
+        Just(f64::NAN),
+        Just(f64::INFINITY),
+        Just(f64::NEG_INFINITY),
+        Just(f64::MIN_POSITIVE),
+        Just(f64::MAX),
+        -2.0..2.0_f64,
+        any::<f64>(),
+    ]
+}
+
+/// Signed offsets in milliseconds, with a bias towards extreme values.
+fn millis() -> impl Strategy<Value = i64> {
+    prop_oneof![
+        Just(0),
+        Just(i64::MAX),
+        Just(i64::MIN),
+        -100_000..100_000_i64,
+        any::<i64>(),
+    ]
+}
+
+fn time_unit() -> impl Strategy<Value = Option<TimeUnit>> {
+    prop_oneof![
+        Just(None),
+        prop::sample::select(TimeUnit::VALUES.to_vec()).prop_map(Some),
+        float().prop_map(|seconds| Some(TimeUnit::Custom {
+            label: "shift",
+            seconds
+        })),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn test_display_never_panics(
+        (count, last_count, expected_updates, produced, in_flight, retries) in (
+            count(),
+            count(),
+            prop::option::of(count()),
+            prop::option::of(count()),
+            prop::option::of(count()),
+            count(),
+        ),
+        (start, stop, last_log, now, round_start) in (
+            millis(),
+            prop::option::of(millis()),
+            millis(),
+            0..i64::MAX,
+            prop::option::of(millis()),
+        ),
+        (log_interval, expected_duration, backoff) in (
+            duration(),
+            prop::option::of(duration()),
+            prop::option::of(duration()),
+        ),
+        (completed_fraction, smoothed_local_speed, local_speed_smoothing, peak_rate, metric) in (
+            prop::option::of(float()),
+            prop::option::of(float()),
+            float(),
+            prop::option::of(float()),
+            prop::option::of((count(), float())),
+        ),
+        (anomaly_factor, interval_speeds, prev_metric, threshold) in (
+            prop::option::of(float()),
+            prop::collection::vec(float(), 0..8),
+            prop::option::of(float()),
+            prop::option::of(float()),
+        ),
+        (time_unit, percent_precision, batches, round, rounds, sampling) in (
+            time_unit(),
+            any::<usize>(),
+            prop::option::of((count(), count())),
+            count(),
+            prop::option::of(count()),
+            count(),
+        ),
+        (raw_millis, local_speed, display_delta, reconciled) in (
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+        ),
+    ) {
+        let clock = ManualClock::new();
+        let base = clock.now();
+        clock.advance(Duration::from_millis(now as u64));
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .percent_precision(percent_precision);
+
+        pl.count = count;
+        pl.last_count = last_count;
+        pl.expected_updates = expected_updates;
+        pl.produced = produced;
+        pl.in_flight = in_flight;
+        pl.retries = retries;
+        pl.start_time = Some(offset(base, start));
+        pl.stop_time = stop.map(|stop| offset(base, stop));
+        pl.last_log_time = offset(base, last_log);
+        pl.round_start_time = round_start.map(|round_start| offset(base, round_start));
+        pl.log_interval = log_interval;
+        pl.expected_duration = expected_duration;
+        pl.backoff = backoff;
+        pl.completed_fraction = completed_fraction;
+        pl.smoothed_local_speed = smoothed_local_speed;
+        pl.local_speed_smoothing = local_speed_smoothing;
+        pl.peak_rate = peak_rate;
+        pl.metric = metric;
+        pl.anomaly_factor = anomaly_factor;
+        pl.interval_speeds = interval_speeds.into();
+        pl.prev_metric = prev_metric;
+        pl.convergence_threshold = threshold;
+        pl.time_unit = time_unit;
+        pl.batches = batches;
+        pl.round = round;
+        pl.rounds = rounds;
+        pl.sampling = sampling;
+        pl.raw_millis = raw_millis;
+        pl.local_speed = local_speed;
+        pl.display_delta = display_delta;
+        pl.reconciled = reconciled;
+
+        let _ = pl.to_string();
+        let _ = DetailedReport(&pl).to_string();
+        let _ = pl.stable_line().to_string();
+        let _ = format!("{:?}", pl);
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::humanize;
+use crate::Measure;
+use nvml_wrapper::error::NvmlError;
+use nvml_wrapper::Nvml;
+use std::sync::Arc;
+
+/**
+
+A [`Measure`] of the memory used and of the utilization of an NVIDIA GPU.
+
+The measure is displayed as `GPUn used/total mem, utilization`, where `n` is
+the index of the device. It is based on the NVIDIA Management Library, which
+is loaded dynamically when the measure is created, and it is available with
+the `nvml` feature.
+
+# Examples
+
+```no_run
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::GpuUsage;
+
+let mut pl = ProgressLogger::default();
+for gpu in GpuUsage::all()? {
+    pl.attach(gpu);
+}
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug)]
+pub struct GpuUsage {
+    nvml: Arc<Nvml>,
+    index: u32,
+    name: String,
+}
+
+impl GpuUsage {
+    /// Create a new measure for the device with the given index.
+    pub fn new(index: u32) -> Result<Self, NvmlError> {
+        Self::with_nvml(Arc::new(Nvml::init()?), index)
+    }
+
+    /// Create a measure for each device.
+    pub fn all() -> Result<Vec<Self>, NvmlError> {
+        let nvml = Arc::new(Nvml::init()?);
+        (0..nvml.device_count()?)
+            .map(|index| Self::with_nvml(nvml.clone(), index))
+            .collect()
+    }
+
+    fn with_nvml(nvml: Arc<Nvml>, index: u32) -> Result<Self, NvmlError> {
+        // Fail early if the device does not exist
+        nvml.device_by_index(index)?;
+        Ok(Self {
+            nvml,
+            index,
+            name: format!("GPU{}", index),
+        })
+    }
+}
+
+impl Measure for GpuUsage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        let device = self.nvml.device_by_index(self.index).ok()?;
+        let memory = device.memory_info().ok()?;
+        let utilization = device.utilization_rates().ok()?;
+        Some(format!(
+            "{}B/{}B mem, {}%",
+            humanize(memory.used as _),
+            humanize(memory.total as _),
+            utilization.gpu
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gpu_usage() {
+        // There might be no GPU or no driver on the test machine
+        if let Ok(gpus) = GpuUsage::all() {
+            for mut gpu in gpus {
+                assert!(gpu.name().starts_with("GPU"));
+                assert!(gpu.sample().is_some());
+            }
+        }
+    }
+}
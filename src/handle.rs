@@ -0,0 +1,434 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{Clock, ProgressLog, ProgressLogger};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
+
+/**
+
+A cloneable, [`Send`] and [`Sync`] handle wrapping a [`ProgressLogger`] in a
+mutex.
+
+The wrapped logger is unchanged, and its methods still require an exclusive
+reference, which is obtained through [`lock`](#method.lock). The handle
+provides, however, update methods taking a shared reference: since all clones
+of a handle refer to the same logger, a handle can be stored in shared
+structures, used from callbacks that only get a shared reference, or moved
+into threads or asynchronous tasks that need to report progress.
+
+The count is kept in the [counter](ProgressLogger::counter) of the logger, and
+the time and the count of the next log in atomic variables: thus, the update
+methods do not lock the logger, except when it is time to log. The time is measured with the
+[clock](ProgressLogger::clock) of the logger, and
+[`update`](#method.update) honors the [sampling
+period](ProgressLogger::sampling) of the logger.
+
+If the `tokio` feature is enabled,
+[`spawn_async_ticker`](#method.spawn_async_ticker) spawns an asynchronous task
+logging progress at each interval.
+
+# Example
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+
+stderrlog::new().verbosity(2).init()?;
+let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+pl.lock().item_name("pumpkin").start("Smashing pumpkins in parallel...");
+std::thread::scope(|s| {
+    for _ in 0..4 {
+        s.spawn(|| {
+            for _ in 0..100 {
+                // do something on each pumpkin
+                pl.update();
+            }
+        });
+    }
+});
+pl.done();
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Clone)]
+pub struct ProgressLoggerHandle(pub(crate) Arc<Inner>);
+
+pub(crate) struct Inner {
+    /// The logger.
+    pub(crate) pl: Mutex<ProgressLogger>,
+    /// The counter attached to the logger.
+    pub(crate) counter: Arc<AtomicUsize>,
+    /// The time of the next log, in nanoseconds from the epoch of the
+    /// [time base](#structfield.time_base).
+    next_log_time: AtomicU64,
+    /// The value of the [counter](#structfield.counter) at which the next
+    /// log will happen regardless of time, or [`usize::MAX`].
+    next_log_count: AtomicUsize,
+    /// The clock of the logger when the handle was created, and the origin
+    /// of [`next_log_time`](#structfield.next_log_time).
+    time_base: TimeBase,
+    /// The sampling period of the logger.
+    sampling: AtomicUsize,
+}
+
+/// The clock of a logger, and an instant of the clock used as origin.
+struct TimeBase {
+    clock: Option<Arc<dyn Clock>>,
+    epoch: Instant,
+}
+
+impl TimeBase {
+    fn new(pl: &ProgressLogger) -> Self {
+        Self {
+            clock: pl.clock.clone(),
+            epoch: pl.now(),
+        }
+    }
+
+    fn now(&self) -> Instant {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => Instant::now(),
+        }
+    }
+}
+
+impl ProgressLoggerHandle {
+    /// Create a new handle for the given logger.
+    ///
+    /// A [counter](ProgressLogger::counter) is attached to the logger, if
+    /// not already present.
+    pub fn new(mut pl: ProgressLogger) -> Self {
+        let counter = pl.counter();
+        let time_base = TimeBase::new(&pl);
+        let next_log_time = AtomicU64::new(if pl.is_running() {
+            nanos_since(time_base.epoch, pl.next_log_time)
+        } else {
+            u64::MAX
+        });
+        pl.sync_count();
+        let next_log_count = AtomicUsize::new(next_log_count(&pl));
+        Self(Arc::new(Inner {
+            counter,
+            next_log_time,
+            next_log_count,
+            time_base,
+            sampling: AtomicUsize::new(pl.sampling),
+            pl: Mutex::new(pl),
+        }))
+    }
+
+    /// Return a guard giving access to the logger.
+    pub fn lock(&self) -> ProgressLoggerGuard<'_> {
+        ProgressLoggerGuard {
+            guard: self.0.pl.lock().unwrap_or_else(PoisonError::into_inner),
+            inner: &self.0,
+        }
+    }
+
+    /// Return a guard giving access to the logger, or [`None`] if another
+    /// thread is holding the lock.
+    pub fn try_lock(&self) -> Option<ProgressLoggerGuard<'_>> {
+        let guard = match self.0.pl.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(ProgressLoggerGuard {
+            guard,
+            inner: &self.0,
+        })
+    }
+
+    /// Return the current time according to the clock the logger had when
+    /// the handle was created, without locking the logger.
+    pub(crate) fn now(&self) -> Instant {
+        self.0.time_base.now()
+    }
+
+    /// Increase the count by the [sampling period](ProgressLogger::sampling)
+    /// of the logger (usually one) and check whether it is time to log.
+    #[inline]
+    pub fn update(&self) {
+        self.update_with_count(self.0.sampling.load(Ordering::Relaxed));
+    }
+
+    /// Increase the count by the given amount and check whether it is time to log.
+    #[inline]
+    pub fn update_with_count(&self, count: usize) {
+        let value = self
+            .0
+            .counter
+            .fetch_add(count, Ordering::Relaxed)
+            .wrapping_add(count);
+        if value >= self.0.next_log_count.load(Ordering::Relaxed) {
+            self.log_if();
+            return;
+        }
+        let time_base = &self.0.time_base;
+        let next_log_time = time_base.epoch.checked_add(Duration::from_nanos(
+            self.0.next_log_time.load(Ordering::Relaxed),
+        ));
+        if next_log_time.is_some_and(|next_log_time| next_log_time <= time_base.now()) {
+            self.log_if();
+        }
+    }
+
+    /// Increase the count and, once every
+    /// [`LIGHT_UPDATE_MASK`](ProgressLogger::LIGHT_UPDATE_MASK) + 1 calls,
+    /// check whether it is time to log.
+    #[inline]
+    pub fn light_update(&self) {
+        let count = self
+            .0
+            .counter
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
+        if (count & ProgressLogger::LIGHT_UPDATE_MASK) == 0
+            || count >= self.0.next_log_count.load(Ordering::Relaxed)
+        {
+            self.log_if();
+        }
+    }
+
+    /// Increase the count and, once every `MASK` + 1 calls, check whether it
+    /// is time to log.
+    ///
+    /// See [`ProgressLog::light_update_with`].
+    #[inline]
+    pub fn light_update_with<const MASK: usize>(&self) {
+        let count = self
+            .0
+            .counter
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
+        if (count & MASK) == 0 || count >= self.0.next_log_count.load(Ordering::Relaxed) {
+            self.log_if();
+        }
+    }
+
+    /// Stop the logger, print `Completed.`, and display the final stats.
+    pub fn done(&self) {
+        self.lock().done();
+    }
+
+    /// Log if it is time to, unless another thread is holding the lock.
+    #[cold]
+    fn log_if(&self) {
+        if let Ok(pl) = self.0.pl.try_lock() {
+            let mut pl = ProgressLoggerGuard {
+                guard: pl,
+                inner: &self.0,
+            };
+            pl.log_if();
+        }
+    }
+
+    /// Spawn a [`tokio`] task logging progress at each interval until the
+    /// logger is stopped.
+    ///
+    /// The logger must have been started before calling this method;
+    /// otherwise, the task terminates immediately. To log from a thread
+    /// instead, see [`ProgressLogger::spawn_ticker`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let runtime = tokio::runtime::Builder::new_current_thread()
+    ///     .enable_time()
+    ///     .build()?;
+    /// runtime.block_on(async {
+    ///     let mut pl = ProgressLogger::default();
+    ///     pl.item_name("request");
+    ///     pl.start("Serving requests...");
+    ///     let pl = ProgressLoggerHandle::new(pl);
+    ///     let ticker = pl.spawn_async_ticker();
+    ///     for _ in 0..100 {
+    ///         // serve a request
+    ///         pl.update();
+    ///     }
+    ///     pl.done();
+    ///     ticker.await
+    /// })?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn spawn_async_ticker(&self) -> tokio::task::JoinHandle<()> {
+        let pl = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let timeout = {
+                    let mut pl = pl.lock();
+                    if pl.start_time.is_none() || pl.stop_time.is_some() {
+                        break;
+                    }
+                    pl.log_if();
+                    pl.ticker_timeout()
+                };
+                tokio::time::sleep(timeout).await;
+            }
+        })
+    }
+}
+
+impl From<ProgressLogger> for ProgressLoggerHandle {
+    fn from(pl: ProgressLogger) -> Self {
+        Self::new(pl)
+    }
+}
+
+/// A guard giving access to the logger of a [`ProgressLoggerHandle`].
+///
+/// When the guard is dropped, the time and the count of the next log and the
+/// sampling period seen by the update methods of the handle are synchronized
+/// with the logger.
+///
+/// The update methods of the handle measure time with the clock the logger
+/// had when the handle was created, so that they do not need any lock. If the
+/// [clock](ProgressLogger::clock) of the logger is changed through the guard,
+/// the update methods check whether it is time to log at each update.
+pub struct ProgressLoggerGuard<'a> {
+    guard: MutexGuard<'a, ProgressLogger>,
+    inner: &'a Inner,
+}
+
+impl Deref for ProgressLoggerGuard<'_> {
+    type Target = ProgressLogger;
+
+    fn deref(&self) -> &ProgressLogger {
+        &self.guard
+    }
+}
+
+impl DerefMut for ProgressLoggerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.guard
+    }
+}
+
+impl Drop for ProgressLoggerGuard<'_> {
+    fn drop(&mut self) {
+        let time_base = &self.inner.time_base;
+        let same_clock = match (&time_base.clock, &self.guard.clock) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        // Updates cannot cause a log unless the logger is running; if the
+        // clock has changed, the time of the next log cannot be expressed in
+        // the time base, and every update checks whether it is time to log
+        self.inner.next_log_time.store(
+            if !self.guard.is_running() {
+                u64::MAX
+            } else if same_clock {
+                nanos_since(time_base.epoch, self.guard.next_log_time)
+            } else {
+                0
+            },
+            Ordering::Relaxed,
+        );
+        self.guard.sync_count();
+        self.inner
+            .next_log_count
+            .store(next_log_count(&self.guard), Ordering::Relaxed);
+        self.inner
+            .sampling
+            .store(self.guard.sampling, Ordering::Relaxed);
+    }
+}
+
+/// Return the value of the counter of the given logger, whose count must have
+/// been synchronized, at which the next log will happen regardless of time.
+fn next_log_count(pl: &ProgressLogger) -> usize {
+    if !pl.is_running() || pl.next_log_count == usize::MAX {
+        return usize::MAX;
+    }
+    pl.counter_base
+        .saturating_add(pl.next_log_count.saturating_sub(pl.count))
+}
+
+pub(crate) fn nanos_since(epoch: Instant, instant: Instant) -> u64 {
+    instant
+        .saturating_duration_since(epoch)
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_clock_and_sampling() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .clock(clock.clone())
+            .deterministic(true)
+            .log_interval(Duration::from_secs(1))
+            .sampling(Some(10));
+        let pl = ProgressLoggerHandle::new(pl);
+        pl.lock().start("");
+        for _ in 0..3 {
+            pl.update();
+        }
+        assert_eq!(logs.progress_lines(), 0);
+        clock.advance(Duration::from_secs(2));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        assert_eq!(pl.lock().count(), 40);
+        pl.done();
+    }
+
+    #[test]
+    fn test_clock_changed_after_creation() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        let pl = ProgressLoggerHandle::new(pl);
+        pl.lock()
+            .clock(clock.clone())
+            .log_interval(Duration::from_secs(1))
+            .start("");
+        pl.update();
+        assert_eq!(logs.progress_lines(), 0);
+        clock.advance(Duration::from_secs(2));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        pl.done();
+    }
+
+    #[test]
+    fn test_min_logs() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .clock(clock.clone())
+            .expected_updates(Some(1000))
+            .min_logs(Some(9));
+        let pl = ProgressLoggerHandle::new(pl);
+        pl.lock().start("");
+        for _ in 0..1000 {
+            pl.update();
+        }
+        assert_eq!(logs.progress_lines(), 10);
+        pl.done();
+    }
+}
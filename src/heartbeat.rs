@@ -0,0 +1,138 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLoggerError, Record, Sample, Sink};
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/**
+
+A [`Sink`] rewriting a heartbeat file, so that external supervisors (e.g.,
+Kubernetes liveness probes or cron-based watchers) can detect stuck jobs by
+looking at the modification time or at the content of the file, without
+parsing logs.
+
+When the logger starts, at each progress line, and when the logger is done,
+the sink replaces the content of the file with a single line containing the
+time of the sample in milliseconds since the Unix epoch, as given by the
+[clock](crate::ProgressLogger::clock) of the logger, and the count,
+separated by a space. The file is written to a temporary file in the same
+directory, and then renamed, so readers never see a partial line.
+
+Errors never interrupt logging; they are recorded and can be retrieved with
+[`ProgressLogger::check_sinks`](crate::ProgressLogger::check_sinks).
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::HeartbeatSink;
+
+let path = std::env::temp_dir().join("crawler.heartbeat");
+let mut pl = ProgressLogger::default();
+pl.add_sink(HeartbeatSink::new(&path));
+pl.start("Crawling...");
+pl.done();
+pl.check_sinks()?;
+#     std::fs::remove_file(path)?;
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug)]
+pub struct HeartbeatSink {
+    path: PathBuf,
+    /// The temporary file renamed to [`path`](#structfield.path).
+    tmp_path: PathBuf,
+    /// The first error since the last call to [`Sink::take_error`].
+    error: Mutex<Option<io::Error>>,
+}
+
+impl HeartbeatSink {
+    /// Create a sink rewriting the heartbeat file at the given path.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_owned();
+        let mut tmp_path = OsString::from(path.as_os_str());
+        tmp_path.push(".tmp");
+        Self {
+            path,
+            tmp_path: tmp_path.into(),
+            error: Mutex::new(None),
+        }
+    }
+
+    fn write(&self, sample: &Sample<'_>) -> io::Result<()> {
+        let millis = sample
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        std::fs::write(&self.tmp_path, format!("{} {}\n", millis, sample.count))?;
+        std::fs::rename(&self.tmp_path, &self.path)
+    }
+}
+
+impl Sink for HeartbeatSink {
+    fn emit(&self, _record: &Record<'_>) {}
+
+    fn sample(&self, sample: &Sample<'_>) {
+        if let Err(error) = self.write(sample) {
+            self.error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(error);
+        }
+    }
+
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        self.error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .map(ProgressLoggerError::Io)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_heartbeat() -> Result<(), ProgressLoggerError> {
+        let path =
+            std::env::temp_dir().join(format!("dsi-pl-test-{}.heartbeat", std::process::id()));
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(HeartbeatSink::new(&path));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(125);
+        let (millis, count) = std::fs::read_to_string(&path)?
+            .trim_end()
+            .split_once(' ')
+            .map(|(millis, count)| (millis.parse::<u128>().unwrap(), count.to_owned()))
+            .unwrap();
+        assert!(millis > 0);
+        assert_eq!(count, "125");
+        pl.done_with_count(250);
+        assert!(std::fs::read_to_string(&path)?.ends_with(" 250\n"));
+        pl.check_sinks()?;
+        std::fs::remove_file(&path)?;
+
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(HeartbeatSink::new(path.join("missing")));
+        pl.start("");
+        assert!(pl.check_sinks().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::udp_socket;
+use crate::{ProgressLoggerError, Record, RecordKind, Sample, Sink};
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The timeout of connections to an HTTP endpoint.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The characters to escape in tag keys and values.
+const TAG_SPECIAL: &[char] = &[',', ' ', '='];
+
+/// Where an [`InfluxSink`] sends its lines.
+enum Transport {
+    Writer(Mutex<Box<dyn Write + Send>>),
+    Udp(UdpSocket),
+    Http { host: String, path: String },
+}
+
+/**
+
+A [`Sink`] writing interval samples in the InfluxDB line protocol.
+
+At each progress line, and when the logger is done, the sink writes a line
+such as
+```text
+progress,item=pumpkin count=125i,speed=12.5,interval_count=125i,interval_speed=12.5 10000000000
+```
+containing the number of items, the average speed, the number of items
+processed and the speed during the last log interval, the resident memory
+(`memory`, if memory display is enabled) and the time of the sample in
+nanoseconds since the Unix epoch.
+
+Lines can be sent to any [writer](#method.new) (e.g., a file or a TCP
+stream), to a [UDP socket](#method.udp), or to an [HTTP
+endpoint](#method.http). Errors never interrupt logging, so that the progress
+of the activity is never disturbed by the metrics system; they are recorded and
+can be retrieved with
+[`ProgressLogger::check_sinks`](crate::ProgressLogger::check_sinks).
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::InfluxSink;
+
+let path = std::env::temp_dir().join("pumpkins.influx");
+let mut pl = ProgressLogger::default();
+pl.add_sink(
+    InfluxSink::new(std::fs::File::create(&path)?)
+        .measurement("smashing")
+        .tag("host", "a"),
+);
+#     std::fs::remove_file(path)?;
+#     Ok(())
+# }
+```
+
+*/
+pub struct InfluxSink {
+    transport: Transport,
+    measurement: String,
+    tags: String,
+    /// The token sent in the `Authorization` header of HTTP requests.
+    token: Option<String>,
+    /// The first error since the last call to [`Sink::take_error`].
+    error: Mutex<Option<io::Error>>,
+}
+
+impl InfluxSink {
+    /// Create a new sink writing lines to the given writer.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self::with_transport(Transport::Writer(Mutex::new(Box::new(writer))))
+    }
+
+    /// Create a new sink sending each line in a datagram to the given address.
+    pub fn udp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = udp_socket(addr)?;
+        Ok(Self::with_transport(Transport::Udp(socket)))
+    }
+
+    /// Create a new sink posting each line to the given HTTP endpoint (e.g.,
+    /// `http://localhost:8086/write?db=jobs` for InfluxDB 1.x, or
+    /// `http://localhost:8086/api/v2/write?org=acme&bucket=jobs` for InfluxDB
+    /// 2.x, which also requires a [token](InfluxSink::token)).
+    ///
+    /// Only plain HTTP is supported; lines are posted synchronously, with a
+    /// timeout of five seconds. Responses with a status other than 2xx are
+    /// recorded as errors.
+    pub fn http(url: impl AsRef<str>) -> io::Result<Self> {
+        Ok(Self::try_http(url)?)
+    }
+
+    /// Like [`http`](InfluxSink::http), but returning a
+    /// [`ProgressLoggerError::InvalidUrl`] if the URL is not supported.
+    pub fn try_http(url: impl AsRef<str>) -> Result<Self, ProgressLoggerError> {
+        let url = url.as_ref();
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| ProgressLoggerError::InvalidUrl(url.to_owned()))?;
+        let (host, path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, "/"),
+        };
+        Ok(Self::with_transport(Transport::Http {
+            host: host.to_owned(),
+            path: path.to_owned(),
+        }))
+    }
+
+    fn with_transport(transport: Transport) -> Self {
+        Self {
+            transport,
+            measurement: "progress".to_owned(),
+            tags: String::new(),
+            token: None,
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Set the name of the measurement. The default is `progress`.
+    pub fn measurement(mut self, measurement: impl AsRef<str>) -> Self {
+        self.measurement = escape(measurement.as_ref(), &[',', ' ']);
+        self
+    }
+
+    /// Add a tag to each line.
+    pub fn tag(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let _ = write!(
+            self.tags,
+            ",{}={}",
+            escape(key.as_ref(), TAG_SPECIAL),
+            escape(value.as_ref(), TAG_SPECIAL)
+        );
+        self
+    }
+
+    /// Set the API token sent in the `Authorization` header of HTTP requests.
+    pub fn token(mut self, token: impl AsRef<str>) -> Self {
+        self.token = Some(token.as_ref().to_owned());
+        self
+    }
+
+    /// Return the line for the given sample.
+    fn line(&self, sample: &Sample<'_>) -> String {
+        let mut line = format!(
+            "{},item={}{} count={}i",
+            self.measurement,
+            escape(sample.item_name, TAG_SPECIAL),
+            self.tags,
+            sample.count
+        );
+        if let Some(speed) = sample.speed {
+            let _ = write!(line, ",speed={}", speed);
+        }
+        let _ = write!(line, ",interval_count={}i", sample.interval_count);
+        if let Some(interval_speed) = sample.interval_speed {
+            let _ = write!(line, ",interval_speed={}", interval_speed);
+        }
+        if let Some(memory) = sample.memory {
+            let _ = write!(line, ",memory={}i", memory);
+        }
+        if let Ok(time) = sample.time.duration_since(UNIX_EPOCH) {
+            let _ = write!(line, " {}", time.as_nanos());
+        }
+        line.push('\n');
+        line
+    }
+
+    /// Post the given body to the HTTP endpoint, returning an error if the
+    /// status of the response is not 2xx.
+    fn post(&self, host: &str, path: &str, body: &str) -> io::Result<()> {
+        let addr = host
+            .to_socket_addrs()
+            .or_else(|_| (host, 80).to_socket_addrs())?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown host"))?;
+        let mut stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT)?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n",
+            path,
+            host,
+            body.len(),
+        );
+        if let Some(token) = &self.token {
+            let _ = write!(request, "Authorization: Token {}\r\n", token);
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes())?;
+        // Read the status line (this also makes the server not see a reset)
+        let mut response = Vec::with_capacity(256);
+        let mut buffer = [0; 256];
+        while !response.contains(&b'\n') && response.len() < 1024 {
+            let n = stream.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            response.extend_from_slice(&buffer[..n]);
+        }
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        match status_line.split(' ').nth(1) {
+            Some(status) if status.len() == 3 && status.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "unexpected HTTP response: {}",
+                status_line
+            ))),
+        }
+    }
+}
+
+/// Escape the given characters and backslashes.
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Sink for InfluxSink {
+    fn emit(&self, _record: &Record<'_>) {}
+
+    fn sample(&self, sample: &Sample<'_>) {
+        if sample.kind == RecordKind::Start {
+            return;
+        }
+        let line = self.line(sample);
+        let result = match &self.transport {
+            Transport::Writer(writer) => {
+                let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+                writer
+                    .write_all(line.as_bytes())
+                    .and_then(|_| writer.flush())
+            }
+            Transport::Udp(socket) => socket.send(line.as_bytes()).map(|_| ()),
+            Transport::Http { host, path } => self.post(host, path, &line),
+        };
+        if let Err(error) = result {
+            self.error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(error);
+        }
+    }
+
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        self.error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .map(ProgressLoggerError::Io)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    /// A writer appending to a shared buffer.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(sink: InfluxSink) {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(sink);
+        pl.item_name("big pumpkin");
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(125);
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            InfluxSink::try_http("https://localhost/write"),
+            Err(ProgressLoggerError::InvalidUrl(url)) if url == "https://localhost/write"
+        ));
+        assert_eq!(
+            InfluxSink::http("ftp://localhost").err().unwrap().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(InfluxSink::new(Broken));
+        pl.start("");
+        assert!(pl.check_sinks().is_ok());
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(10);
+        pl.log_now();
+        let error = pl.check_sinks().unwrap_err();
+        assert!(matches!(error, ProgressLoggerError::Io(_)));
+        assert_eq!(error.to_string(), "I/O error: broken");
+        assert!(pl.check_sinks().is_ok());
+    }
+
+    #[test]
+    fn test_writer() {
+        let buffer = Buffer::default();
+        run(InfluxSink::new(buffer.clone()).tag("host", "a,b"));
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "progress,item=big\\ pumpkin,host=a\\,b \
+             count=125i,speed=12.5,interval_count=125i,interval_speed=12.5 10000000000\n"
+        );
+    }
+
+    /// Serve a single request with the given response, returning the request
+    /// line, the headers and the body.
+    fn serve(
+        listener: TcpListener,
+        response: &'static [u8],
+    ) -> std::thread::JoinHandle<io::Result<(String, Vec<String>, String)>> {
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line)?;
+            let mut headers = vec![];
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header)?;
+                if header == "\r\n" {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Content-Length: ") {
+                    length = value.trim().parse().unwrap();
+                }
+                headers.push(header.trim_end().to_owned());
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            stream.write_all(response)?;
+            Ok((request_line, headers, String::from_utf8(body).unwrap()))
+        })
+    }
+
+    #[test]
+    fn test_http() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/write?db=jobs", listener.local_addr()?);
+        let server = serve(listener, b"HTTP/1.1 204 No Content\r\n\r\n");
+        run(InfluxSink::http(url)?.measurement("m"));
+        let (request_line, headers, body) = server.join().unwrap()?;
+        assert_eq!(request_line, "POST /write?db=jobs HTTP/1.1\r\n");
+        assert!(!headers.iter().any(|h| h.starts_with("Authorization")));
+        assert!(body.starts_with("m,item=big\\ pumpkin count=125i,"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_http_token_and_status() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!(
+            "http://{}/api/v2/write?org=acme&bucket=jobs",
+            listener.local_addr()?
+        );
+        let server = serve(listener, b"HTTP/1.1 401 Unauthorized\r\n\r\n");
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .add_sink(InfluxSink::http(url)?.token("secret"));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(125);
+        let (_, headers, _) = server.join().unwrap()?;
+        assert!(headers.contains(&"Authorization: Token secret".to_owned()));
+        let error = pl.check_sinks().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "I/O error: unexpected HTTP response: HTTP/1.1 401 Unauthorized"
+        );
+        Ok(())
+    }
+}
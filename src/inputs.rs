@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::humanize;
+use crate::{ProgressLog, ProgressLogger};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/**
+
+A [`ProgressLogger`] measuring in bytes the progress over multiple inputs.
+
+The logger is configured with the total size of the inputs as number of
+expected updates; you should update it with the number of bytes processed. The
+transition to the next input is marked by [`next_input`](#method.next_input),
+which logs the name and the size of the input.
+
+The logger can be accessed directly through [`Deref`] and [`DerefMut`].
+
+# Example
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+
+stderrlog::new().verbosity(2).init()?;
+let mut pl = InputsProgress::new([("a.txt", 1000), ("b.txt", 3000)]);
+pl.start("Processing files...");
+for (name, size) in [("a.txt", 1000), ("b.txt", 3000)] {
+    pl.next_input(name);
+    for _ in 0..size / 100 {
+        // process 100 bytes
+        pl.update_with_count(100);
+    }
+}
+pl.done();
+#     Ok(())
+# }
+```
+
+*/
+pub struct InputsProgress {
+    /// The logger.
+    pl: ProgressLogger,
+    /// The name and size in bytes of each input.
+    inputs: Vec<(String, u64)>,
+    /// The index of the current input, if any.
+    current: Option<usize>,
+}
+
+impl InputsProgress {
+    /// Create a new logger for the given inputs, specified by name and size in bytes.
+    pub fn new<S: Into<String>>(inputs: impl IntoIterator<Item = (S, u64)>) -> Self {
+        let inputs: Vec<(String, u64)> = inputs
+            .into_iter()
+            .map(|(name, size)| (name.into(), size))
+            .collect();
+        let total = inputs
+            .iter()
+            .fold(0_u64, |total, (_, size)| total.saturating_add(*size));
+        let mut pl = ProgressLogger::default();
+        pl.item_name("byte")
+            .expected_updates(Some(total.try_into().unwrap_or(usize::MAX)));
+        Self {
+            pl,
+            inputs,
+            current: None,
+        }
+    }
+
+    /// Create a new logger for the given files, whose sizes are read from the
+    /// file system.
+    pub fn from_paths<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> io::Result<Self> {
+        let inputs = paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                Ok((path.display().to_string(), path.metadata()?.len()))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self::new(inputs))
+    }
+
+    /// Mark the transition to the input with the given name, logging its
+    /// name and size.
+    ///
+    /// If the name is not among the inputs of the logger, the next input
+    /// in order is assumed. The count is raised, if necessary, to the total
+    /// size of the previous inputs, so that skipped or partially counted
+    /// inputs do not distort the estimates.
+    pub fn next_input(&mut self, name: impl AsRef<str>) {
+        let name = name.as_ref();
+        let index = self
+            .inputs
+            .iter()
+            .position(|(input, _)| input == name)
+            .unwrap_or_else(|| self.current.map_or(0, |current| current + 1));
+        self.current = Some(index);
+
+        let previous = self.inputs[..index.min(self.inputs.len())]
+            .iter()
+            .fold(0_u64, |total, (_, size)| total.saturating_add(*size));
+        let previous = previous.try_into().unwrap_or(usize::MAX);
+        if self.pl.count < previous {
+            self.pl.count = previous;
+        }
+
+        match self.inputs.get(index) {
+            Some((_, size)) => self.pl.info(format_args!(
+                "Input {}/{}: {} ({}B)",
+                index + 1,
+                self.inputs.len(),
+                name,
+                humanize(*size as f64)
+            )),
+            None => self.pl.info(format_args!("Input: {}", name)),
+        }
+    }
+
+    /// Return the index of the current input, if any.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Return the name and the size of the inputs.
+    pub fn inputs(&self) -> &[(String, u64)] {
+        &self.inputs
+    }
+
+    /// Return the underlying logger.
+    pub fn into_inner(self) -> ProgressLogger {
+        self.pl
+    }
+}
+
+impl Deref for InputsProgress {
+    type Target = ProgressLogger;
+
+    fn deref(&self) -> &ProgressLogger {
+        &self.pl
+    }
+}
+
+impl DerefMut for InputsProgress {
+    fn deref_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_input() {
+        let mut pl = InputsProgress::new([("a", 100), ("b", 200), ("c", 300)]);
+        assert_eq!(pl.expected_updates, Some(600));
+        pl.start("");
+        pl.next_input("a");
+        pl.update_with_count(50);
+        pl.next_input("c");
+        assert_eq!(pl.current(), Some(2));
+        assert_eq!(pl.count, 300);
+        pl.next_input("d");
+        assert_eq!(pl.current(), Some(3));
+        assert_eq!(pl.count, 600);
+    }
+}
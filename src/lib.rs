@@ -7,14 +7,148 @@
 
 #![doc = include_str!("../README.md")]
 
-use log::info;
-use num_format::{Locale, ToFormattedString};
+use log::{log, Level};
+use num_format::{Buffer, Locale};
 use pluralizer::pluralize;
-use std::fmt::{Arguments, Display, Formatter, Result};
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::fmt::{Arguments, Debug, Display, Formatter, Result};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use sysinfo::{Pid, ProcessExt, ProcessRefreshKind, RefreshKind, System, SystemExt};
 
+mod adaptive;
+use adaptive::UpdateSelector;
+
+mod aggregate;
+pub use aggregate::{AggregateProgress, Transport, UdpTransport, WorkerProgress};
+
+mod background;
+pub use background::BackgroundLogger;
+
+mod channel;
+pub use channel::{ChannelSink, EventSender, ProgressEvent};
+
+mod chunks;
+pub use chunks::ProgressChunks;
+
+mod clock;
+pub use clock::{Clock, CoarseClock, ManualClock, SystemClock};
+
+#[cfg(feature = "tokio")]
+mod future;
+
+mod composite;
+pub use composite::CompositeProgress;
+
+mod error;
+pub use error::ProgressLoggerError;
+
+mod heartbeat;
+pub use heartbeat::HeartbeatSink;
+
+mod influx;
+pub use influx::InfluxSink;
+
+#[cfg(feature = "nvml")]
+mod gpu;
+#[cfg(feature = "nvml")]
+pub use gpu::GpuUsage;
+
+#[cfg(feature = "mpi")]
+mod mpi_transport;
+#[cfg(feature = "mpi")]
+pub use mpi_transport::MpiTransport;
+
+mod inputs;
+pub use inputs::InputsProgress;
+
+mod measure;
+pub use measure::{CpuUsage, LoadAverage, Measure, NetworkIo, OpenFiles, Rss, Thermal};
+
+mod memory;
+
+mod messages;
+pub use messages::Messages;
+
+mod sink;
+pub use sink::{Record, RecordKind, Sample, Sink};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+
+pub mod testing;
+
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "otel")]
+pub use otel::OtelSink;
+
+mod queue;
+use queue::QueueDepth;
+
+mod reader;
+pub use reader::{CompressedInput, ProgressDecompressedReader, ProgressSeekReader};
+
+mod relaxed;
+pub use relaxed::RelaxedCounter;
+use relaxed::Slot;
+
+mod anomaly;
+
+mod sampling;
+
+mod stable;
+
+mod markers;
+pub use stable::STABLE_FORMAT_VERSION;
+
+#[cfg(test)]
+mod fuzz;
+
+mod registry;
+pub use registry::{dump_all, log_on_panic, registered_loggers};
+
+mod report;
+pub use report::GrandTotalReport;
+use report::{DetailedReport, GrandTotal};
+
+mod round;
+
+mod secondary;
+use secondary::SecondaryCounter;
+
+mod scope;
+pub use scope::{ProgressScope, ScopedWorker};
+
+mod handle;
+pub use handle::{ProgressLoggerGuard, ProgressLoggerHandle};
+
+#[cfg(all(feature = "signals", unix))]
+mod signals;
+
+mod statsd;
+pub use statsd::StatsdSink;
+
+mod state;
+pub use state::{SharedState, Snapshot};
+
+mod summary;
+pub use summary::{Format, Summary};
+
+mod timer;
+pub use timer::TimerGuard;
+
+mod ticker;
+pub use ticker::Ticker;
+
+mod watchdog;
+pub use watchdog::Watchdog;
+
 mod utils;
+pub use utils::TimeUnit;
 use utils::*;
 
 /**
@@ -34,7 +168,11 @@ pub trait ProgressLog {
     /// - the [virtual-memory size](sysinfo::Process::virtual_memory) of the process that created the logger;
     /// - the [available memory](sysinfo::System::available_memory);
     /// - the [free memory](`sysinfo::System::free_memory);
-    /// - the [total amount](sysinfo::System::total_memory) of memory.
+    /// - the [total amount](sysinfo::System::total_memory) of memory;
+    /// - the change of the resident-set size during the current log interval
+    ///   and, if it is growing, the estimated time before the available
+    ///   memory (which takes into account cgroup limits) is exhausted at the
+    ///   current growth rate.
     fn display_memory(&mut self, display_memory: bool) -> &mut Self;
 
     /// Set the name of an item.
@@ -63,9 +201,39 @@ pub trait ProgressLog {
     /// Start the logger, displaying the given message.
     ///
     /// You can pass the empty string to display nothing.
+    ///
+    /// The placeholders `{expected}` and `{item_name}` in the message are
+    /// replaced by the [expected number of updates](#tymethod.expected_updates),
+    /// formatted as the count, or `?` if it is unknown, and by the [name of an
+    /// item](#tymethod.item_name), pluralized according to the expected number
+    /// of updates. For example, `"Smashing {expected} {item_name}..."` is
+    /// displayed as `Smashing 1,024 pumpkins...`.
+    ///
+    /// Calling this method on a logger that has already been started, whether
+    /// it is running or stopped, restarts it: the previous run is discarded,
+    /// as if [`reset`](#method.reset) had been called.
     fn start(&mut self, msg: impl AsRef<str>);
 
+    /// Return the logger to an idle state, as if it had never been started.
+    ///
+    /// The count and all timing information are cleared, but the
+    /// configuration is left untouched, so a logger can be reused for another
+    /// activity. Unlike [`start`](#tymethod.start), this method does not start
+    /// the logger and does not log anything.
+    ///
+    /// The default implementation does nothing.
+    fn reset(&mut self) {}
+
     /// Increase the count and check whether it is time to log.
+    ///
+    /// For a [`ProgressLogger`], the count is increased by the [sampling
+    /// period](ProgressLogger::sampling), which is usually one; this is the
+    /// only update method honoring the sampling period.
+    ///
+    /// The logger logs only while it is running: updates before the logger
+    /// is [started](#tymethod.start) or after it is [stopped](#tymethod.stop)
+    /// are counted, but they never cause a log. Note that starting the logger
+    /// clears the count.
     fn update(&mut self);
 
     /// Set the count and check whether it is time to log.
@@ -77,14 +245,289 @@ pub trait ProgressLog {
     /// Useful for very short activities with respect to which  checking the time is expensive.
     fn light_update(&mut self);
 
+    /// Increase the count and, once every `MASK` + 1 calls, check whether it
+    /// is time to log.
+    ///
+    /// This method is a variant of [`light_update`](ProgressLog::light_update)
+    /// in which the granularity of the check is chosen at compile time at each
+    /// call site, so that different loops can use different granularities at
+    /// no runtime cost. `MASK` should be of the form 2<sup>*k*</sup> − 1.
+    ///
+    /// The default implementation calls
+    /// [`light_update`](ProgressLog::light_update).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.start("Scanning...");
+    /// for _ in 0..1_000_000 {
+    ///     // Check the time every 1024 calls
+    ///     pl.light_update_with::<1023>();
+    /// }
+    /// pl.done();
+    /// ```
+    fn light_update_with<const MASK: usize>(&mut self) {
+        self.light_update();
+    }
+
+    /// Increase the count and check whether it is time to log, choosing
+    /// automatically between [`update`](ProgressLog::update) and
+    /// [`light_update`](ProgressLog::light_update).
+    ///
+    /// The cost of the first items after the start (including the time spent
+    /// processing them) is measured: if items are cheap, this method
+    /// switches to the lighter path, so there is no need to understand
+    /// [`LIGHT_UPDATE_MASK`](ProgressLogger::LIGHT_UPDATE_MASK) to avoid
+    /// overhead. The cost is measured again at each start, with the
+    /// [clock](ProgressLogger::clock) of the logger.
+    ///
+    /// The default implementation calls [`update`](ProgressLog::update).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.start("Scanning...");
+    /// for _ in 0..1_000_000 {
+    ///     pl.adaptive_update();
+    /// }
+    /// pl.done();
+    /// ```
+    fn adaptive_update(&mut self) {
+        self.update();
+    }
+
     /// Increase the count and force a log.
+    ///
+    /// Nothing is logged if the logger has not been started or has been
+    /// stopped.
     fn update_and_display(&mut self);
 
+    /// Correct the count by the given (possibly negative) amount, without
+    /// checking whether it is time to log.
+    ///
+    /// This method is useful when items counted previously turn out to be
+    /// invalid or must be reprocessed. The count saturates at zero.
+    ///
+    /// The default implementation does nothing.
+    fn correct(&mut self, _delta: isize) {}
+
+    /// Set the count, without checking whether it is time to log.
+    ///
+    /// The default implementation does nothing.
+    fn set_count(&mut self, _count: usize) {}
+
+    /// Set the label of the item being processed, which will be displayed
+    /// with the progress information until the logger is stopped.
+    ///
+    /// Knowing what is being processed is the first step to understand why
+    /// an activity looks stuck.
+    ///
+    /// The default implementation does nothing.
+    fn set_current_item(&mut self, _item: impl AsRef<str>) {}
+
+    /// Increase the count by the length of the given chunk and check whether
+    /// it is time to log.
+    ///
+    /// This method is a convenient single entry point for producers that
+    /// naturally work in blocks.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let data = vec![0_u8; 1 << 20];
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("byte");
+    /// pl.start("Checksumming...");
+    /// for chunk in data.chunks(4096) {
+    ///     // process the chunk
+    ///     pl.update_chunk(chunk);
+    /// }
+    /// pl.done();
+    /// ```
+    #[inline]
+    fn update_chunk<T>(&mut self, chunk: &[T]) {
+        self.update_with_count(chunk.len());
+    }
+
+    /// Set the [label of the item being processed](ProgressLog::set_current_item)
+    /// to the label of the last item of a block, increase the count by the
+    /// given amount, and check whether it is time to log.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("record");
+    /// pl.start("Importing...");
+    /// for file in ["a.csv", "b.csv"] {
+    ///     // import 1000 records from the file
+    ///     pl.update_many(1000, file);
+    /// }
+    /// pl.done();
+    /// ```
+    #[inline]
+    fn update_many(&mut self, count: usize, last: impl AsRef<str>) {
+        self.set_current_item(last);
+        self.update_with_count(count);
+    }
+
+    /// Set the current phase of the activity, which will be displayed at the
+    /// start of the progress information until the logger is stopped.
+    ///
+    /// The count and the timing information are not affected, so phases are
+    /// useful for activities with internal mode switches that do not warrant
+    /// separate loggers. You can pass the empty string to clear the phase.
+    ///
+    /// The default implementation does nothing.
+    fn set_phase(&mut self, _phase: impl AsRef<str>) {}
+
+    /// Increase the number of retries, which is tracked separately from the
+    /// count, without checking whether it is time to log.
+    ///
+    /// If there have been retries, the logger displays the total number of
+    /// retries and the number of retries during the last log interval.
+    ///
+    /// The default implementation does nothing.
+    fn update_retry(&mut self) {}
+
+    /// Set the current backoff delay between retries, which will be
+    /// displayed with the retries.
+    ///
+    /// Pass [`None`] when retries are over.
+    ///
+    /// The default implementation does nothing.
+    fn set_backoff(&mut self, _backoff: Option<Duration>) {}
+
+    /// Increase the number of successes, without checking whether it is time
+    /// to log.
+    ///
+    /// If a [success ratio](ProgressLogger::success_ratio) is displayed, the
+    /// count is interpreted as the number of attempts, and the logger
+    /// displays the percentage of successful attempts.
+    ///
+    /// The default implementation does nothing.
+    fn update_success(&mut self) {}
+
+    /// Increase the number of successes by the given value, without checking
+    /// whether it is time to log.
+    ///
+    /// See [`update_success`](#method.update_success).
+    ///
+    /// The default implementation does nothing.
+    fn update_success_with_count(&mut self, _count: usize) {}
+
+    /// Record a cache hit, without checking whether it is time to log.
+    ///
+    /// Hits and misses are tracked separately from the count: if there have
+    /// been any, the logger displays the hit rate.
+    ///
+    /// The default implementation does nothing.
+    fn hit(&mut self) {}
+
+    /// Record a cache miss, without checking whether it is time to log.
+    ///
+    /// See [`hit`](#method.hit).
+    ///
+    /// The default implementation does nothing.
+    fn miss(&mut self) {}
+
+    /// Increase the number of items in flight, that is, items whose
+    /// processing has begun but not ended, without checking whether it is
+    /// time to log.
+    ///
+    /// Once this method has been called, the logger displays the number of
+    /// items in flight until it is stopped, which reveals backpressure in
+    /// pipelines and bounded queues.
+    ///
+    /// The default implementation does nothing.
+    fn begin_item(&mut self) {}
+
+    /// Decrease the number of items in flight, increase the count, and check
+    /// whether it is time to log.
+    ///
+    /// See [`begin_item`](#method.begin_item).
+    ///
+    /// The default implementation calls [`update`](ProgressLog::update).
+    fn end_item(&mut self) {
+        self.update();
+    }
+
+    /// Increase the number of items produced, without checking whether it
+    /// is time to log.
+    ///
+    /// In a pipeline stage, the count is interpreted as the number of items
+    /// consumed: once this method has been called, the logger displays the
+    /// number of items produced, their speed, and the backlog between
+    /// produced and consumed items, with its change during the last log
+    /// interval.
+    ///
+    /// The default implementation does nothing.
+    fn update_produced(&mut self) {}
+
+    /// Increase the number of items produced by the given value, without
+    /// checking whether it is time to log.
+    ///
+    /// See [`update_produced`](#method.update_produced).
+    ///
+    /// The default implementation does nothing.
+    fn update_produced_with_count(&mut self, _count: usize) {}
+
+    /// Start a new round of an iterative algorithm.
+    ///
+    /// The count and the other global stats are not affected, but once this
+    /// method has been called, the logger displays the current round and the
+    /// count and speed since its start, in addition to the overall progress.
+    /// See also [`ProgressLogger::rounds`].
+    ///
+    /// The default implementation does nothing.
+    fn new_round(&mut self) {}
+
+    /// Record the value of the convergence metric of an iterative algorithm
+    /// (e.g., a residual) in the current round.
+    ///
+    /// The last value recorded is displayed; see
+    /// [`ProgressLogger::convergence_metric`].
+    ///
+    /// The default implementation does nothing.
+    fn record_metric(&mut self, _value: f64) {}
+
+    /// Force a log, without changing the count.
+    ///
+    /// The next log will happen after a full log interval. Nothing is logged
+    /// if the logger has not been started or has been stopped.
+    ///
+    /// The default implementation does nothing.
+    fn log_now(&mut self) {}
+
+    /// Return whether the log interval has elapsed since the last log.
+    ///
+    /// This method makes it possible to control exactly when output happens:
+    /// if it returns true, you can call [`log_now`](#method.log_now).
+    /// It returns false if the logger is not running.
+    ///
+    /// The default implementation returns false.
+    fn should_log(&self) -> bool {
+        false
+    }
+
     /// Stop the logger, fixing the final time.
+    ///
+    /// Stopping a logger that is already stopped does nothing, so the final
+    /// time is the one of the first call. Stopping a logger that has not been
+    /// started does nothing but log a warning.
     fn stop(&mut self);
 
     /// Stop the logger, print `Completed.`, and display the final stats.
     /// The number of expected updates will be cleared.
+    ///
+    /// If the logger has not been started, this method does nothing but log
+    /// a warning.
     fn done(&mut self);
 
     /// Stop the logger, set the count, print `Completed.`, and display the final stats.
@@ -103,6 +546,117 @@ pub trait ProgressLog {
     /// You do not need to call this method unless you display the logger manually.
     fn refresh(&mut self);
 
+    /// Return an iterator over the chunks of the given slice, of the given
+    /// size, updating the logger with the length of each chunk.
+    ///
+    /// Each chunk is counted when the next one is requested, or when the
+    /// iterator is exhausted or dropped, so array-processing code gets
+    /// accurate counts without per-element updates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let data = vec![1.0_f64; 10_000];
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("value");
+    /// pl.start("Summing values...");
+    /// let mut sum = 0.0;
+    /// for chunk in pl.chunks(&data, 1024) {
+    ///     sum += chunk.iter().sum::<f64>();
+    /// }
+    /// pl.done();
+    /// ```
+    fn chunks<'a, T>(
+        &mut self,
+        slice: &'a [T],
+        chunk_size: usize,
+    ) -> ProgressChunks<'a, '_, T, Self>
+    where
+        Self: Sized,
+    {
+        ProgressChunks::new(self, slice, chunk_size)
+    }
+
+    /// Call the given closure on each element of a slice, with its index,
+    /// counting each element after it has been processed.
+    ///
+    /// Elements are counted with
+    /// [`adaptive_update`](ProgressLog::adaptive_update), so if elements are
+    /// cheap, the lighter update path is used.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let data = vec![1.0_f64; 1 << 20];
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("value");
+    /// pl.start("Summing values...");
+    /// let mut sum = 0.0;
+    /// pl.for_each_indexed(&data, |_, x| sum += x);
+    /// pl.done();
+    /// ```
+    fn for_each_indexed<T>(&mut self, slice: &[T], mut f: impl FnMut(usize, &T))
+    where
+        Self: Sized,
+    {
+        for (i, x) in slice.iter().enumerate() {
+            f(i, x);
+            self.adaptive_update();
+        }
+    }
+
+    /// Call the given closure on each line read from the given reader,
+    /// without the line terminator, counting each line after it has been
+    /// processed.
+    ///
+    /// Lines are counted with
+    /// [`adaptive_update`](ProgressLog::adaptive_update). The buffer holding
+    /// a line is reused across lines.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::io::Cursor;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("line");
+    /// pl.start("Counting words...");
+    /// let mut words = 0;
+    /// pl.scan(Cursor::new("a b\nc\n"), |line| {
+    ///     words += line.split_whitespace().count()
+    /// })?;
+    /// pl.done();
+    /// assert_eq!(words, 3);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    fn scan(
+        &mut self,
+        mut reader: impl std::io::BufRead,
+        mut f: impl FnMut(&str),
+    ) -> std::io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let end = line.trim_end_matches(['\n', '\r']).len();
+            f(&line[..end]);
+            self.adaptive_update();
+        }
+    }
+
     /// Output the given message.
     ///
     /// For maximum flexibility, this method takes as argument the result of a [`std::format_args!`] macro.
@@ -176,6 +730,12 @@ impl<P: ProgressLog> ProgressLog for Option<P> {
         }
     }
 
+    fn reset(&mut self) {
+        if let Some(pl) = self {
+            pl.reset();
+        }
+    }
+
     fn update(&mut self) {
         if let Some(pl) = self {
             pl.update();
@@ -188,100 +748,485 @@ impl<P: ProgressLog> ProgressLog for Option<P> {
         }
     }
 
+    #[inline(always)]
     fn light_update(&mut self) {
         if let Some(pl) = self {
             pl.light_update();
         }
     }
 
+    #[inline(always)]
+    fn light_update_with<const MASK: usize>(&mut self) {
+        if let Some(pl) = self {
+            pl.light_update_with::<MASK>();
+        }
+    }
+
+    #[inline(always)]
+    fn adaptive_update(&mut self) {
+        if let Some(pl) = self {
+            pl.adaptive_update();
+        }
+    }
+
     fn update_and_display(&mut self) {
         if let Some(pl) = self {
             pl.update_and_display();
         }
     }
 
-    fn stop(&mut self) {
+    fn correct(&mut self, delta: isize) {
         if let Some(pl) = self {
-            pl.stop();
+            pl.correct(delta);
         }
     }
 
-    fn done(&mut self) {
+    fn set_count(&mut self, count: usize) {
         if let Some(pl) = self {
-            pl.done();
+            pl.set_count(count);
         }
     }
 
-    fn done_with_count(&mut self, count: usize) {
+    fn set_current_item(&mut self, item: impl AsRef<str>) {
         if let Some(pl) = self {
-            pl.done_with_count(count);
+            pl.set_current_item(item);
         }
     }
 
-    fn elapsed(&self) -> Option<Duration> {
-        self.as_ref().and_then(|pl| pl.elapsed())
+    fn set_phase(&mut self, phase: impl AsRef<str>) {
+        if let Some(pl) = self {
+            pl.set_phase(phase);
+        }
     }
 
-    fn refresh(&mut self) {
+    fn update_retry(&mut self) {
         if let Some(pl) = self {
-            pl.refresh();
+            pl.update_retry();
         }
     }
 
-    fn info(&self, args: Arguments<'_>) {
+    fn set_backoff(&mut self, backoff: Option<Duration>) {
         if let Some(pl) = self {
-            pl.info(args);
+            pl.set_backoff(backoff);
         }
     }
 
-    fn clone(&self) -> Self {
-        self.as_ref().map(|pl| pl.clone())
+    fn update_success(&mut self) {
+        if let Some(pl) = self {
+            pl.update_success();
+        }
     }
-}
 
-/**
+    fn update_success_with_count(&mut self, count: usize) {
+        if let Some(pl) = self {
+            pl.update_success_with_count(count);
+        }
+    }
 
-An implementation of [`ProgressLog`] with output generated using the [`log`](https://docs.rs/log) crate
-at the `info` level.
+    fn hit(&mut self) {
+        if let Some(pl) = self {
+            pl.hit();
+        }
+    }
 
-*/
-pub struct ProgressLogger {
-    /// The name of an item. Defaults to `item`.
-    item_name: String,
-    /// The log interval. Defaults to 10 seconds.
-    log_interval: Duration,
-    /// The expected number of updates. If set, the logger will display the percentage of completion and
-    /// an estimate of the time to completion.
-    expected_updates: Option<usize>,
-    /// The time unit to use for speed. If set, the logger will always display the speed in this unit
-    /// instead of making a choice of readable unit based on the elapsed time. Moreover, large numbers
-    /// will not be thousands separated. This is useful when the output of the logger must be parsed.
-    time_unit: Option<TimeUnit>,
-    /// Display additionally the speed achieved during the last log interval.
-    local_speed: bool,
-    /// When the logger was started.
-    start_time: Option<Instant>,
-    /// The last time we logged the activity (to compute speed).
-    last_log_time: Instant,
-    /// The next time we will log the activity.
-    next_log_time: Instant,
-    /// When the logger was stopped.
-    stop_time: Option<Instant>,
-    /// The number of items.
-    count: usize,
-    /// The number of items at the last log (to compute speed).
-    last_count: usize,
-    /// Display additionally the amount of used and free memory using this [`sysinfo::System`]
-    system: Option<System>,
-    /// The pid of the current process
-    pid: Pid,
-}
+    fn miss(&mut self) {
+        if let Some(pl) = self {
+            pl.miss();
+        }
+    }
 
-impl Default for ProgressLogger {
-    fn default() -> Self {
-        Self {
-            item_name: "item".into(),
-            log_interval: Duration::from_secs(10),
+    fn begin_item(&mut self) {
+        if let Some(pl) = self {
+            pl.begin_item();
+        }
+    }
+
+    fn end_item(&mut self) {
+        if let Some(pl) = self {
+            pl.end_item();
+        }
+    }
+
+    fn update_produced(&mut self) {
+        if let Some(pl) = self {
+            pl.update_produced();
+        }
+    }
+
+    fn update_produced_with_count(&mut self, count: usize) {
+        if let Some(pl) = self {
+            pl.update_produced_with_count(count);
+        }
+    }
+
+    fn new_round(&mut self) {
+        if let Some(pl) = self {
+            pl.new_round();
+        }
+    }
+
+    fn record_metric(&mut self, value: f64) {
+        if let Some(pl) = self {
+            pl.record_metric(value);
+        }
+    }
+
+    fn log_now(&mut self) {
+        if let Some(pl) = self {
+            pl.log_now();
+        }
+    }
+
+    fn should_log(&self) -> bool {
+        self.as_ref().is_some_and(|pl| pl.should_log())
+    }
+
+    fn stop(&mut self) {
+        if let Some(pl) = self {
+            pl.stop();
+        }
+    }
+
+    fn done(&mut self) {
+        if let Some(pl) = self {
+            pl.done();
+        }
+    }
+
+    fn done_with_count(&mut self, count: usize) {
+        if let Some(pl) = self {
+            pl.done_with_count(count);
+        }
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        self.as_ref().and_then(|pl| pl.elapsed())
+    }
+
+    fn refresh(&mut self) {
+        if let Some(pl) = self {
+            pl.refresh();
+        }
+    }
+
+    fn info(&self, args: Arguments<'_>) {
+        if let Some(pl) = self {
+            pl.info(args);
+        }
+    }
+
+    fn clone(&self) -> Self {
+        self.as_ref().map(|pl| pl.clone())
+    }
+}
+
+/// The number of log intervals after which a [`ProgressLogger`] logs even
+/// if fewer than [`min_items_per_log`](ProgressLogger::min_items_per_log)
+/// items have been processed.
+const STALLED_LOG_INTERVALS: u32 = 10;
+
+/// A closure called by a [`ProgressLogger`] around its log lines.
+type Hook = Exclusive<Box<dyn FnMut(&ProgressLogger) + Send>>;
+
+/// A closure called by a [`ProgressLogger`] to obtain a value.
+type Source<T> = Exclusive<Box<dyn FnMut() -> T + Send>>;
+
+// ProgressLogger must be usable from multiple threads through shared
+// references (e.g., in an Arc or in a static).
+const _: fn() = || {
+    fn assert<T: Send + Sync>() {}
+    assert::<ProgressLogger>();
+};
+
+/**
+
+An implementation of [`ProgressLog`] with output generated using the [`log`](https://docs.rs/log) crate
+at the `info` level.
+
+*/
+pub struct ProgressLogger {
+    /// The name of an item. Defaults to `item`.
+    item_name: String,
+    /// The singular form of the name of an item, computed when the name is set.
+    item_singular: String,
+    /// The plural form of the name of an item, computed when the name is set.
+    item_plural: String,
+    /// The log interval. Defaults to 10 seconds.
+    log_interval: Duration,
+    /// The label of the item being processed, or the empty string.
+    current_item: String,
+    /// The current phase of the activity, or the empty string.
+    phase: String,
+    /// The number of retries.
+    retries: usize,
+    /// The number of retries at the last log.
+    last_retries: usize,
+    /// The current backoff delay between retries, if any.
+    backoff: Option<Duration>,
+    /// The label of the success ratio, if displayed.
+    success_label: Option<String>,
+    /// The number of successes.
+    successes: usize,
+    /// The number of cache hits.
+    hits: usize,
+    /// The number of cache misses.
+    misses: usize,
+    /// The number of items in flight, if tracked.
+    in_flight: Option<usize>,
+    /// The depth of the attached queue, if any.
+    queue: Option<QueueDepth>,
+    /// The number of items produced, if tracked.
+    produced: Option<usize>,
+    /// The backlog between produced and consumed items at the last log.
+    last_backlog: usize,
+    /// The name and the accumulated nanoseconds of each timer.
+    timers: Vec<(String, Arc<AtomicU64>)>,
+    /// Log also when the count reaches a power of two.
+    exponential_logging: bool,
+    /// The minimum number of new items required to log when the log
+    /// interval has elapsed.
+    min_items_per_log: usize,
+    /// Suppress progress lines identical to the previous one, except for
+    /// timing information.
+    dedup: bool,
+    /// The last progress line, rendered at the start time, if deduplication
+    /// is enabled.
+    last_line: Option<String>,
+    /// The number of times the last progress line has been suppressed.
+    repeated: usize,
+    /// The delay before the first log, if different from the log interval.
+    first_log_delay: Option<Duration>,
+    /// The minimum number of logs when the expected number of updates is
+    /// known, if any.
+    min_logs: Option<usize>,
+    /// The count at which the next log will happen regardless of time.
+    next_log_count: usize,
+    /// The maximum number of progress lines, if any.
+    max_logs: Option<usize>,
+    /// The maximum relative jitter of the log interval, if any.
+    log_interval_jitter: Option<f64>,
+    /// The factor applied to the log interval during the current run.
+    jitter_factor: f64,
+    /// The number of decimal digits of the percentage of completion.
+    percent_precision: usize,
+    /// Display all durations as integer milliseconds.
+    raw_millis: bool,
+    /// Prefix each line with the time elapsed since the start.
+    elapsed_prefix: bool,
+    /// The catalog of the fixed strings of the output.
+    messages: Messages,
+    /// Whether to accumulate the totals of successive runs.
+    accumulate: bool,
+    /// The expected number of rounds, if known.
+    rounds: Option<usize>,
+    /// The expected duration of the activity, if known.
+    expected_duration: Option<Duration>,
+    /// The identifier of the run, if any.
+    run_id: Option<String>,
+    /// The metadata attached to the run.
+    metadata: Vec<(String, String)>,
+    /// The current round, or zero if no round has been started.
+    round: usize,
+    /// The count at the start of the current round.
+    round_start_count: usize,
+    /// The start time of the current round, if any.
+    round_start_time: Option<Instant>,
+    /// The name of the convergence metric.
+    metric_name: String,
+    /// The threshold under which the convergence metric converges, if any.
+    convergence_threshold: Option<f64>,
+    /// The round of the last value of the convergence metric, and the value.
+    metric: Option<(usize, f64)>,
+    /// The last value of the convergence metric in a previous round.
+    prev_metric: Option<f64>,
+    /// The totals of the previous runs, in accumulate mode.
+    grand_total: GrandTotal,
+    /// The number of progress lines logged since the logger was started.
+    logs: usize,
+    /// The expected number of updates. If set, the logger will display the percentage of completion and
+    /// an estimate of the time to completion.
+    expected_updates: Option<usize>,
+    /// The time unit to use for speed. If set, the logger will always display the speed in this unit
+    /// instead of making a choice of readable unit based on the elapsed time. Moreover, large numbers
+    /// will not be thousands separated. This is useful when the output of the logger must be parsed.
+    time_unit: Option<TimeUnit>,
+    /// Display additionally the speed achieved during the last log interval.
+    local_speed: bool,
+    /// When the logger was started.
+    start_time: Option<Instant>,
+    /// The last time we logged the activity (to compute speed).
+    last_log_time: Instant,
+    /// The next time we will log the activity.
+    next_log_time: Instant,
+    /// When the logger was stopped.
+    stop_time: Option<Instant>,
+    /// The number of items.
+    count: usize,
+    /// The number of items at the last log (to compute speed).
+    last_count: usize,
+    /// Display additionally the amount of used and free memory using this [`sysinfo::System`]
+    system: Option<System>,
+    /// The pid of the current process
+    pid: Pid,
+    /// The attached measures, with their last sampled value.
+    measures: Vec<(Box<dyn Measure>, Option<String>)>,
+    /// An atomic counter whose increments are added to the count, if any.
+    counter: Option<Arc<AtomicUsize>>,
+    /// The value of [`counter`](#structfield.counter) at the last synchronization.
+    counter_base: usize,
+    /// The slots of the relaxed counters.
+    relaxed: Vec<Arc<Slot>>,
+    /// The sum of the slots of the relaxed counters at the last synchronization.
+    relaxed_base: usize,
+    /// A closure returning the count, if any.
+    watch: Option<Source<usize>>,
+    /// A closure returning the expected number of updates, if known, if any.
+    expected_updates_fn: Option<Source<Option<usize>>>,
+    /// A closure called before each progress log line, if any.
+    before_log: Option<Hook>,
+    /// A closure called after each progress log line, if any.
+    after_log: Option<Hook>,
+    /// A closure returning a string appended to the progress information, if
+    /// any, with its last value.
+    status: Option<(Source<String>, String)>,
+    /// The clock providing the current time. If not set, [`Instant::now`] is used.
+    clock: Option<Arc<dyn Clock>>,
+    /// Render the output using only values provided by the clock and by the count.
+    deterministic: bool,
+    /// Additional destinations for the output.
+    sinks: Vec<Arc<dyn Sink>>,
+    /// Freeze the time units chosen automatically at the first log.
+    freeze_time_unit: bool,
+    /// The time units for timing and speed chosen at the first log, if frozen.
+    frozen_time_units: Option<(TimeUnit, TimeUnit)>,
+    /// The smoothing factor of the local speed. Defaults to 1 (no smoothing).
+    local_speed_smoothing: f64,
+    /// The smoothed local speed, in items per second, at the last log.
+    smoothed_local_speed: Option<f64>,
+    /// The level of the output of [`done`](ProgressLog::done). Defaults to `Info`.
+    done_level: Level,
+    /// Print `Completed.` when done. Defaults to true.
+    completed_message: bool,
+    /// Display the final stats when done. Defaults to true.
+    done_summary: bool,
+    /// Display wall-clock start and stop times in the final stats.
+    display_timestamps: bool,
+    /// Display the number of items processed since the last log.
+    display_delta: bool,
+    /// The target speed in items per second, if any.
+    target_rate: Option<f64>,
+    /// The theoretical peak speed in items per second, if known.
+    peak_rate: Option<f64>,
+    /// A secondary counter, if any.
+    secondary: Option<SecondaryCounter>,
+    /// The factor beyond which the speed of a log interval is flagged as
+    /// anomalous, if any.
+    anomaly_factor: Option<f64>,
+    /// The number of items represented by a call to
+    /// [`update`](ProgressLog::update).
+    sampling: usize,
+    /// Whether the count has been set exactly by
+    /// [`done_with_count`](ProgressLog::done_with_count) after sampling.
+    reconciled: bool,
+    /// The speeds of the last log intervals, if anomaly flagging is enabled.
+    interval_speeds: VecDeque<f64>,
+    /// Display the final stats as a multi-line report.
+    detailed_report: bool,
+    /// Write progress lines and final stats in stable format.
+    stable_format: bool,
+    /// Emit marker lines at the start and at the end of each activity.
+    markers: bool,
+    /// The identifier of the current activity, if the logger has been
+    /// started.
+    activity_id: Option<u64>,
+    /// The highest speed, in items per second, over a log interval.
+    peak_speed: f64,
+    /// The highest resident memory of the process, in bytes, at a refresh.
+    peak_memory: u64,
+    /// The resident-set size at the last log.
+    last_memory: u64,
+    /// A buffer for formatting log lines, reused across logs.
+    line: String,
+    /// The time returned by [`now`](ProgressLogger::now), if frozen.
+    frozen_now: Option<Instant>,
+    /// The choice of the update method of
+    /// [`adaptive_update`](ProgressLog::adaptive_update).
+    selector: UpdateSelector,
+    /// The available memory under which the logger warns, if any.
+    low_memory: Option<u64>,
+    /// A closure called when the available memory is low.
+    on_low_memory: Option<Hook>,
+    /// The CPU time of the process when the logger was started.
+    start_cpu_time: Option<Duration>,
+    /// The number of batches and the batch size, if the logger has been
+    /// configured for [batches](#method.batches).
+    batches: Option<(usize, usize)>,
+    /// The completed fraction of the activity set explicitly, if any.
+    completed_fraction: Option<f64>,
+    /// The shared state kept up to date by the logger, if any.
+    state: Option<SharedState>,
+    /// The minimum interval between updates of the shared state and samples
+    /// of the depth of the queue.
+    state_interval: Duration,
+    /// The next time we will update the shared state and sample the depth of
+    /// the queue when checking the time.
+    next_state_time: Instant,
+    /// The wall-clock time at which the logger was started.
+    start_system_time: Option<SystemTime>,
+    /// The wall-clock time at which the logger was stopped.
+    stop_system_time: Option<SystemTime>,
+}
+
+impl Default for ProgressLogger {
+    fn default() -> Self {
+        Self {
+            item_name: "item".into(),
+            item_singular: "item".into(),
+            item_plural: "items".into(),
+            log_interval: Duration::from_secs(10),
+            current_item: String::new(),
+            phase: String::new(),
+            retries: 0,
+            last_retries: 0,
+            backoff: None,
+            success_label: None,
+            successes: 0,
+            hits: 0,
+            misses: 0,
+            in_flight: None,
+            queue: None,
+            produced: None,
+            last_backlog: 0,
+            timers: Vec::new(),
+            exponential_logging: false,
+            min_items_per_log: 0,
+            dedup: false,
+            last_line: None,
+            repeated: 0,
+            first_log_delay: None,
+            min_logs: None,
+            next_log_count: usize::MAX,
+            max_logs: None,
+            log_interval_jitter: None,
+            jitter_factor: 1.0,
+            percent_precision: 2,
+            raw_millis: false,
+            elapsed_prefix: false,
+            messages: Messages::ENGLISH,
+            accumulate: false,
+            rounds: None,
+            expected_duration: None,
+            run_id: None,
+            metadata: Vec::new(),
+            round: 0,
+            round_start_count: 0,
+            round_start_time: None,
+            metric_name: "metric".into(),
+            convergence_threshold: None,
+            metric: None,
+            prev_metric: None,
+            grand_total: GrandTotal::default(),
+            logs: 0,
             expected_updates: None,
             time_unit: None,
             local_speed: false,
@@ -293,50 +1238,1589 @@ impl Default for ProgressLogger {
             last_count: 0,
             system: None,
             pid: Pid::from(std::process::id() as usize),
+            measures: Vec::new(),
+            counter: None,
+            counter_base: 0,
+            relaxed: Vec::new(),
+            relaxed_base: 0,
+            watch: None,
+            expected_updates_fn: None,
+            before_log: None,
+            after_log: None,
+            status: None,
+            clock: None,
+            deterministic: false,
+            sinks: Vec::new(),
+            freeze_time_unit: false,
+            frozen_time_units: None,
+            local_speed_smoothing: 1.0,
+            smoothed_local_speed: None,
+            done_level: Level::Info,
+            completed_message: true,
+            done_summary: true,
+            display_timestamps: false,
+            display_delta: false,
+            target_rate: None,
+            peak_rate: None,
+            secondary: None,
+            anomaly_factor: None,
+            sampling: 1,
+            reconciled: false,
+            interval_speeds: VecDeque::new(),
+            detailed_report: false,
+            stable_format: false,
+            markers: false,
+            activity_id: None,
+            peak_speed: 0.0,
+            peak_memory: 0,
+            last_memory: 0,
+            line: String::new(),
+            frozen_now: None,
+            selector: UpdateSelector::default(),
+            low_memory: None,
+            on_low_memory: None,
+            start_cpu_time: None,
+            batches: None,
+            completed_fraction: None,
+            state: None,
+            state_interval: Duration::from_nanos(1_000_000_000 / 60),
+            next_state_time: Instant::now(),
+            start_system_time: None,
+            stop_system_time: None,
+        }
+    }
+}
+
+impl ProgressLogger {
+    /// Calls to [light_update](#method.light_update) will cause a call to
+    /// [`Instant::now`] only if the current count
+    /// is a multiple of this mask plus one.
+    pub const LIGHT_UPDATE_MASK: usize = (1 << 20) - 1;
+
+    /// Create a logger whose count is read from the given atomic counter.
+    ///
+    /// See [`counter`](#method.counter).
+    pub fn with_counter(counter: Arc<AtomicUsize>) -> Self {
+        Self {
+            counter: Some(counter),
+            ..ProgressLogger::default()
+        }
+    }
+
+    /// Return an atomic counter shared with the logger, creating it if necessary.
+    ///
+    /// Once a counter is attached, the increments of the counter since the
+    /// logger was [started](ProgressLog::start) are added to the count:
+    /// worker threads can thus increment the counter directly, without
+    /// synchronizing on the logger. The logger never writes to the counter, so
+    /// it can also be an existing counter maintained by your code (see
+    /// [`with_counter`](#method.with_counter)).
+    ///
+    /// The value of the counter is read when logging, when calling
+    /// [`refresh`](ProgressLog::refresh), and when stopping the logger. To log
+    /// progress periodically, the thread owning the logger can call
+    /// [`update_with_count(0)`](ProgressLog::update_with_count), or you can
+    /// use a [`Ticker`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let mut pl = ProgressLogger::default();
+    /// let counter = pl.counter();
+    /// pl.item_name("pumpkin");
+    /// pl.start("Smashing pumpkins in parallel...");
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         let counter = counter.clone();
+    ///         s.spawn(move || {
+    ///             for _ in 0..100 {
+    ///                 // do something on each pumpkin
+    ///                 counter.fetch_add(1, Ordering::Relaxed);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// pl.done();
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn counter(&mut self) -> Arc<AtomicUsize> {
+        match &self.counter {
+            Some(counter) => counter.clone(),
+            None => {
+                let counter = Arc::new(AtomicUsize::new(0));
+                self.counter_base = 0;
+                self.counter = Some(counter.clone());
+                counter
+            }
+        }
+    }
+
+    /// Set a closure returning the current count, polled by the logger.
+    ///
+    /// The closure makes it possible to derive progress from an external
+    /// source of truth, such as an atomic counter, the size of a file, or the
+    /// number of rows of a database table. It is called when logging, when
+    /// calling [`refresh`](ProgressLog::refresh), and when stopping the logger,
+    /// and its result replaces the count. It takes precedence over an attached
+    /// [`counter`](#method.counter).
+    ///
+    /// Paired with a [`Ticker`], the logger will log progress at each interval
+    /// without any update being necessary.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let progress = Arc::new(AtomicUsize::new(0));
+    /// let mut pl = ProgressLogger::default();
+    /// let source = progress.clone();
+    /// pl.item_name("pumpkin")
+    ///     .watch(move || source.load(Ordering::Relaxed));
+    /// pl.start("Smashing pumpkins...");
+    /// let ticker = pl.spawn_ticker();
+    /// for _ in 0..100 {
+    ///     // do something on each pumpkin
+    ///     progress.fetch_add(1, Ordering::Relaxed);
+    /// }
+    /// ticker.done();
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn watch(&mut self, watch: impl FnMut() -> usize + Send + 'static) -> &mut Self {
+        self.watch = Some(Exclusive::new(Box::new(watch)));
+        self
+    }
+
+    /// Set a closure returning a string that will be appended verbatim to
+    /// the progress information.
+    ///
+    /// The closure is called each time the logger is
+    /// [refreshed](ProgressLog::refresh), that is, before each log. This is
+    /// the simplest way to display the state of an algorithm (e.g., the size
+    /// of the frontier of a visit) along with its progress.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let depth = Arc::new(AtomicUsize::new(0));
+    /// let mut pl = ProgressLogger::default();
+    /// let status_depth = depth.clone();
+    /// pl.status(move || format!("depth={}", status_depth.load(Ordering::Relaxed)));
+    /// ```
+    pub fn status(&mut self, status: impl FnMut() -> String + Send + 'static) -> &mut Self {
+        self.status = Some((Exclusive::new(Box::new(status)), String::new()));
+        self
+    }
+
+    /// Set a closure returning the expected number of updates, if known,
+    /// which will be called at each log.
+    ///
+    /// Some activities can estimate their total only lazily (e.g., after
+    /// reading a header). When the closure returns a value, the value
+    /// replaces the [expected number of updates](ProgressLog::expected_updates),
+    /// so the percentage of completion and the estimated time to completion
+    /// are displayed as soon as the total becomes known.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::sync::{Arc, OnceLock};
+    ///
+    /// let total = Arc::new(OnceLock::new());
+    /// let mut pl = ProgressLogger::default();
+    /// let expected = total.clone();
+    /// pl.expected_updates_fn(move || expected.get().copied());
+    /// pl.start("Reading records...");
+    /// // after reading the header
+    /// total.set(1000).unwrap();
+    /// ```
+    pub fn expected_updates_fn(
+        &mut self,
+        expected_updates: impl FnMut() -> Option<usize> + Send + 'static,
+    ) -> &mut Self {
+        self.expected_updates_fn = Some(Exclusive::new(Box::new(expected_updates)));
+        self
+    }
+
+    /// Return the current number of items.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Set a closure that will be called with the logger just before each
+    /// progress log line is emitted.
+    ///
+    /// This makes it possible to refresh application gauges, flush buffers,
+    /// or log additional lines grouped with the progress output.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use log::info;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.before_log(|pl| info!("About to log {} items", pl.count()));
+    /// ```
+    pub fn before_log(&mut self, hook: impl FnMut(&ProgressLogger) + Send + 'static) -> &mut Self {
+        self.before_log = Some(Exclusive::new(Box::new(hook)));
+        self
+    }
+
+    /// Set a closure that will be called with the logger just after each
+    /// progress log line has been emitted.
+    ///
+    /// See [`before_log`](#method.before_log).
+    pub fn after_log(&mut self, hook: impl FnMut(&ProgressLogger) + Send + 'static) -> &mut Self {
+        self.after_log = Some(Exclusive::new(Box::new(hook)));
+        self
+    }
+
+    /// Set the clock providing the current time to the logger.
+    ///
+    /// By default, the logger uses [`Instant::now`]. Setting a
+    /// [`ManualClock`] makes it possible to control the passing of time, for
+    /// example in tests.
+    pub fn clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        let now = clock.now();
+        self.clock = Some(Arc::new(clock));
+        self.last_log_time = now;
+        self.next_log_time = now;
+        self.next_state_time = now;
+        self
+    }
+
+    /// Set deterministic mode.
+    ///
+    /// In deterministic mode, the output of the logger depends only on the
+    /// values provided by its [clock](#method.clock) and on the count: memory
+    /// information is not displayed even if [requested](ProgressLog::display_memory).
+    /// Paired with a [`ManualClock`], deterministic mode makes it
+    /// possible to snapshot-test logging output.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone()).deterministic(true).item_name("pumpkin");
+    /// pl.start("");
+    /// clock.advance(Duration::from_secs(2));
+    /// pl.update_with_count(1000);
+    /// assert_eq!(
+    ///     pl.to_string(),
+    ///     "1,000 pumpkins, 2s, 500.00 pumpkins/s, 2.00 ms/pumpkin"
+    /// );
+    /// ```
+    pub fn deterministic(&mut self, deterministic: bool) -> &mut Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Add a destination for the output of the logger.
+    ///
+    /// The output is sent to the [`log`](https://docs.rs/log) crate and to
+    /// all sinks. See the [`testing`] module for a sink capturing the output.
+    pub fn add_sink(&mut self, sink: impl Sink + 'static) -> &mut Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Return the first error that happened in a sink since the previous
+    /// call, if any.
+    ///
+    /// Sinks never interrupt logging, so errors (e.g., a full disk or an
+    /// unreachable server) would otherwise go unnoticed. Errors of all sinks
+    /// are cleared, but only the first one is returned.
+    pub fn check_sinks(&self) -> std::result::Result<(), ProgressLoggerError> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Some(error) = sink.take_error() {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+        result
+    }
+
+    /// Refresh the logger and return a guard that displays it.
+    ///
+    /// Since [`Display::fmt`] cannot refresh memory information, which
+    /// requires a mutable reference, you should call
+    /// [`refresh`](ProgressLog::refresh) before displaying the logger on your
+    /// own. This method does it for you.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use log::info;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let mut pl = ProgressLogger::default();
+    /// pl.display_memory(true);
+    /// pl.start("Smashing pumpkins...");
+    /// info!("{}", pl.display());
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn display(&mut self) -> RefreshedDisplay<'_> {
+        self.refresh();
+        RefreshedDisplay(self)
+    }
+
+    /// Emit a line of output to the `log` crate and to all sinks.
+    fn emit(&self, level: Level, kind: RecordKind, args: Arguments<'_>) {
+        let prefix = self.fmt_elapsed_prefix();
+        log!(level, "{}{}", prefix, args);
+        self.emit_to_sinks(level, kind, format_args!("{}{}", prefix, args));
+    }
+
+    /// Emit a line of output to all sinks.
+    fn emit_to_sinks(&self, level: Level, kind: RecordKind, args: Arguments<'_>) {
+        for sink in &self.sinks {
+            sink.emit(&Record {
+                level,
+                kind,
+                count: self.count,
+                args,
+            });
+        }
+    }
+
+    /// Return the prefix `[+hh:mm:ss] ` with the elapsed time, if
+    /// [enabled](ProgressLogger::elapsed_prefix), or the empty string, in a
+    /// form that can be displayed without allocating.
+    fn fmt_elapsed_prefix(&self) -> ElapsedPrefix {
+        if !self.elapsed_prefix {
+            return ElapsedPrefix(None);
+        }
+        let elapsed = self.start_time.map_or(Duration::ZERO, |start_time| {
+            self.stop_time
+                .unwrap_or_else(|| self.now())
+                .saturating_duration_since(start_time)
+        });
+        ElapsedPrefix(Some(elapsed.as_secs()))
+    }
+
+    /// Pass a [`Sample`] of the current state to the sinks.
+    fn sample(&self, kind: RecordKind, now: Instant) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let elapsed = self.start_time.map_or(Duration::ZERO, |start_time| {
+            now.saturating_duration_since(start_time)
+        });
+        let interval_count = self.count.saturating_sub(self.last_count);
+        let interval =
+            now.saturating_duration_since(self.last_log_time.max(self.start_time.unwrap_or(now)));
+        let finite = |x: f64| Some(x).filter(|x| x.is_finite());
+        let sample = Sample {
+            kind,
+            run_id: self.run_id.as_deref(),
+            item_name: &self.item_name,
+            time: self.system_now(),
+            count: self.count,
+            elapsed,
+            speed: finite(self.count as f64 / elapsed.as_secs_f64()),
+            interval_count,
+            interval_speed: finite(interval_count as f64 / interval.as_secs_f64()),
+            expected_updates: self.expected_updates,
+            time_to_end: self
+                .expected_updates
+                .map(|expected_updates| self.time_to_end(expected_updates, elapsed)),
+            memory: self
+                .system
+                .as_ref()
+                .filter(|_| !self.deterministic)
+                .and_then(|system| system.process(self.pid))
+                .map(|process| process.memory()),
+        };
+        for sink in &self.sinks {
+            sink.sample(&sample);
+        }
+    }
+
+    /// Set whether to freeze the time units chosen automatically.
+    ///
+    /// By default, if no [time unit](ProgressLog::time_unit) is set, the
+    /// logger chooses at each log a readable unit, which might make
+    /// consecutive lines switch units. If this option is enabled, the units
+    /// are chosen at the first log in which the speed is defined, and then
+    /// kept until the logger is [reset](ProgressLog::reset) or restarted.
+    pub fn freeze_time_unit(&mut self, freeze_time_unit: bool) -> &mut Self {
+        self.freeze_time_unit = freeze_time_unit;
+        self
+    }
+
+    /// Set the smoothing factor of the [local speed](ProgressLog::local_speed).
+    ///
+    /// The local speed displayed is an exponentially weighted moving average
+    /// of the speeds of the log intervals: the speed of the current interval
+    /// is given weight `smoothing`, and the previous average weight
+    /// 1 − `smoothing`. The default value, 1, disables smoothing; smaller
+    /// values make the local speed less jumpy on bursty workloads. Values
+    /// outside the interval (0..1] are clamped.
+    pub fn local_speed_smoothing(&mut self, smoothing: f64) -> &mut Self {
+        self.local_speed_smoothing = if smoothing.is_nan() {
+            1.0
+        } else {
+            smoothing.clamp(f64::MIN_POSITIVE, 1.0)
+        };
+        self
+    }
+
+    /// Return the local speed in items per second at the given time,
+    /// smoothed with the average of the previous intervals.
+    fn local_speed_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_log_time);
+        let speed = self.count.saturating_sub(self.last_count) as f64 / elapsed.as_secs_f64();
+        match self.smoothed_local_speed {
+            Some(smoothed) if speed.is_finite() => {
+                self.local_speed_smoothing * speed + (1.0 - self.local_speed_smoothing) * smoothed
+            }
+            _ => speed,
+        }
+    }
+
+    /// Set the level of the output of [`done`](ProgressLog::done) and
+    /// [`done_with_count`](ProgressLog::done_with_count).
+    ///
+    /// The default level is `Info`.
+    pub fn done_level(&mut self, done_level: Level) -> &mut Self {
+        self.done_level = done_level;
+        self
+    }
+
+    /// Set whether [`done`](ProgressLog::done) and
+    /// [`done_with_count`](ProgressLog::done_with_count) print `Completed.`
+    /// before the final stats.
+    ///
+    /// The default is true.
+    pub fn completed_message(&mut self, completed_message: bool) -> &mut Self {
+        self.completed_message = completed_message;
+        self
+    }
+
+    /// Set whether [`done`](ProgressLog::done) and
+    /// [`done_with_count`](ProgressLog::done_with_count) display the final
+    /// stats.
+    ///
+    /// The default is true.
+    pub fn done_summary(&mut self, done_summary: bool) -> &mut Self {
+        self.done_summary = done_summary;
+        self
+    }
+
+    /// Set whether [`done`](ProgressLog::done) and
+    /// [`done_with_count`](ProgressLog::done_with_count) display the final
+    /// stats as an aligned multi-line report.
+    ///
+    /// The report contains the number of items, the elapsed time, the
+    /// average speed, the peak speed over a log interval and, if available,
+    /// the peak resident memory (sampled at each refresh when
+    /// [memory display](ProgressLog::display_memory) is enabled) and the CPU
+    /// time of the process. Peak memory and CPU time are omitted in
+    /// [deterministic mode](#method.deterministic).
+    ///
+    /// The default is false.
+    pub fn detailed_report(&mut self, detailed_report: bool) -> &mut Self {
+        self.detailed_report = detailed_report;
+        self
+    }
+
+    /// Configure the logger for a batched loop (e.g., fetching rows from a
+    /// database cursor), setting the expected number of updates to the given
+    /// number of rows.
+    ///
+    /// Each batch must be recorded by [`update_batch`](#method.update_batch),
+    /// and the logger will display both the number of rows and the number of
+    /// batches (out of the expected number of batches), with an estimate of
+    /// the time to completion based on rows.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("row").batches(10_000, 1_000);
+    /// pl.start("Fetching rows...");
+    /// for _ in 0..10 {
+    ///     // fetch a batch of rows
+    ///     pl.update_batch(1_000);
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn batches(&mut self, expected_rows: usize, batch_size: usize) -> &mut Self {
+        self.batches = Some((0, batch_size));
+        self.expected_updates(Some(expected_rows))
+    }
+
+    /// Record a batch of the given number of rows and check whether it is
+    /// time to log.
+    ///
+    /// See [`batches`](#method.batches).
+    pub fn update_batch(&mut self, rows: usize) {
+        let (batches, _) = self.batches.get_or_insert((0, 0));
+        *batches = batches.saturating_add(1);
+        self.update_with_count(rows);
+    }
+
+    /// Set the completed fraction of the activity, between 0 and 1.
+    ///
+    /// If the [expected number of updates](ProgressLog::expected_updates) is
+    /// not set, the percentage of completion and the estimate of the time to
+    /// completion are computed from this fraction. This is useful when
+    /// completion is measured in a different unit than the count, as in the
+    /// case of a [`ProgressDecompressedReader`], whose count is the number of
+    /// decompressed bytes, but whose completion is measured by the position
+    /// in the compressed input.
+    ///
+    /// The fraction is cleared when the logger is [reset](ProgressLog::reset).
+    pub fn completed_fraction(&mut self, completed_fraction: Option<f64>) -> &mut Self {
+        self.completed_fraction = completed_fraction
+            .filter(|fraction| !fraction.is_nan())
+            .map(|fraction| fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Set whether to display the wall-clock times at which the logger was
+    /// started and stopped, in RFC 3339 format, in the final stats.
+    ///
+    /// Timestamps make it possible to correlate runs with external events
+    /// without relying on the timestamps of the logging backend.
+    pub fn display_timestamps(&mut self, display_timestamps: bool) -> &mut Self {
+        self.display_timestamps = display_timestamps;
+        self
+    }
+
+    /// Set whether to display the number of items processed since the last log.
+    ///
+    /// When the log interval is fixed, the raw number of items processed
+    /// during the interval (e.g., `+124,502 this interval`) is often easier
+    /// to reason about than a speed.
+    pub fn display_delta(&mut self, display_delta: bool) -> &mut Self {
+        self.display_delta = display_delta;
+        self
+    }
+
+    /// Set whether to display the ratio between the number of
+    /// [successes](ProgressLog::update_success) and the count, with the
+    /// given label (e.g., `matched`).
+    ///
+    /// The ratio is displayed as a percentage followed by the label. Pass
+    /// [`None`] to hide the ratio.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("record").success_ratio(Some("matched"));
+    /// pl.start("Matching records...");
+    /// for i in 0..100 {
+    ///     if i % 4 != 0 {
+    ///         pl.update_success();
+    ///     }
+    ///     pl.update();
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn success_ratio(&mut self, label: Option<&str>) -> &mut Self {
+        self.success_label = label.map(str::to_owned);
+        self
+    }
+
+    /// Set whether to log also when the count reaches a power of two (i.e., at
+    /// 1, 2, 4, 8, … items), in addition to logging at each log interval.
+    ///
+    /// This mode documents densely the early part of activities whose
+    /// progress is very skewed, and sparsely their tail. The default is
+    /// false.
+    pub fn exponential_logging(&mut self, exponential_logging: bool) -> &mut Self {
+        self.exponential_logging = exponential_logging;
+        self.update_next_log_count();
+        self
+    }
+
+    /// Set the minimum number of progress lines to log when the expected
+    /// number of updates is known.
+    ///
+    /// The logger logs, in addition to each log interval, each time the
+    /// count has increased by the expected number of updates divided by
+    /// `min_logs` + 1 since the last log, so at least `min_logs` lines
+    /// appear regardless of the duration of the activity. If [`None`], which
+    /// is the default, logs depend only on time.
+    pub fn min_logs(&mut self, min_logs: Option<usize>) -> &mut Self {
+        self.min_logs = min_logs;
+        self.update_next_log_count();
+        self
+    }
+
+    /// Set the number of decimal digits of the percentage of completion.
+    ///
+    /// Zero displays integer percentages, which are sufficient for coarse
+    /// activities, whereas activities with trillions of items might need
+    /// more digits to show any progress. Precisions larger than 16, which
+    /// would display only noise, are reduced to 16. The default is 2.
+    pub fn percent_precision(&mut self, percent_precision: usize) -> &mut Self {
+        self.percent_precision = percent_precision.min(16);
+        self
+    }
+
+    /// Set whether to display all durations (e.g., the elapsed time and the
+    /// estimated time to completion) as integer milliseconds.
+    ///
+    /// Together with a [time unit](ProgressLog::time_unit) for speeds, this
+    /// mode makes the output fully machine-oriented, so that scripts can
+    /// parse it without heuristics. The default is false.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone())
+    ///     .deterministic(true)
+    ///     .raw_millis(true)
+    ///     .time_unit(Some(TimeUnit::Seconds));
+    /// pl.start("");
+    /// clock.advance(Duration::from_secs(62));
+    /// pl.update_with_count(1000);
+    /// assert_eq!(
+    ///     pl.to_string(),
+    ///     "1000 items, 62000ms, 16.13 items/s, 0.06 s/item"
+    /// );
+    /// ```
+    pub fn raw_millis(&mut self, raw_millis: bool) -> &mut Self {
+        self.raw_millis = raw_millis;
+        self
+    }
+
+    /// Set whether to prefix each line with the time elapsed since the start,
+    /// as in `[+00:42:17] `.
+    ///
+    /// The prefix is added to the lines passed to the `log` crate and to the
+    /// [sinks](ProgressLogger::add_sink), but not to the output of
+    /// [`Display`]. Unlike the timestamps added by logging backends, the
+    /// prefix is measured with the [clock](ProgressLogger::clock) of the
+    /// logger, and it survives copying and pasting the output. The default
+    /// is false.
+    pub fn elapsed_prefix(&mut self, elapsed_prefix: bool) -> &mut Self {
+        self.elapsed_prefix = elapsed_prefix;
+        self
+    }
+
+    /// Set the maximum number of progress lines logged automatically.
+    ///
+    /// If the expected number of updates is known, the log interval is
+    /// stretched as needed to spread the remaining lines over the estimated
+    /// time left; otherwise, the interval is stretched to at least the
+    /// elapsed time, so the number of lines grows logarithmically with time.
+    /// Once the maximum is reached, the logger stops logging automatically. Forced
+    /// logs (e.g., [`log_now`](ProgressLog::log_now)) are not affected, but
+    /// they count towards the maximum. This setting keeps predictable the
+    /// size of the output of very long activities. If [`None`], which is the
+    /// default, there is no maximum.
+    pub fn max_logs(&mut self, max_logs: Option<usize>) -> &mut Self {
+        self.max_logs = max_logs;
+        self.update_next_log_count();
+        self
+    }
+
+    /// Set the maximum relative jitter of the log interval.
+    ///
+    /// If set to a fraction `f`, at each start the log interval is multiplied
+    /// by a random factor between 1 − `f` and 1 + `f`. When many identical
+    /// jobs start at the same time, this decorrelates their progress lines,
+    /// which would otherwise hit the logging pipeline at the same instants.
+    /// The fraction is clamped to [0 . . 1]. The default is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// // Log every 9 to 11 seconds
+    /// pl.log_interval_jitter(Some(0.1));
+    /// ```
+    pub fn log_interval_jitter(&mut self, jitter: Option<f64>) -> &mut Self {
+        self.log_interval_jitter = jitter
+            .filter(|jitter| !jitter.is_nan())
+            .map(|jitter| jitter.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Return the log interval multiplied by the jitter factor of the
+    /// current run.
+    fn jittered_log_interval(&self) -> Duration {
+        Duration::try_from_secs_f64(self.log_interval.as_secs_f64() * self.jitter_factor)
+            .unwrap_or(self.log_interval)
+    }
+
+    /// Compute the count at which the next log will happen regardless of
+    /// time, if any.
+    fn update_next_log_count(&mut self) {
+        let mut next_log_count = usize::MAX;
+        if self.exponential_logging {
+            next_log_count = self
+                .count
+                .saturating_add(1)
+                .checked_next_power_of_two()
+                .unwrap_or(usize::MAX);
+        }
+        if let (Some(min_logs), Some(expected_updates)) = (self.min_logs, self.expected_updates) {
+            let step = (expected_updates / min_logs.saturating_add(1)).max(1);
+            next_log_count = next_log_count.min(self.count.saturating_add(step));
+        }
+        if matches!(self.max_logs, Some(max_logs) if self.logs >= max_logs) {
+            next_log_count = usize::MAX;
+        }
+        self.next_log_count = next_log_count;
+    }
+
+    /// Set the minimum number of items that must have been processed since
+    /// the last log for the logger to log when the log interval has elapsed.
+    ///
+    /// If fewer items have been processed, the logger waits for more items
+    /// instead of printing a line almost identical to the previous one, which
+    /// avoids pages of output during slow phases. However, the logger logs
+    /// anyway if ten log intervals have elapsed since the last log, so that
+    /// stalled activities do not go silent. Forced logs (e.g.,
+    /// [`log_now`](ProgressLog::log_now)) are not affected. The default is
+    /// zero.
+    pub fn min_items_per_log(&mut self, min_items_per_log: usize) -> &mut Self {
+        self.min_items_per_log = min_items_per_log;
+        self
+    }
+
+    /// Set the delay before the first log, independently of the log
+    /// interval.
+    ///
+    /// A short delay makes activities that are short, but not instantaneous,
+    /// produce at least one progress line. If [`None`], the first log happens
+    /// after a log interval, which is the default.
+    pub fn first_log_delay(&mut self, first_log_delay: Option<Duration>) -> &mut Self {
+        self.first_log_delay = first_log_delay;
+        self
+    }
+
+    /// Set whether to suppress progress lines whose content, except for
+    /// timing information, is identical to that of the previous line.
+    ///
+    /// Suppressed lines are replaced by a single `… (repeated n×)` line,
+    /// emitted before the next different line or the final stats, which keeps
+    /// compact the logs of long stalled phases. The default is false.
+    pub fn dedup(&mut self, dedup: bool) -> &mut Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Return whether enough items have been processed since the last log
+    /// to log when the log interval has elapsed, or whether the logger has
+    /// been waiting for items for so long that it should log anyway.
+    fn enough_items_to_log(&self, now: Instant) -> bool {
+        self.count.saturating_sub(self.last_count) >= self.min_items_per_log
+            || now.saturating_duration_since(self.last_log_time)
+                >= self.log_interval.saturating_mul(STALLED_LOG_INTERVALS)
+    }
+
+    /// Set the theoretical peak speed in items per second (e.g., the disk
+    /// bandwidth divided by the size of an item).
+    ///
+    /// If set, each log displays the efficiency, that is, the speed during
+    /// the last log interval as a percentage of the peak speed, and the final
+    /// stats display the average speed as a percentage of the peak speed.
+    /// The default is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone())
+    ///     .deterministic(true)
+    ///     .item_name("block")
+    ///     .peak_rate(Some(500.0));
+    /// pl.start("");
+    /// clock.advance(Duration::from_secs(10));
+    /// pl.update_with_count(2150);
+    /// assert!(pl.to_string().ends_with("; 43.00% of peak"));
+    /// ```
+    pub fn peak_rate(&mut self, peak_rate: Option<f64>) -> &mut Self {
+        self.peak_rate = peak_rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// Format the given speed in items per second as a percentage of the
+    /// [peak speed](#method.peak_rate), if set.
+    fn fmt_efficiency(&self, f: &mut Formatter<'_>, speed: f64) -> Result {
+        if let (Some(peak_rate), true) = (self.peak_rate, speed.is_finite()) {
+            f.write_fmt(format_args!("; {:.2}% of peak", 100.0 * speed / peak_rate))?;
+        }
+        Ok(())
+    }
+
+    /// Set a target speed in items per second.
+    ///
+    /// The logger does not enforce the target speed, but
+    /// [`pause_hint`](#method.pause_hint) will tell you how long to sleep to
+    /// stay below it.
+    pub fn target_rate(&mut self, target_rate: Option<f64>) -> &mut Self {
+        self.target_rate = target_rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// Return how long the caller should pause to keep the average speed
+    /// since the start below the [target speed](#method.target_rate).
+    ///
+    /// The result is zero if no target speed is set, if the logger is not
+    /// running, or if the speed is already below the target.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// stderrlog::new().verbosity(2).init()?;
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("page").target_rate(Some(1000.0));
+    /// pl.start("Crawling politely...");
+    /// for _ in 0..100 {
+    ///     // download a page
+    ///     pl.update();
+    ///     std::thread::sleep(pl.pause_hint());
+    /// }
+    /// pl.done();
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn pause_hint(&self) -> Duration {
+        match (self.target_rate, self.start_time, self.stop_time) {
+            (Some(target_rate), Some(start_time), None) => {
+                let elapsed = self.now().saturating_duration_since(start_time);
+                Duration::try_from_secs_f64(self.count as f64 / target_rate)
+                    .unwrap_or(Duration::MAX)
+                    .saturating_sub(elapsed)
+            }
+            _ => Duration::ZERO,
         }
     }
-}
 
-impl ProgressLogger {
-    /// Calls to [light_update](#method.light_update) will cause a call to
-    /// [`Instant::now`] only if the current count
-    /// is a multiple of this mask plus one.
-    pub const LIGHT_UPDATE_MASK: usize = (1 << 20) - 1;
+    /// Return the current wall-clock time according to the clock of the logger.
+    fn system_now(&self) -> SystemTime {
+        match &self.clock {
+            Some(clock) => clock.system_now(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Return the current time according to the clock of the logger.
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        if let Some(now) = self.frozen_now {
+            return now;
+        }
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Read the count from the watch closure, if any, or add the increments
+    /// of the attached counter and of the relaxed counters.
+    fn sync_count(&mut self) {
+        if let Some(watch) = &mut self.watch {
+            self.count = watch.get_mut()();
+            return;
+        }
+        if let Some(counter) = &self.counter {
+            let value = counter.load(Ordering::Relaxed);
+            self.count = self
+                .count
+                .saturating_add(value.wrapping_sub(self.counter_base));
+            self.counter_base = value;
+        }
+        if !self.relaxed.is_empty() {
+            let value = self.relaxed_sum();
+            self.count = self
+                .count
+                .saturating_add(value.wrapping_sub(self.relaxed_base));
+            self.relaxed_base = value;
+        }
+    }
 
+    #[cold]
+    #[inline(never)]
     fn log(&mut self, now: Instant) {
         self.refresh();
-        info!("{}", self);
+        self.sample_queue();
+        if let Some(expected_updates) = self
+            .expected_updates_fn
+            .as_mut()
+            .and_then(|f| f.get_mut()())
+        {
+            self.expected_updates = Some(expected_updates);
+        }
+        if self.freeze_time_unit && self.frozen_time_units.is_none() {
+            if let Some(start_time) = self.start_time {
+                let seconds_per_item =
+                    now.saturating_duration_since(start_time).as_secs_f64() / self.count as f64;
+                if seconds_per_item.is_finite() && seconds_per_item > 0.0 {
+                    self.frozen_time_units = Some((
+                        TimeUnit::nice_time_unit(seconds_per_item),
+                        TimeUnit::nice_speed_unit(seconds_per_item),
+                    ));
+                }
+            }
+        }
+        if !self.is_repeated() {
+            if let Some(mut hook) = self.before_log.take() {
+                hook.get_mut()(self);
+                self.before_log = Some(hook);
+            }
+            self.log_stats(Level::Info, RecordKind::Progress);
+            self.logs = self.logs.saturating_add(1);
+            self.sample(RecordKind::Progress, now);
+            if let Some(mut hook) = self.after_log.take() {
+                hook.get_mut()(self);
+                self.after_log = Some(hook);
+            }
+        }
+        self.check_low_memory();
+        self.update_peak_speed(now);
+        self.record_interval_speed(now);
+        let local_speed = self.local_speed_at(now);
+        if local_speed.is_finite() {
+            self.smoothed_local_speed = Some(local_speed);
+        }
         self.last_count = self.count;
+        self.last_retries = self.retries;
+        self.last_backlog = self.backlog();
+        self.reset_queue_watermarks();
+        self.update_last_memory();
         self.last_log_time = now;
-        self.next_log_time = now + self.log_interval;
+        self.next_log_time = self.next_log_time_from(now);
+        self.update_next_log_count();
+        self.update_state(now);
+    }
+
+    /// Return whether the logger has been started and has not been stopped.
+    fn is_running(&self) -> bool {
+        self.start_time.is_some() && self.stop_time.is_none()
+    }
+
+    /// Log a warning if the logger has not been started, returning whether
+    /// it has been started.
+    fn check_started(&self, method: &str) -> bool {
+        if self.start_time.is_none() {
+            self.emit(
+                Level::Warn,
+                RecordKind::Info,
+                format_args!("ProgressLogger::{}() called before start()", method),
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Check whether it is time to log on behalf of
+    /// [`light_update`](ProgressLog::light_update), keeping the check out of
+    /// the hot loop.
+    #[cold]
+    #[inline(never)]
+    fn light_log_if(&mut self) {
+        self.log_if();
     }
 
     fn log_if(&mut self) {
-        let now = Instant::now();
-        if self.next_log_time <= now {
+        if !self.is_running() {
+            return;
+        }
+        let now = self.now();
+        self.log_if_at(now);
+    }
+
+    /// Log if it is time to at the given time, which must be the current
+    /// time or a recent snapshot of it.
+    fn log_if_at(&mut self, now: Instant) {
+        if !self.is_running() {
+            return;
+        }
+        let time_to_log = self.next_log_time <= now;
+        // Counts fed through counters or watch closures are synchronized only
+        // here, so that item-based conditions do not see a stale count
+        if time_to_log || self.next_log_count != usize::MAX {
+            self.sync_count();
+        }
+        if (time_to_log && self.enough_items_to_log(now)) || self.count >= self.next_log_count {
             self.log(now);
+        } else if self.next_state_time <= now {
+            self.sample_queue();
+            self.update_state(now);
+        }
+    }
+
+    /// Log the current state of the logger at the given level.
+    ///
+    /// If the `kv` feature is enabled, the count, the speed in items per second,
+    /// the percentage of completion and the milliseconds to completion are
+    /// attached to the record as structured key-value fields.
+    ///
+    /// The line is formatted once, in a buffer reused across calls, and then
+    /// passed to the `log` crate and to the sinks.
+    fn log_stats(&mut self, level: Level, kind: RecordKind) {
+        if !log::log_enabled!(level) && self.sinks.is_empty() {
+            return;
+        }
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        // Formatting into a String cannot fail; lines in stable format have
+        // no prefix
+        let _ = if self.stable_format {
+            std::fmt::Write::write_fmt(&mut line, format_args!("{}", self.stable_line()))
+        } else {
+            std::fmt::Write::write_fmt(
+                &mut line,
+                format_args!("{}{}", self.fmt_elapsed_prefix(), self),
+            )
+        };
+        #[cfg(feature = "kv")]
+        {
+            let (count, speed, percent, millis_to_end) = self.kv_fields();
+            log!(level, count = count, speed = speed, percent = percent, millis_to_end = millis_to_end; "{}", line);
+        }
+        #[cfg(not(feature = "kv"))]
+        log!(level, "{}", line);
+        self.emit_to_sinks(level, kind, format_args!("{}", line));
+        self.line = line;
+    }
+
+    /// Set the expected duration of the activity.
+    ///
+    /// If neither the [expected number of
+    /// updates](ProgressLog::expected_updates) nor the [completed
+    /// fraction](ProgressLogger::completed_fraction) are set, the percentage
+    /// of completion and the time to completion are computed from the
+    /// elapsed time alone. This is useful for activities with a known
+    /// duration but no items, such as a soak test: in this case, you can log
+    /// periodically without calling [`update`](ProgressLog::update) by using
+    /// a [`Ticker`].
+    ///
+    /// If the expected number of updates is set, too, the logger displays
+    /// both estimates, and whether the activity is ahead of or behind
+    /// schedule, comparing the estimate based on the count with the time left.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone())
+    ///     .deterministic(true)
+    ///     .expected_duration(Some(Duration::from_secs(30 * 60)));
+    /// pl.start("Soaking...");
+    /// clock.advance(Duration::from_secs(12 * 60));
+    /// assert!(pl.to_string().ends_with("; 40.00% done, 18m 0s to end"));
+    /// ```
+    pub fn expected_duration(&mut self, expected_duration: Option<Duration>) -> &mut Self {
+        self.expected_duration = expected_duration;
+        self
+    }
+
+    /// Set the identifier of the run.
+    ///
+    /// The identifier is displayed in the start line, passed to the
+    /// [sinks](ProgressLogger::add_sink) in each [`Sample`], and included in
+    /// the [summary](ProgressLogger::summary), so that results remain
+    /// attributable long after the run.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let logs = dsi_progress_logger::testing::CapturedLogs::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.add_sink(logs.clone())
+    ///     .run_id("2024-03-01-a")
+    ///     .metadata("git", "3f2a9c1")
+    ///     .metadata("alpha", "0.85");
+    /// pl.start("Computing PageRank...");
+    /// assert_eq!(
+    ///     logs.lines(),
+    ///     ["Computing PageRank... [run 2024-03-01-a; git=3f2a9c1, alpha=0.85]"]
+    /// );
+    /// ```
+    pub fn run_id(&mut self, run_id: impl AsRef<str>) -> &mut Self {
+        self.run_id = Some(run_id.as_ref().to_owned());
+        self
+    }
+
+    /// Attach a key/value pair of metadata to the run (e.g., a commit hash,
+    /// an input path, or a parameter).
+    ///
+    /// Metadata are displayed in the start line and included in the
+    /// [summary](ProgressLogger::summary) in the order in which they were
+    /// attached; attaching a key again replaces its value. See also
+    /// [`run_id`](ProgressLogger::run_id).
+    pub fn metadata(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> &mut Self {
+        let value = value.as_ref().to_owned();
+        match self.metadata.iter_mut().find(|(k, _)| k == key.as_ref()) {
+            Some((_, v)) => *v = value,
+            None => self.metadata.push((key.as_ref().to_owned(), value)),
+        }
+        self
+    }
+
+    /// Return the count, the speed in items per second (if defined), and, if
+    /// the number of expected updates is known, the percentage of completion
+    /// and the estimated milliseconds to completion.
+    fn kv_fields(&self) -> (usize, Option<f64>, Option<f64>, Option<u64>) {
+        let elapsed = match (self.start_time, self.stop_time) {
+            (Some(start_time), Some(stop_time)) => stop_time.saturating_duration_since(start_time),
+            (Some(start_time), None) => self.now().saturating_duration_since(start_time),
+            _ => Duration::ZERO,
+        };
+        let speed = Some(self.count as f64 / elapsed.as_secs_f64())
+            .filter(|speed| speed.is_finite() && self.count != 0);
+        let percent = self
+            .expected_updates
+            .map(|expected_updates| self.count as f64 / expected_updates as f64)
+            .or(self.completed_fraction)
+            .or(self
+                .expected_duration
+                .map(|expected_duration| elapsed.as_secs_f64() / expected_duration.as_secs_f64()))
+            .map(|fraction| 100.0 * fraction);
+        let millis_to_end = self
+            .expected_updates
+            .map(|expected_updates| self.millis_to_end(expected_updates, elapsed))
+            .or(self
+                .expected_duration
+                .map(|expected_duration| expected_duration.saturating_sub(elapsed).as_millis()))
+            .map(|millis_to_end| millis_to_end.try_into().unwrap_or(u64::MAX));
+        (self.count, speed, percent, millis_to_end)
+    }
+
+    /// Return the time of the next log if we log at the given time.
+    ///
+    /// If the log interval is so large that the result cannot be represented,
+    /// the next log will happen in about a century.
+    fn next_log_time_from(&self, now: Instant) -> Instant {
+        now.checked_add(self.effective_log_interval(now))
+            .or_else(|| now.checked_add(Duration::from_secs(u32::MAX as u64)))
+            .unwrap_or(now)
+    }
+
+    /// Return the log interval, stretched if necessary so that the number of
+    /// progress lines does not exceed the [maximum](#method.max_logs).
+    ///
+    /// If the expected number of updates is known, the estimated time left is
+    /// divided evenly among the remaining lines; otherwise, the interval is
+    /// at least the elapsed time. When no more lines are allowed, the
+    /// interval is [`Duration::MAX`].
+    fn effective_log_interval(&self, now: Instant) -> Duration {
+        let log_interval = self.jittered_log_interval();
+        let Some(max_logs) = self.max_logs else {
+            return log_interval;
+        };
+        let remaining_logs = max_logs.saturating_sub(self.logs);
+        if remaining_logs == 0 {
+            return Duration::MAX;
+        }
+        let elapsed = self.start_time.map_or(Duration::ZERO, |start_time| {
+            now.saturating_duration_since(start_time)
+        });
+        match self.expected_updates {
+            Some(expected_updates) => {
+                let time_left = self.time_to_end(expected_updates, elapsed);
+                log_interval.max(time_left.div_f64(remaining_logs as f64))
+            }
+            None => log_interval.max(elapsed),
+        }
+    }
+
+    /// Estimate the milliseconds to completion given the number of expected
+    /// updates and the elapsed time, saturating in case of overflow.
+    fn millis_to_end(&self, expected_updates: usize, elapsed: Duration) -> u128 {
+        utils::millis_to_end(self.count, expected_updates, elapsed)
+    }
+
+    /// Estimate the time to completion given the number of expected updates
+    /// and the elapsed time, saturating in case of overflow.
+    fn time_to_end(&self, expected_updates: usize, elapsed: Duration) -> Duration {
+        millis_to_duration(self.millis_to_end(expected_updates, elapsed))
+    }
+
+    /// Return whether deduplication is enabled and the next progress line
+    /// would be identical to the previous one, in which case the line is
+    /// counted as repeated.
+    ///
+    /// Lines are compared after rendering them as if no time had elapsed
+    /// since the start, so that timing information (elapsed time, speed,
+    /// time to completion) does not affect the comparison. Otherwise, the
+    /// repetitions of the previous line, if any, are reported, and the next
+    /// line is recorded.
+    fn is_repeated(&mut self) -> bool {
+        if !self.dedup {
+            return false;
+        }
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        self.frozen_now = self.start_time;
+        // Formatting into a String cannot fail
+        let _ = if self.stable_format {
+            std::fmt::Write::write_fmt(&mut line, format_args!("{}", self.stable_line()))
+        } else {
+            std::fmt::Write::write_fmt(&mut line, format_args!("{}", self))
+        };
+        self.frozen_now = None;
+        let repeated = self.last_line.as_ref() == Some(&line);
+        if repeated {
+            self.repeated = self.repeated.saturating_add(1);
+        } else {
+            self.report_repeated();
+            self.last_line
+                .get_or_insert_with(String::new)
+                .clone_from(&line);
+        }
+        self.line = line;
+        repeated
+    }
+
+    /// Report the repetitions of the last progress line, if any.
+    fn report_repeated(&mut self) {
+        if self.repeated != 0 {
+            self.emit(
+                Level::Info,
+                RecordKind::Info,
+                format_args!("… (repeated {}×)", self.fmt_count(self.repeated)),
+            );
+            self.repeated = 0;
+        }
+    }
+
+    /// Print the [completion message](Messages::completed) and display the final stats of a stopped logger.
+    fn completed(&mut self) {
+        self.report_repeated();
+        if self.completed_message {
+            self.emit(
+                self.done_level,
+                RecordKind::Completed,
+                format_args!("{}", self.messages.completed),
+            );
+        }
+        self.final_stats(self.done_level);
+    }
+
+    /// Stop the logger, print `Interrupted after` followed by the elapsed
+    /// time and the count, and display the final stats, all at the
+    /// [warning](Level::Warn) level.
+    ///
+    /// This method is meant to be called when the activity is interrupted,
+    /// for example by a signal: in this case, the partial stats show how far
+    /// it got. See also
+    /// [`ProgressLoggerHandle::exit_on_signals`](crate::ProgressLoggerHandle#method.exit_on_signals),
+    /// which is available with the `signals` feature.
+    pub fn interrupted(&mut self) {
+        if !self.check_started("interrupted") {
+            return;
+        }
+        self.stop();
+        self.report_repeated();
+        let elapsed = self.elapsed().unwrap_or_default();
+        self.emit(
+            Level::Warn,
+            RecordKind::Completed,
+            format_args!(
+                "{} {}, {} {}",
+                self.messages.interrupted,
+                self.fmt_duration(elapsed),
+                self.fmt_count(self.count),
+                self.item_name_for(self.count)
+            ),
+        );
+        self.final_stats(Level::Warn);
+    }
+
+    /// Display the final stats, if enabled, at the given level.
+    fn final_stats(&mut self, level: Level) {
+        // just to avoid wrong reuses
+        self.expected_updates = None;
+        self.refresh();
+        if let Some(stop_time) = self.stop_time {
+            self.update_peak_speed(stop_time);
+        }
+        if self.done_summary {
+            if self.detailed_report && !self.stable_format {
+                self.emit(
+                    level,
+                    RecordKind::Summary,
+                    format_args!("{}", DetailedReport(self)),
+                );
+            } else {
+                self.log_stats(level, RecordKind::Summary);
+            }
+        }
+        self.end_activity(level);
+        if let Some(stop_time) = self.stop_time {
+            self.sample(RecordKind::Summary, stop_time);
+            self.update_state(stop_time);
+        }
+    }
+
+    /// Update the peak speed with the speed since the last log.
+    fn update_peak_speed(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_log_time);
+        let speed = self.count.saturating_sub(self.last_count) as f64 / elapsed.as_secs_f64();
+        if speed.is_finite() {
+            self.peak_speed = self.peak_speed.max(speed);
+        }
+    }
+
+    /// Format a number of items, thousands separated unless a time unit is set.
+    fn fmt_count(&self, count: usize) -> FmtCount {
+        if self.time_unit.is_none() {
+            let mut buffer = Buffer::new();
+            buffer.write_formatted(&count, &Locale::en);
+            FmtCount::Separated(buffer)
+        } else {
+            FmtCount::Plain(count)
+        }
+    }
+
+    /// Set the singular and plural forms of the name of an item, overriding
+    /// those computed by [`item_name`](ProgressLog::item_name) using English
+    /// rules.
+    ///
+    /// The singular form is also used as the name of an item. This method is
+    /// useful for irregular nouns the automatic rules get wrong, and for
+    /// non-English names.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_names("sommet", "sommets");
+    /// pl.start("Visite du graphe...");
+    /// pl.update_with_count(2);
+    /// assert!(pl.to_string().starts_with("2 sommets, "));
+    /// ```
+    pub fn item_names(&mut self, singular: impl AsRef<str>, plural: impl AsRef<str>) -> &mut Self {
+        self.item_name = singular.as_ref().into();
+        self.item_singular = singular.as_ref().into();
+        self.item_plural = plural.as_ref().into();
+        self
+    }
+
+    /// Return the singular or the plural form of the name of an item,
+    /// depending on the given count.
+    pub(crate) fn item_name_for(&self, count: usize) -> &str {
+        if count == 1 {
+            &self.item_singular
+        } else {
+            &self.item_plural
+        }
+    }
+
+    /// Replace the placeholders `{expected}` and `{item_name}` in a start
+    /// message.
+    fn expand_placeholders(&self, msg: &str) -> String {
+        if !msg.contains('{') {
+            return msg.to_owned();
+        }
+        let expected = self.expected_updates.map_or("?".to_owned(), |expected| {
+            self.fmt_count(expected).to_string()
+        });
+        let count = self.expected_updates.unwrap_or(2);
+        msg.replace("{expected}", &expected)
+            .replace("{item_name}", self.item_name_for(count))
+    }
+
+    /// Append the identifier of the run and the metadata, if any, to a start
+    /// message.
+    fn append_run_info(&self, msg: &mut String) {
+        if self.run_id.is_none() && self.metadata.is_empty() {
+            return;
+        }
+        msg.push_str(" [");
+        if let Some(run_id) = &self.run_id {
+            msg.push_str("run ");
+            msg.push_str(run_id);
+            if !self.metadata.is_empty() {
+                msg.push_str("; ");
+            }
+        }
+        for (i, (key, value)) in self.metadata.iter().enumerate() {
+            if i != 0 {
+                msg.push_str(", ");
+            }
+            msg.push_str(key);
+            msg.push('=');
+            msg.push_str(value);
+        }
+        msg.push(']');
+    }
+
+    /// Format a duration, using the time unit if it is a
+    /// [custom](TimeUnit::Custom) one.
+    fn fmt_duration(&self, duration: Duration) -> String {
+        if self.raw_millis {
+            return format!("{}ms", duration.as_millis());
+        }
+        match self.time_unit {
+            Some(TimeUnit::Custom { label, seconds }) => {
+                format!("{:.2}{}", duration.as_secs_f64() / seconds, label)
+            }
+            _ => TimeUnit::pretty_print(duration.as_millis()),
+        }
+    }
+
+    /// Return the backlog between produced and consumed items.
+    fn backlog(&self) -> usize {
+        self.produced.unwrap_or(0).saturating_sub(self.count)
+    }
+
+    /// Format the time-based estimate and its divergence from the given
+    /// count-based estimate of the time to completion, if an [expected
+    /// duration](ProgressLogger::expected_duration) is set.
+    fn fmt_schedule(
+        &self,
+        f: &mut Formatter<'_>,
+        elapsed: Duration,
+        millis_to_end: u128,
+    ) -> Result {
+        let Some(expected_duration) = self.expected_duration else {
+            return Ok(());
+        };
+        let time_left = expected_duration.saturating_sub(elapsed);
+        f.write_fmt(format_args!(
+            "; {:.*}% of time elapsed, {} left, ",
+            self.percent_precision,
+            (100.0 * elapsed.as_secs_f64() / expected_duration.as_secs_f64()).min(100.0),
+            self.fmt_duration(time_left)
+        ))?;
+        // Compare the estimates at the granularity of seconds
+        let time_to_end = millis_to_end / 1000;
+        let time_left = time_left.as_secs() as u128;
+        match time_left.cmp(&time_to_end) {
+            std::cmp::Ordering::Greater => f.write_fmt(format_args!(
+                "ahead of schedule by {}",
+                self.fmt_duration(Duration::from_secs((time_left - time_to_end) as u64))
+            )),
+            std::cmp::Ordering::Less => f.write_fmt(format_args!(
+                "behind schedule by {}",
+                self.fmt_duration(Duration::from_secs(
+                    (time_to_end - time_left).try_into().unwrap_or(u64::MAX)
+                ))
+            )),
+            std::cmp::Ordering::Equal => f.write_str("on schedule"),
         }
     }
 
+    /// Format the number of items produced and the backlog, if tracked,
+    /// given the elapsed time.
+    fn fmt_produced(&self, f: &mut Formatter<'_>, elapsed: Duration) -> Result {
+        let Some(produced) = self.produced else {
+            return Ok(());
+        };
+        f.write_fmt(format_args!("; produced {}, ", self.fmt_count(produced)))?;
+        let speed = produced as f64 / elapsed.as_secs_f64();
+        if speed.is_finite() {
+            f.write_fmt(format_args!("{:.2} {}/s", speed, self.item_plural))?;
+        } else {
+            f.write_str("n/a")?;
+        }
+        if self.stop_time.is_none() {
+            let backlog = self.backlog();
+            f.write_fmt(format_args!(
+                ", backlog {} ({}{} {})",
+                self.fmt_count(backlog),
+                if backlog < self.last_backlog {
+                    "-"
+                } else {
+                    "+"
+                },
+                self.fmt_count(backlog.abs_diff(self.last_backlog)),
+                self.messages.this_interval
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Format the success ratio, if displayed, and the hit rate, if there
+    /// have been hits or misses.
+    fn fmt_success_ratio(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(label) = &self.success_label {
+            if self.count == 0 {
+                f.write_fmt(format_args!("; n/a {}", label))?;
+            } else {
+                f.write_fmt(format_args!(
+                    "; {:.2}% {}",
+                    100.0 * self.successes as f64 / self.count as f64,
+                    label
+                ))?;
+            }
+        }
+        let lookups = self.hits.saturating_add(self.misses);
+        if lookups != 0 {
+            f.write_fmt(format_args!(
+                "; {:.2}% hit rate",
+                100.0 * self.hits as f64 / lookups as f64
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Format speed and timing given the number of seconds per item.
+    ///
+    /// If the number of seconds per item is not positive and finite (e.g.,
+    /// because the count or the elapsed time are zero), `n/a` is printed.
     fn fmt_timing_speed(&self, f: &mut Formatter<'_>, seconds_per_item: f64) -> Result {
+        if !seconds_per_item.is_finite() || seconds_per_item <= 0.0 {
+            return f.write_str("n/a");
+        }
+
         let items_per_second = 1.0 / seconds_per_item;
 
         let time_unit_timing = self
             .time_unit
+            .or(self.frozen_time_units.map(|(timing, _)| timing))
             .unwrap_or_else(|| TimeUnit::nice_time_unit(seconds_per_item));
 
         let time_unit_speed = self
             .time_unit
+            .or(self.frozen_time_units.map(|(_, speed)| speed))
             .unwrap_or_else(|| TimeUnit::nice_speed_unit(seconds_per_item));
 
         f.write_fmt(format_args!(
             "{:.2} {}/{}, {:.2} {}/{}",
             items_per_second * time_unit_speed.as_seconds(),
-            pluralize(&self.item_name, 2, false),
+            self.item_plural,
             time_unit_speed.label(),
             seconds_per_item / time_unit_timing.as_seconds(),
             time_unit_timing.label(),
-            self.item_name
+            self.item_singular
         ))?;
 
         Ok(())
@@ -358,108 +2842,353 @@ impl ProgressLog for ProgressLogger {
         self
     }
 
-    fn item_name(&mut self, item_name: impl AsRef<str>) -> &mut Self {
-        self.item_name = item_name.as_ref().into();
-        self
+    fn item_name(&mut self, item_name: impl AsRef<str>) -> &mut Self {
+        let item_name = item_name.as_ref();
+        self.item_singular = pluralize(item_name, 1, false);
+        self.item_plural = pluralize(item_name, 2, false);
+        self.item_name = item_name.into();
+        self
+    }
+
+    fn log_interval(&mut self, log_interval: Duration) -> &mut Self {
+        self.log_interval = log_interval;
+        self
+    }
+
+    fn expected_updates(&mut self, expected_updates: Option<usize>) -> &mut Self {
+        self.expected_updates = expected_updates;
+        self.update_next_log_count();
+        self
+    }
+
+    fn time_unit(&mut self, time_unit: Option<TimeUnit>) -> &mut Self {
+        self.time_unit = time_unit;
+        self
+    }
+
+    fn local_speed(&mut self, local_speed: bool) -> &mut Self {
+        self.local_speed = local_speed;
+        self
+    }
+
+    fn start(&mut self, msg: impl AsRef<str>) {
+        self.reset();
+        let now = self.now();
+        self.start_time = Some(now);
+        self.start_system_time = Some(self.system_now());
+        self.start_cpu_time = cpu_time();
+        self.last_log_time = now;
+        self.next_log_time = match self.first_log_delay {
+            Some(first_log_delay) => now.checked_add(first_log_delay).unwrap_or(now),
+            None => self.next_log_time_from(now),
+        };
+        let mut msg = self.expand_placeholders(msg.as_ref());
+        if !msg.is_empty() {
+            self.append_run_info(&mut msg);
+            self.emit(Level::Info, RecordKind::Start, format_args!("{}", msg));
+        }
+        self.begin_activity(&msg);
+        self.sample(RecordKind::Start, now);
+        self.update_state(now);
+    }
+
+    fn reset(&mut self) {
+        self.accumulate_run();
+        self.reset_secondary();
+        let now = self.now();
+        self.start_time = None;
+        self.round = 0;
+        self.round_start_count = 0;
+        self.round_start_time = None;
+        self.metric = None;
+        self.prev_metric = None;
+        self.stop_time = None;
+        self.start_system_time = None;
+        self.stop_system_time = None;
+        self.count = 0;
+        self.last_count = 0;
+        self.current_item.clear();
+        self.phase.clear();
+        self.retries = 0;
+        self.last_retries = 0;
+        self.backoff = None;
+        self.successes = 0;
+        self.hits = 0;
+        self.misses = 0;
+        self.in_flight = None;
+        self.produced = None;
+        self.last_backlog = 0;
+        self.reset_queue_watermarks();
+        self.reset_timers();
+        self.last_line = None;
+        self.repeated = 0;
+        self.logs = 0;
+        self.update_next_log_count();
+        self.frozen_time_units = None;
+        self.smoothed_local_speed = None;
+        self.peak_speed = 0.0;
+        self.interval_speeds.clear();
+        self.reconciled = false;
+        self.activity_id = None;
+        self.selector = UpdateSelector::default();
+        self.jitter_factor = self
+            .log_interval_jitter
+            .map_or(1.0, |jitter| 1.0 + jitter * (2.0 * random_unit() - 1.0));
+        self.completed_fraction = None;
+        if let Some((batches, _)) = &mut self.batches {
+            *batches = 0;
+        }
+        self.peak_memory = 0;
+        self.update_last_memory();
+        self.start_cpu_time = None;
+        if let Some(counter) = &self.counter {
+            self.counter_base = counter.load(Ordering::Relaxed);
+        }
+        self.relaxed_base = self.relaxed_sum();
+        self.last_log_time = now;
+        self.next_log_time = now;
+        self.next_state_time = now;
+    }
+
+    fn refresh(&mut self) {
+        if self.stop_time.is_none() {
+            self.sync_count();
+        }
+        if let Some((status, value)) = &mut self.status {
+            *value = status.get_mut()();
+        }
+        if self.deterministic {
+            return;
+        }
+        if let Some(system) = &mut self.system {
+            system.refresh_process_specifics(self.pid, ProcessRefreshKind::new());
+            system.refresh_memory();
+            if let Some(process) = system.process(self.pid) {
+                self.peak_memory = self.peak_memory.max(process.memory());
+            }
+        }
+        self.sample_measures();
+    }
+
+    fn update(&mut self) {
+        self.count = self.count.saturating_add(self.sampling);
+        self.log_if();
+    }
+
+    fn update_with_count(&mut self, count: usize) {
+        self.count = self.count.saturating_add(count);
+        self.log_if();
+    }
+
+    /// Increase the count and, once every [`LIGHT_UPDATE_MASK`](#fields.LIGHT_UPDATE_MASK) + 1 calls, check whether it is time to log.
+    ///
+    /// The common path is a (branchless) saturating increment and a single,
+    /// highly predictable branch; the check is out of line.
+    #[inline(always)]
+    fn light_update(&mut self) {
+        self.light_update_with::<{ Self::LIGHT_UPDATE_MASK }>();
+    }
+
+    #[inline(always)]
+    fn light_update_with<const MASK: usize>(&mut self) {
+        self.count = self.count.saturating_add(1);
+        if (self.count & MASK) == 0 {
+            self.light_log_if();
+        }
+    }
+
+    #[inline(always)]
+    fn adaptive_update(&mut self) {
+        self.adaptive_update_impl();
+    }
+
+    fn update_and_display(&mut self) {
+        self.count = self.count.saturating_add(1);
+        if !self.is_running() {
+            return;
+        }
+        let now = self.now();
+        self.log(now);
+    }
+
+    fn correct(&mut self, delta: isize) {
+        self.set_count(self.count.saturating_add_signed(delta));
+    }
+
+    fn set_count(&mut self, count: usize) {
+        self.count = count;
+        // Keep the count of the last interval consistent
+        self.last_count = self.last_count.min(count);
+    }
+
+    fn set_current_item(&mut self, item: impl AsRef<str>) {
+        self.current_item.clear();
+        self.current_item.push_str(item.as_ref());
     }
 
-    fn log_interval(&mut self, log_interval: Duration) -> &mut Self {
-        self.log_interval = log_interval;
-        self
+    fn set_phase(&mut self, phase: impl AsRef<str>) {
+        self.phase.clear();
+        self.phase.push_str(phase.as_ref());
     }
 
-    fn expected_updates(&mut self, expected_updates: Option<usize>) -> &mut Self {
-        self.expected_updates = expected_updates;
-        self
+    fn update_retry(&mut self) {
+        self.retries = self.retries.saturating_add(1);
     }
 
-    fn time_unit(&mut self, time_unit: Option<TimeUnit>) -> &mut Self {
-        self.time_unit = time_unit;
-        self
+    fn set_backoff(&mut self, backoff: Option<Duration>) {
+        self.backoff = backoff;
     }
 
-    fn local_speed(&mut self, local_speed: bool) -> &mut Self {
-        self.local_speed = local_speed;
-        self
+    fn update_success(&mut self) {
+        self.update_success_with_count(1);
     }
 
-    fn start(&mut self, msg: impl AsRef<str>) {
-        let now = Instant::now();
-        self.start_time = Some(now);
-        self.stop_time = None;
-        self.count = 0;
-        self.last_count = 0;
-        self.last_log_time = now;
-        self.next_log_time = now + self.log_interval;
-        if !msg.as_ref().is_empty() {
-            info!("{}", msg.as_ref());
-        }
+    fn update_success_with_count(&mut self, count: usize) {
+        self.successes = self.successes.saturating_add(count);
     }
 
-    fn refresh(&mut self) {
-        if let Some(system) = &mut self.system {
-            system.refresh_process_specifics(self.pid, ProcessRefreshKind::new());
-        }
+    fn hit(&mut self) {
+        self.hits = self.hits.saturating_add(1);
     }
 
-    fn update(&mut self) {
-        self.count += 1;
-        self.log_if();
+    fn miss(&mut self) {
+        self.misses = self.misses.saturating_add(1);
     }
 
-    fn update_with_count(&mut self, count: usize) {
-        self.count += count;
-        self.log_if();
+    fn begin_item(&mut self) {
+        self.in_flight = Some(self.in_flight.unwrap_or(0).saturating_add(1));
     }
 
-    /// Increase the count and, once every [`LIGHT_UPDATE_MASK`](#fields.LIGHT_UPDATE_MASK) + 1 calls, check whether it is time to log.
-    #[inline(always)]
-    fn light_update(&mut self) {
-        self.count += 1;
-        if (self.count & Self::LIGHT_UPDATE_MASK) == 0 {
-            self.log_if();
+    fn end_item(&mut self) {
+        self.in_flight = Some(self.in_flight.unwrap_or(0).saturating_sub(1));
+        self.update();
+    }
+
+    fn update_produced(&mut self) {
+        self.update_produced_with_count(1);
+    }
+
+    fn update_produced_with_count(&mut self, count: usize) {
+        self.produced = Some(self.produced.unwrap_or(0).saturating_add(count));
+    }
+
+    fn new_round(&mut self) {
+        self.sync_count();
+        self.round = self.round.saturating_add(1);
+        self.round_start_count = self.count;
+        self.round_start_time = Some(self.now());
+    }
+
+    fn record_metric(&mut self, value: f64) {
+        if let Some((round, metric)) = self.metric {
+            if round != self.round {
+                self.prev_metric = Some(metric);
+            }
         }
+        self.metric = Some((self.round, value));
     }
 
-    fn update_and_display(&mut self) {
-        self.count += 1;
-        self.log(Instant::now());
+    fn log_now(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let now = self.now();
+        self.log(now);
+    }
+
+    fn should_log(&self) -> bool {
+        let now = self.now();
+        self.is_running() && self.next_log_time <= now && self.enough_items_to_log(now)
     }
 
     fn stop(&mut self) {
-        self.stop_time = Some(Instant::now());
+        if !self.check_started("stop") || self.stop_time.is_some() {
+            return;
+        }
+        self.sync_count();
+        self.stop_time = Some(self.now());
+        self.stop_system_time = Some(self.system_now());
         self.expected_updates = None;
     }
 
     fn done(&mut self) {
+        if !self.check_started("done") {
+            return;
+        }
         self.stop();
-        info!("Completed.");
-        // just to avoid wrong reuses
-        self.expected_updates = None;
-        self.refresh();
-        info!("{}", self);
+        self.completed();
     }
 
     fn done_with_count(&mut self, count: usize) {
+        if !self.check_started("done_with_count") {
+            return;
+        }
+        self.stop();
         self.count = count;
-        self.done();
+        self.reconciled = true;
+        self.completed();
     }
 
     fn elapsed(&self) -> Option<Duration> {
-        self.start_time?.elapsed().into()
+        Some(
+            self.stop_time
+                .unwrap_or_else(|| self.now())
+                .saturating_duration_since(self.start_time?),
+        )
     }
 
     fn info(&self, args: Arguments<'_>) {
-        info!("{}", std::fmt::format(args));
+        self.emit(Level::Info, RecordKind::Info, args);
     }
 
     #[allow(clippy::manual_map)]
     fn clone(&self) -> Self {
         Self {
             item_name: self.item_name.clone(),
+            item_singular: self.item_singular.clone(),
+            item_plural: self.item_plural.clone(),
             log_interval: self.log_interval,
             time_unit: self.time_unit,
             local_speed: self.local_speed,
+            clock: self.clock.clone(),
+            freeze_time_unit: self.freeze_time_unit,
+            local_speed_smoothing: self.local_speed_smoothing,
+            done_level: self.done_level,
+            completed_message: self.completed_message,
+            done_summary: self.done_summary,
+            display_timestamps: self.display_timestamps,
+            display_delta: self.display_delta,
+            success_label: self.success_label.clone(),
+            exponential_logging: self.exponential_logging,
+            min_items_per_log: self.min_items_per_log,
+            dedup: self.dedup,
+            first_log_delay: self.first_log_delay,
+            min_logs: self.min_logs,
+            max_logs: self.max_logs,
+            log_interval_jitter: self.log_interval_jitter,
+            percent_precision: self.percent_precision,
+            raw_millis: self.raw_millis,
+            elapsed_prefix: self.elapsed_prefix,
+            messages: self.messages,
+            accumulate: self.accumulate,
+            rounds: self.rounds,
+            expected_duration: self.expected_duration,
+            run_id: self.run_id.clone(),
+            metadata: self.metadata.clone(),
+            metric_name: self.metric_name.clone(),
+            convergence_threshold: self.convergence_threshold,
+            target_rate: self.target_rate,
+            peak_rate: self.peak_rate,
+            anomaly_factor: self.anomaly_factor,
+            sampling: self.sampling,
+            low_memory: self.low_memory,
+            detailed_report: self.detailed_report,
+            stable_format: self.stable_format,
+            markers: self.markers,
+            state_interval: self.state_interval,
+            batches: self.batches.map(|(_, batch_size)| (0, batch_size)),
+            sinks: self.sinks.clone(),
+            deterministic: self.deterministic,
             system: match self.system {
                 Some(_) => Some(System::new_with_specifics(RefreshKind::new().with_memory())),
                 None => None,
@@ -469,88 +3198,332 @@ impl ProgressLog for ProgressLogger {
     }
 }
 
+/// Dump the configuration and the internal state of the logger.
+///
+/// Instants are displayed as offsets from the start time, and are [`None`]
+/// if the logger has not been started; closures, hooks and sinks are
+/// displayed only through their presence or their number.
+impl Debug for ProgressLogger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let since_start = |instant: Instant| {
+            self.start_time
+                .map(|start_time| instant.saturating_duration_since(start_time))
+        };
+        f.debug_struct("ProgressLogger")
+            .field("item_name", &self.item_name)
+            .field("log_interval", &self.log_interval)
+            .field("run_id", &self.run_id)
+            .field("metadata", &self.metadata)
+            .field("expected_updates", &self.expected_updates)
+            .field("expected_duration", &self.expected_duration)
+            .field("time_unit", &self.time_unit)
+            .field("local_speed", &self.local_speed)
+            .field("display_memory", &self.system.is_some())
+            .field("deterministic", &self.deterministic)
+            .field("exponential_logging", &self.exponential_logging)
+            .field("min_items_per_log", &self.min_items_per_log)
+            .field("first_log_delay", &self.first_log_delay)
+            .field("min_logs", &self.min_logs)
+            .field("max_logs", &self.max_logs)
+            .field("log_interval_jitter", &self.log_interval_jitter)
+            .field("dedup", &self.dedup)
+            .field("accumulate", &self.accumulate)
+            .field("grand_total", &self.grand_total)
+            .field("target_rate", &self.target_rate)
+            .field("peak_rate", &self.peak_rate)
+            .field("anomaly_factor", &self.anomaly_factor)
+            .field("stable_format", &self.stable_format)
+            .field("markers", &self.markers)
+            .field("sampling", &self.sampling)
+            .field("batches", &self.batches)
+            .field("done_level", &self.done_level)
+            .field("messages", &self.messages)
+            .field("sinks", &self.sinks.len())
+            .field("measures", &self.measures.len())
+            .field("has_clock", &self.clock.is_some())
+            .field("has_watch", &self.watch.is_some())
+            .field("has_counter", &self.counter.is_some())
+            .field("relaxed_counters", &self.relaxed.len())
+            .field("has_status", &self.status.is_some())
+            .field("start_time", &self.start_time.and_then(since_start))
+            .field("stop_time", &self.stop_time.and_then(since_start))
+            .field("last_log_time", &since_start(self.last_log_time))
+            .field("next_log_time", &since_start(self.next_log_time))
+            .field("next_state_time", &since_start(self.next_state_time))
+            .field("start_system_time", &self.start_system_time)
+            .field("count", &self.count)
+            .field("last_count", &self.last_count)
+            .field("counter_base", &self.counter_base)
+            .field("relaxed_base", &self.relaxed_base)
+            .field("next_log_count", &self.next_log_count)
+            .field("logs", &self.logs)
+            .field("repeated", &self.repeated)
+            .field("round", &self.round)
+            .field("round_start_count", &self.round_start_count)
+            .field("phase", &self.phase)
+            .field("current_item", &self.current_item)
+            .field("retries", &self.retries)
+            .field("successes", &self.successes)
+            .field("hits", &self.hits)
+            .field("misses", &self.misses)
+            .field("in_flight", &self.in_flight)
+            .field("produced", &self.produced)
+            .field("queue", &self.queue)
+            .field("completed_fraction", &self.completed_fraction)
+            .field("frozen_time_units", &self.frozen_time_units)
+            .field("smoothed_local_speed", &self.smoothed_local_speed)
+            .field("peak_speed", &self.peak_speed)
+            .field("peak_memory", &self.peak_memory)
+            .field("last_memory", &self.last_memory)
+            .field("low_memory", &self.low_memory)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Display for ProgressLogger {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         if let Some(start_time) = self.start_time {
-            let count_fmtd = if self.time_unit.is_none() {
-                self.count.to_formatted_string(&Locale::en)
-            } else {
-                self.count.to_string()
-            };
+            let count_fmtd = self.fmt_count(self.count);
 
             if let Some(stop_time) = self.stop_time {
-                let elapsed = stop_time - start_time;
+                let elapsed = stop_time.saturating_duration_since(start_time);
                 let seconds_per_item = elapsed.as_secs_f64() / self.count as f64;
 
                 f.write_fmt(format_args!(
-                    "Elapsed: {}",
-                    TimeUnit::pretty_print(elapsed.as_millis())
+                    "{}: {}",
+                    self.messages.elapsed,
+                    self.fmt_duration(elapsed)
                 ))?;
 
                 if self.count != 0 {
                     f.write_fmt(format_args!(
-                        " [{} {}, ",
+                        " [{}{} {}, ",
+                        self.approximate_marker(),
                         count_fmtd,
-                        pluralize(&self.item_name, self.count as isize, false)
+                        self.item_name_for(self.count)
                     ))?;
+                    if let Some((batches, _)) = self.batches {
+                        f.write_fmt(format_args!(
+                            "{} {}, ",
+                            self.fmt_count(batches),
+                            pluralize("batch", batches as isize, false)
+                        ))?;
+                    }
                     self.fmt_timing_speed(f, seconds_per_item)?;
-                    f.write_fmt(format_args!("]"))?
+                    f.write_fmt(format_args!("]"))?;
+                    self.fmt_efficiency(f, 1.0 / seconds_per_item)?;
+                }
+
+                self.fmt_secondary(f, elapsed)?;
+
+                self.fmt_produced(f, elapsed)?;
+                self.fmt_timers(f, elapsed)?;
+
+                self.fmt_success_ratio(f)?;
+
+                if self.retries != 0 {
+                    f.write_fmt(format_args!(
+                        "; {} {}",
+                        self.fmt_count(self.retries),
+                        pluralize("retry", self.retries as isize, false)
+                    ))?;
+                }
+
+                self.fmt_rounds(f)?;
+                self.fmt_metric(f)?;
+
+                if let (true, Some(start), Some(stop)) = (
+                    self.display_timestamps,
+                    self.start_system_time,
+                    self.stop_system_time,
+                ) {
+                    f.write_fmt(format_args!(
+                        "; from {} to {}",
+                        rfc3339(start),
+                        rfc3339(stop)
+                    ))?;
                 }
             } else {
-                let now = Instant::now();
+                let now = self.now();
 
-                let elapsed = now - start_time;
+                let elapsed = now.saturating_duration_since(start_time);
 
+                if !self.phase.is_empty() {
+                    f.write_fmt(format_args!("[{}] ", self.phase))?;
+                }
                 f.write_fmt(format_args!(
-                    "{} {}, {}, ",
+                    "{}{} {}, ",
+                    self.approximate_marker(),
                     count_fmtd,
-                    pluralize(&self.item_name, self.count as isize, false),
-                    TimeUnit::pretty_print(elapsed.as_millis()),
+                    self.item_name_for(self.count),
                 ))?;
+                if let Some((batches, batch_size)) = self.batches {
+                    f.write_fmt(format_args!("{}", self.fmt_count(batches)))?;
+                    if let (Some(expected_updates), true) = (self.expected_updates, batch_size != 0)
+                    {
+                        f.write_fmt(format_args!(
+                            "/{}",
+                            self.fmt_count(expected_updates.div_ceil(batch_size))
+                        ))?;
+                    }
+                    f.write_str(" batches, ")?;
+                }
+                f.write_fmt(format_args!("{}, ", self.fmt_duration(elapsed)))?;
 
                 let seconds_per_item = elapsed.as_secs_f64() / self.count as f64;
                 self.fmt_timing_speed(f, seconds_per_item)?;
 
                 if let Some(expected_updates) = self.expected_updates {
-                    let millis_to_end: u128 = (expected_updates.saturating_sub(self.count) as u128
-                        * elapsed.as_millis())
-                        / (self.count as u128 + 1);
+                    let millis_to_end = self.millis_to_end(expected_updates, elapsed);
                     f.write_fmt(format_args!(
-                        "; {:.2}% done, {} to end",
+                        "; {:.*}% {}, {} {}, {} {}",
+                        self.percent_precision,
                         100.0 * self.count as f64 / expected_updates as f64,
-                        TimeUnit::pretty_print(millis_to_end)
+                        self.messages.done,
+                        self.fmt_count(expected_updates.saturating_sub(self.count)),
+                        self.messages.to_go,
+                        self.fmt_duration(millis_to_duration(millis_to_end)),
+                        self.messages.to_end
+                    ))?;
+                    self.fmt_schedule(f, elapsed, millis_to_end)?;
+                } else if let Some(fraction) = self.completed_fraction {
+                    let millis_to_end =
+                        elapsed.as_secs_f64() * 1000.0 * (1.0 - fraction) / fraction;
+                    f.write_fmt(format_args!(
+                        "; {:.*}% {}, ",
+                        self.percent_precision,
+                        100.0 * fraction,
+                        self.messages.done
+                    ))?;
+                    if millis_to_end.is_finite() {
+                        f.write_fmt(format_args!(
+                            "{} {}",
+                            self.fmt_duration(Duration::from_millis(millis_to_end.max(0.0) as u64)),
+                            self.messages.to_end
+                        ))?;
+                    } else {
+                        f.write_fmt(format_args!("n/a {}", self.messages.to_end))?;
+                    }
+                } else if let Some(expected_duration) = self.expected_duration {
+                    f.write_fmt(format_args!(
+                        "; {:.*}% {}, {} {}",
+                        self.percent_precision,
+                        (100.0 * elapsed.as_secs_f64() / expected_duration.as_secs_f64())
+                            .min(100.0),
+                        self.messages.done,
+                        self.fmt_duration(expected_duration.saturating_sub(elapsed)),
+                        self.messages.to_end
                     ))?;
                 }
 
                 if self.local_speed && self.stop_time.is_none() {
                     f.write_fmt(format_args!(" ["))?;
 
-                    let elapsed = now - self.last_log_time;
-                    let seconds_per_item =
-                        elapsed.as_secs_f64() / (self.count - self.last_count) as f64;
-                    self.fmt_timing_speed(f, seconds_per_item)?;
+                    self.fmt_timing_speed(f, 1.0 / self.local_speed_at(now))?;
 
                     f.write_fmt(format_args!("]"))?;
                 }
+
+                self.fmt_anomaly(f, now)?;
+
+                let local_speed = self.local_speed_at(now);
+                self.fmt_efficiency(
+                    f,
+                    if local_speed.is_finite() {
+                        local_speed
+                    } else {
+                        1.0 / seconds_per_item
+                    },
+                )?;
+
+                self.fmt_secondary(f, elapsed)?;
+
+                if self.display_delta {
+                    f.write_fmt(format_args!(
+                        "; +{} {}",
+                        self.fmt_count(self.count.saturating_sub(self.last_count)),
+                        self.messages.this_interval
+                    ))?;
+                }
+
+                self.fmt_round(f, now)?;
+                self.fmt_metric(f)?;
+
+                self.fmt_success_ratio(f)?;
+
+                if self.retries != 0 {
+                    f.write_fmt(format_args!(
+                        "; {} {}, +{} {}",
+                        self.fmt_count(self.retries),
+                        pluralize("retry", self.retries as isize, false),
+                        self.fmt_count(self.retries.saturating_sub(self.last_retries)),
+                        self.messages.this_interval
+                    ))?;
+                    if let Some(backoff) = self.backoff {
+                        f.write_fmt(format_args!(", backoff {}", self.fmt_duration(backoff)))?;
+                    }
+                }
+
+                self.fmt_produced(f, elapsed)?;
+                self.fmt_timers(f, elapsed)?;
+
+                if let Some(in_flight) = self.in_flight {
+                    f.write_fmt(format_args!(
+                        "; {}: {}",
+                        self.messages.in_flight,
+                        self.fmt_count(in_flight)
+                    ))?;
+                }
+
+                self.fmt_queue_depth(f)?;
+
+                if !self.current_item.is_empty() {
+                    f.write_fmt(format_args!(
+                        "; {} {}",
+                        self.messages.processing, self.current_item
+                    ))?;
+                }
             }
 
             // It would be ideal to refresh self.system here, but this operation
             // would require an &mut self reference.
-            if let Some(system) = &self.system {
+            if let Some(system) = self.system.as_ref().filter(|_| !self.deterministic) {
+                f.write_str("; res/vir/avail/free/total mem ")?;
+                match system.process(self.pid) {
+                    Some(process) => f.write_fmt(format_args!(
+                        "{}B/{}B",
+                        Humanized(process.memory() as _),
+                        Humanized(process.virtual_memory() as _)
+                    ))?,
+                    None => f.write_str("N/A/N/A")?,
+                }
                 f.write_fmt(format_args!(
-                    "; res/vir/avail/free/total mem {}/{}/{}B/{}B/{}B",
-                    system
-                        .process(self.pid)
-                        .map(|process| humanize(process.memory() as _) + "B")
-                        .unwrap_or("N/A".to_string()),
-                    system
-                        .process(self.pid)
-                        .map(|process| humanize(process.virtual_memory() as _) + "B")
-                        .unwrap_or("N/A".to_string()),
-                    humanize(system.available_memory() as _),
-                    humanize(system.free_memory() as _),
-                    humanize(system.total_memory() as _)
+                    "/{}B/{}B/{}B",
+                    Humanized(system.available_memory() as _),
+                    Humanized(system.free_memory() as _),
+                    Humanized(system.total_memory() as _)
                 ))?;
+                if self.stop_time.is_none() {
+                    self.fmt_memory_growth(f, self.now())?;
+                }
+            }
+
+            if !self.deterministic {
+                for (measure, value) in &self.measures {
+                    f.write_fmt(format_args!(
+                        "; {} {}",
+                        measure.name(),
+                        value.as_deref().unwrap_or("N/A")
+                    ))?;
+                }
+            }
+
+            if let Some((_, status)) = self
+                .status
+                .as_ref()
+                .filter(|(_, status)| !status.is_empty())
+            {
+                f.write_fmt(format_args!("; {}", status))?;
             }
 
             Ok(())
@@ -560,6 +3533,1067 @@ impl Display for ProgressLogger {
     }
 }
 
+/// A refreshed [`ProgressLogger`] ready to be displayed.
+///
+/// Returned by [`ProgressLogger::display`].
+pub struct RefreshedDisplay<'a>(&'a ProgressLogger);
+
+impl Display for RefreshedDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
 pub mod prelude {
-    pub use super::{ProgressLog, ProgressLogger};
+    pub use super::{
+        Clock, CoarseClock, CompositeProgress, InputsProgress, ManualClock, ProgressLog,
+        ProgressLogger, ProgressLoggerHandle, RelaxedCounter, SystemClock, Ticker, TimeUnit,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_counter() {
+        let mut pl = ProgressLogger::default();
+        let counter = pl.counter();
+        counter.fetch_add(5, Ordering::Relaxed);
+        pl.start("");
+        counter.fetch_add(10, Ordering::Relaxed);
+        pl.update();
+        pl.refresh();
+        assert_eq!(pl.count, 11);
+        counter.fetch_add(1, Ordering::Relaxed);
+        pl.stop();
+        assert_eq!(pl.count, 12);
+    }
+
+    #[test]
+    fn test_saturating() {
+        let mut pl = ProgressLogger::default();
+        pl.log_interval(Duration::MAX).local_speed(true);
+        pl.start("");
+        pl.update_with_count(usize::MAX);
+        pl.update();
+        assert_eq!(pl.count, usize::MAX);
+        pl.last_count = usize::MAX;
+        pl.count = 1;
+        pl.expected_updates(Some(usize::MAX));
+        pl.start_time = Some(Instant::now() + Duration::from_secs(1000));
+        let _ = pl.to_string();
+        pl.start_time = Instant::now().checked_sub(Duration::from_secs(1 << 32));
+        pl.count = 0;
+        let _ = pl.to_string();
+        pl.stop_time = pl
+            .start_time
+            .and_then(|t| t.checked_sub(Duration::from_secs(1)));
+        let _ = pl.to_string();
+    }
+
+    #[test]
+    fn test_zero() {
+        let mut pl = ProgressLogger::default();
+        pl.local_speed(true);
+        pl.start("");
+        assert!(pl.to_string().contains("n/a"));
+        pl.stop_time = pl.start_time;
+        pl.count = 10;
+        assert!(pl.to_string().contains("n/a"));
+        pl.done_with_count(0);
+        assert!(!pl.to_string().contains("inf"));
+        assert!(!pl.to_string().contains("NaN"));
+    }
+
+    #[test]
+    fn test_log_now() {
+        let logs = testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone()).clock(clock.clone());
+        assert!(!pl.should_log());
+        pl.start("");
+        assert!(!pl.should_log());
+        pl.log_now();
+        clock.advance(Duration::from_secs(9));
+        assert!(!pl.should_log());
+        clock.advance(Duration::from_secs(1));
+        assert!(pl.should_log());
+        pl.log_now();
+        assert!(!pl.should_log());
+        assert_eq!(logs.progress_lines(), 2);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut pl = ProgressLogger::default();
+        pl.item_name("pumpkin").expected_updates(Some(10));
+        pl.start("");
+        pl.update();
+        pl.reset();
+        assert_eq!(pl.count, 0);
+        assert!(pl.start_time.is_none());
+        assert!(pl.elapsed().is_none());
+        assert_eq!(pl.item_name, "pumpkin");
+        assert_eq!(pl.item_name_for(1), "pumpkin");
+        assert_eq!(pl.item_name_for(2), "pumpkins");
+        assert_eq!(pl.expected_updates, Some(10));
+        assert_eq!(pl.to_string(), "ProgressLogger not started");
+    }
+
+    #[test]
+    fn test_odd_states() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone());
+
+        // Updates before start are counted but never logged
+        clock.advance(Duration::from_secs(60));
+        pl.update();
+        pl.log_now();
+        assert_eq!(pl.count(), 1);
+        assert!(logs.lines().is_empty());
+
+        // Stopping before start only warns
+        pl.stop();
+        pl.done();
+        assert_eq!(
+            logs.lines(),
+            [
+                "ProgressLogger::stop() called before start()",
+                "ProgressLogger::done() called before start()",
+            ]
+        );
+        assert!(pl.stop_time.is_none());
+
+        // Starting twice restarts
+        pl.start("");
+        pl.update_with_count(10);
+        clock.advance(Duration::from_secs(5));
+        pl.start("");
+        assert_eq!(pl.count(), 0);
+        assert_eq!(pl.elapsed(), Some(Duration::ZERO));
+
+        // Stopping twice keeps the first stop time, and updates after stop
+        // never log
+        clock.advance(Duration::from_secs(2));
+        pl.stop();
+        clock.advance(Duration::from_secs(60));
+        pl.stop();
+        pl.update();
+        assert_eq!(pl.elapsed(), Some(Duration::from_secs(2)));
+        assert_eq!(logs.progress_lines(), 0);
+    }
+
+    #[test]
+    fn test_custom_time_unit() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .item_name("page")
+            .time_unit(Some(TimeUnit::Custom {
+                label: "shift",
+                seconds: 28800.0,
+            }));
+        pl.start("");
+        clock.advance(Duration::from_secs(14400));
+        pl.update_with_count(100);
+        pl.stop();
+        assert_eq!(
+            pl.to_string(),
+            "Elapsed: 0.50shift [100 pages, 200.00 pages/shift, 0.01 shift/page]"
+        );
+    }
+
+    #[test]
+    fn test_freeze_time_unit() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).freeze_time_unit(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(100);
+        assert!(pl.to_string().ends_with("10.00 items/s, 100.00 ms/item"));
+        clock.advance(Duration::from_secs(90));
+        assert!(pl.to_string().ends_with("1.00 items/s, 1000.00 ms/item"));
+        pl.reset();
+        assert!(pl.frozen_time_units.is_none());
+    }
+
+    #[test]
+    fn test_local_speed_smoothing() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .local_speed(true)
+            .local_speed_smoothing(0.5);
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(100);
+        assert_eq!(pl.smoothed_local_speed, Some(10.0));
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(300);
+        assert_eq!(pl.smoothed_local_speed, Some(20.0));
+        clock.advance(Duration::from_secs(10));
+        assert!(pl.to_string().ends_with("[10.00 items/s, 100.00 ms/item]"));
+    }
+
+    #[test]
+    fn test_done_output() {
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .completed_message(false)
+            .done_level(Level::Debug);
+        pl.start("");
+        pl.done_with_count(5);
+        let records = logs.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, RecordKind::Summary);
+        assert_eq!(records[0].level, Level::Debug);
+
+        logs.clear();
+        pl.done_summary(false).start("");
+        pl.done();
+        assert!(logs.records().is_empty());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).display_timestamps(true);
+        clock.advance(Duration::from_secs(86400));
+        pl.start("");
+        clock.advance(Duration::from_secs(61));
+        pl.stop();
+        assert_eq!(
+            pl.to_string(),
+            "Elapsed: 1m 1s; from 1970-01-02T00:00:00Z to 1970-01-02T00:01:01Z"
+        );
+    }
+
+    #[test]
+    fn test_delta() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).display_delta(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(1000);
+        pl.update_with_count(124_502);
+        assert!(pl.to_string().ends_with("; +124,502 this interval"));
+    }
+
+    #[test]
+    fn test_items_to_go() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).expected_updates(Some(4_000_000));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(589_779);
+        assert!(pl.to_string().contains("14.74% done, 3,410,221 to go, "));
+    }
+
+    #[test]
+    fn test_hooks() {
+        let logs = testing::CapturedLogs::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut pl = ProgressLogger::default();
+        let before = seen.clone();
+        let after = seen.clone();
+        pl.add_sink(logs.clone())
+            .before_log(move |pl| before.lock().unwrap().push(("before", pl.count())))
+            .after_log(move |pl| after.lock().unwrap().push(("after", pl.count())));
+        pl.start("");
+        pl.update_with_count(5);
+        pl.log_now();
+        pl.done();
+        assert_eq!(*seen.lock().unwrap(), vec![("before", 5), ("after", 5)]);
+        assert_eq!(logs.progress_lines(), 1);
+    }
+
+    #[test]
+    fn test_current_item() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.set_current_item("chunk-0421.bin");
+        pl.update();
+        assert_eq!(
+            pl.to_string(),
+            "1 item, 1s, 1.00 items/s, 1.00 s/item; processing chunk-0421.bin"
+        );
+        pl.done();
+        assert!(!pl.to_string().contains("processing"));
+        pl.reset();
+        pl.start("");
+        assert!(!pl.to_string().contains("processing"));
+    }
+
+    #[test]
+    fn test_phase() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        pl.set_phase("sorting");
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert_eq!(
+            pl.to_string(),
+            "[sorting] 1 item, 1s, 1.00 items/s, 1.00 s/item"
+        );
+        pl.set_phase("merging");
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert_eq!(
+            pl.to_string(),
+            "[merging] 2 items, 2s, 1.00 items/s, 1.00 s/item"
+        );
+        pl.set_phase("");
+        assert!(pl.to_string().starts_with("2 items"));
+    }
+
+    #[test]
+    fn test_retries() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert_eq!(pl.to_string(), "1 item, 1s, 1.00 items/s, 1.00 s/item");
+        pl.update_retry();
+        pl.update_retry();
+        pl.log_now();
+        pl.update_retry();
+        pl.set_backoff(Some(Duration::from_millis(500)));
+        assert!(pl
+            .to_string()
+            .ends_with("; 3 retries, +1 this interval, backoff 500ms"));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; 3 retries"));
+    }
+
+    #[test]
+    fn test_success_ratio() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .success_ratio(Some("matched"));
+        pl.start("");
+        assert!(pl.to_string().ends_with("; n/a matched"));
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(8);
+        pl.update_success_with_count(5);
+        pl.update_success();
+        assert_eq!(
+            pl.to_string(),
+            "8 items, 1s, 8.00 items/s, 125.00 ms/item; 75.00% matched"
+        );
+        pl.done();
+        assert!(pl.to_string().ends_with("]; 75.00% matched"));
+        pl.success_ratio(None);
+        assert!(!pl.to_string().contains("matched"));
+    }
+
+    #[test]
+    fn test_hit_rate() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert!(!pl.to_string().contains("hit rate"));
+        for i in 0..8 {
+            if i % 4 == 0 {
+                pl.miss();
+            } else {
+                pl.hit();
+            }
+        }
+        assert!(pl.to_string().ends_with("; 75.00% hit rate"));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; 75.00% hit rate"));
+    }
+
+    #[test]
+    fn test_in_flight() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        assert!(!pl.to_string().contains("in flight"));
+        for _ in 0..40 {
+            pl.begin_item();
+        }
+        for _ in 0..3 {
+            pl.end_item();
+        }
+        assert_eq!(
+            pl.to_string(),
+            "3 items, 1s, 3.00 items/s, 333.33 ms/item; in flight: 37"
+        );
+        pl.done();
+        assert!(!pl.to_string().contains("in flight"));
+    }
+
+    #[test]
+    fn test_expected_updates_fn() {
+        let clock = ManualClock::new();
+        let total = Arc::new(Mutex::new(None));
+        let expected = total.clone();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .expected_updates_fn(move || *expected.lock().unwrap());
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update_and_display();
+        assert!(!pl.to_string().contains("done"));
+        *total.lock().unwrap() = Some(4);
+        pl.update_and_display();
+        assert!(pl.to_string().contains("; 50.00% done, 2 to go"));
+        *total.lock().unwrap() = None;
+        pl.update_and_display();
+        assert!(pl.to_string().contains("; 75.00% done, 1 to go"));
+    }
+
+    #[test]
+    fn test_produced() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .log_interval(Duration::from_secs(60));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_produced_with_count(300);
+        pl.update_with_count(100);
+        assert_eq!(
+            pl.to_string(),
+            "100 items, 10s, 10.00 items/s, 100.00 ms/item; produced 300, 30.00 items/s, backlog 200 (+200 this interval)"
+        );
+        pl.log_now();
+        clock.advance(Duration::from_secs(10));
+        pl.update_produced_with_count(100);
+        pl.update_with_count(250);
+        assert!(pl
+            .to_string()
+            .ends_with("; produced 400, 20.00 items/s, backlog 50 (-150 this interval)"));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; produced 400, 20.00 items/s"));
+    }
+
+    #[test]
+    fn test_exponential_logging() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .exponential_logging(true)
+            .add_sink(logs.clone());
+        pl.start("");
+        for _ in 0..100 {
+            clock.advance(Duration::from_millis(10));
+            pl.update();
+        }
+        let counts: Vec<_> = logs
+            .lines()
+            .iter()
+            .map(|line| line.split(' ').next().unwrap().to_owned())
+            .collect();
+        assert_eq!(counts, ["1", "2", "4", "8", "16", "32", "64"]);
+
+        // Logs based on time continue
+        clock.advance(Duration::from_secs(10));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 8);
+    }
+
+    /// A downstream implementation of [`ProgressLog`] providing only the
+    /// required methods.
+    #[derive(Default)]
+    struct Counter(usize);
+
+    impl ProgressLog for Counter {
+        fn display_memory(&mut self, _display_memory: bool) -> &mut Self {
+            self
+        }
+        fn item_name(&mut self, _item_name: impl AsRef<str>) -> &mut Self {
+            self
+        }
+        fn log_interval(&mut self, _log_interval: Duration) -> &mut Self {
+            self
+        }
+        fn expected_updates(&mut self, _expected_updates: Option<usize>) -> &mut Self {
+            self
+        }
+        fn time_unit(&mut self, _time_unit: Option<TimeUnit>) -> &mut Self {
+            self
+        }
+        fn local_speed(&mut self, _local_speed: bool) -> &mut Self {
+            self
+        }
+        fn start(&mut self, _msg: impl AsRef<str>) {
+            self.0 = 0;
+        }
+        fn update(&mut self) {
+            self.0 += 1;
+        }
+        fn update_with_count(&mut self, count: usize) {
+            self.0 += count;
+        }
+        fn light_update(&mut self) {
+            self.0 += 1;
+        }
+        fn update_and_display(&mut self) {
+            self.0 += 1;
+        }
+        fn stop(&mut self) {}
+        fn done(&mut self) {}
+        fn done_with_count(&mut self, count: usize) {
+            self.0 = count;
+        }
+        fn elapsed(&self) -> Option<Duration> {
+            None
+        }
+        fn refresh(&mut self) {}
+        fn info(&self, _args: Arguments<'_>) {}
+        fn clone(&self) -> Self {
+            Counter(self.0)
+        }
+    }
+
+    #[test]
+    fn test_default_methods() {
+        let mut pl = Counter::default();
+        pl.start("");
+        pl.light_update_with::<1023>();
+        pl.adaptive_update();
+        pl.update_many(10, "last");
+        pl.begin_item();
+        pl.end_item();
+        pl.hit();
+        pl.update_success();
+        pl.new_round();
+        pl.record_metric(0.5);
+        pl.log_now();
+        assert!(!pl.should_log());
+        assert_eq!(pl.0, 13);
+    }
+
+    #[test]
+    fn test_min_items_per_log() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .min_items_per_log(100)
+            .add_sink(logs.clone());
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(50);
+        assert!(!pl.should_log());
+        assert_eq!(logs.progress_lines(), 0);
+        clock.advance(Duration::from_secs(60));
+        pl.update_with_count(49);
+        assert_eq!(logs.progress_lines(), 0);
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        // Stalled activities log anyway after ten log intervals
+        clock.advance(Duration::from_secs(90));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        clock.advance(Duration::from_secs(10));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 2);
+    }
+
+    #[test]
+    fn test_min_items_per_log_counter() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .min_items_per_log(10)
+            .add_sink(logs.clone());
+        let counter = pl.counter();
+        pl.start("");
+        for _ in 0..50 {
+            clock.advance(Duration::from_secs(1));
+            counter.fetch_add(100, Ordering::Relaxed);
+            pl.update_with_count(0);
+        }
+        assert_eq!(logs.progress_lines(), 5);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .dedup(true)
+            .add_sink(logs.clone());
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(10);
+        for _ in 0..12 {
+            clock.advance(Duration::from_secs(10));
+            pl.update_with_count(0);
+        }
+        clock.advance(Duration::from_secs(10));
+        pl.update();
+        pl.log_now();
+        pl.done();
+        assert_eq!(
+            logs.lines(),
+            [
+                "10 items, 10s, 1.00 items/s, 1.00 s/item",
+                "… (repeated 12×)",
+                "11 items, 2m 20s, 4.71 items/m, 12.73 s/item",
+                "… (repeated 1×)",
+                "Completed.",
+                "Elapsed: 2m 20s [11 items, 4.71 items/m, 12.73 s/item]",
+            ]
+        );
+
+        // Lines differing in anything but timing are not repeated
+        logs.clear();
+        pl.expected_updates(Some(20));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(10);
+        pl.expected_updates(Some(40));
+        clock.advance(Duration::from_secs(10));
+        pl.log_now();
+        assert_eq!(logs.progress_lines(), 2);
+        assert!(!logs.lines().iter().any(|line| line.contains("repeated")));
+    }
+
+    #[test]
+    fn test_forced_logs_when_stopped() {
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.deterministic(true).add_sink(logs.clone());
+        pl.update_and_display();
+        pl.log_now();
+        assert_eq!(logs.progress_lines(), 0);
+        pl.start("");
+        pl.update_and_display();
+        pl.log_now();
+        assert_eq!(logs.progress_lines(), 2);
+        pl.stop();
+        pl.update_and_display();
+        pl.log_now();
+        pl.done();
+        assert_eq!(logs.progress_lines(), 2);
+        assert_eq!(pl.count(), 2);
+    }
+
+    #[test]
+    fn test_first_log_delay() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .first_log_delay(Some(Duration::from_secs(1)))
+            .add_sink(logs.clone());
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        // Later logs happen at each log interval
+        clock.advance(Duration::from_secs(9));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 1);
+        clock.advance(Duration::from_secs(1));
+        pl.update();
+        assert_eq!(logs.progress_lines(), 2);
+    }
+
+    #[test]
+    fn test_min_logs() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .expected_updates(Some(1000))
+            .min_logs(Some(4))
+            .add_sink(logs.clone());
+        pl.start("");
+        for _ in 0..1000 {
+            pl.update();
+        }
+        pl.done();
+        assert_eq!(logs.progress_lines(), 5);
+    }
+
+    #[test]
+    fn test_update_many() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        pl.update_chunk(&[0; 10]);
+        pl.update_many(5, "block-3");
+        assert_eq!(pl.count(), 15);
+        assert!(pl.to_string().ends_with("; processing block-3"));
+        let mut none: Option<ProgressLogger> = None;
+        none.update_chunk(&[0; 10]);
+        none.update_many(5, "block-3");
+    }
+
+    #[test]
+    fn test_light_update_with() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(logs.clone());
+        pl.start("");
+        clock.advance(Duration::from_secs(60));
+        for _ in 0..3 {
+            pl.light_update_with::<3>();
+        }
+        assert_eq!(logs.progress_lines(), 0);
+        pl.light_update_with::<3>();
+        assert_eq!(logs.progress_lines(), 1);
+        let mut none: Option<ProgressLogger> = None;
+        none.light_update_with::<3>();
+    }
+
+    #[test]
+    fn test_log_interval_jitter() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .log_interval(Duration::from_secs(10))
+            .log_interval_jitter(Some(0.5));
+        let mut intervals = Vec::new();
+        for _ in 0..20 {
+            pl.start("");
+            let interval = pl.next_log_time - clock.now();
+            assert!(interval >= Duration::from_secs(5) && interval <= Duration::from_secs(15));
+            intervals.push(interval);
+        }
+        intervals.dedup();
+        assert!(intervals.len() > 1);
+        pl.log_interval_jitter(None).start("");
+        assert_eq!(pl.next_log_time - clock.now(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_max_logs() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .expected_updates(Some(10_000))
+            .max_logs(Some(5))
+            .add_sink(logs.clone());
+        pl.start("");
+        // A long activity: one item per second
+        for _ in 0..10_000 {
+            clock.advance(Duration::from_secs(1));
+            pl.update();
+        }
+        assert!(logs.progress_lines() <= 5);
+        assert!(logs.progress_lines() >= 2);
+
+        // Without an estimate, the interval grows with the elapsed time
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .max_logs(Some(100))
+            .add_sink(logs.clone());
+        pl.start("");
+        for _ in 0..100_000 {
+            clock.advance(Duration::from_secs(1));
+            pl.update();
+        }
+        assert!(logs.progress_lines() < 20);
+    }
+
+    #[test]
+    fn test_percent_precision() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .expected_updates(Some(3_000_000))
+            .percent_precision(0);
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(1_110_000);
+        assert!(pl.to_string().contains("; 37% done, "));
+        pl.percent_precision(4);
+        pl.set_count(3);
+        assert!(pl.to_string().contains("; 0.0001% done, "));
+    }
+
+    #[test]
+    fn test_expected_duration() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .expected_duration(Some(Duration::from_secs(100)));
+        pl.start("");
+        clock.advance(Duration::from_secs(25));
+        assert!(pl.to_string().ends_with("; 25.00% done, 1m 15s to end"));
+        clock.advance(Duration::from_secs(100));
+        assert!(pl.to_string().ends_with("; 100.00% done, 0ms to end"));
+        // The expected number of updates takes precedence
+        pl.expected_updates(Some(10));
+        pl.update_with_count(5);
+        assert!(pl.to_string().contains("; 50.00% done, 5 to go, "));
+    }
+
+    #[test]
+    fn test_schedule() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .expected_updates(Some(1_000))
+            .expected_duration(Some(Duration::from_secs(40 * 60)));
+        pl.start("");
+        clock.advance(Duration::from_secs(10 * 60));
+        pl.update_with_count(399);
+        assert!(pl.to_string().ends_with(
+            "; 39.90% done, 601 to go, 15m 1s to end; \
+             25.00% of time elapsed, 30m 0s left, ahead of schedule by 14m 59s"
+        ));
+        pl.set_count(99);
+        assert!(pl.to_string().ends_with(
+            "; 9.90% done, 901 to go, 1h 30m 6s to end; \
+             25.00% of time elapsed, 30m 0s left, behind schedule by 1h 6s"
+        ));
+    }
+
+    #[test]
+    fn test_peak_rate() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .log_interval(Duration::from_secs(60))
+            .peak_rate(Some(100.0));
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(800);
+        pl.log_now();
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(200);
+        assert!(pl.to_string().ends_with("; 20.00% of peak"));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; 50.00% of peak"));
+        pl.peak_rate(None);
+        assert!(!pl.to_string().contains("peak"));
+    }
+
+    #[test]
+    fn test_raw_millis() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .expected_updates(Some(4_000))
+            .raw_millis(true);
+        pl.start("");
+        clock.advance(Duration::from_millis(1500));
+        pl.update_with_count(1_000);
+        assert!(pl.to_string().contains(", 1500ms, "));
+        assert!(pl.to_string().ends_with(", 4495ms to end"));
+        clock.advance(Duration::from_secs(90));
+        pl.done();
+        assert!(pl.to_string().starts_with("Elapsed: 91500ms ["));
+    }
+
+    #[test]
+    fn test_debug() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).item_name("pumpkin");
+        assert!(format!("{:?}", pl).contains("start_time: None, stop_time: None"));
+        pl.start("");
+        clock.advance(Duration::from_secs(3));
+        pl.update_with_count(42);
+        let debug = format!("{:?}", pl);
+        assert!(debug.starts_with("ProgressLogger { item_name: \"pumpkin\", log_interval: 10s,"));
+        assert!(debug.contains("start_time: Some(0ns)"));
+        assert!(debug.contains("next_log_time: Some(10s)"));
+        assert!(debug.contains("count: 42,"));
+        assert!(debug.ends_with(", .. }"));
+    }
+
+    #[test]
+    fn test_start_placeholders() {
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .item_name("pumpkin")
+            .expected_updates(Some(1024));
+        pl.start("Smashing {expected} {item_name}...");
+        pl.expected_updates(Some(1));
+        pl.start("Smashing {expected} {item_name}...");
+        pl.expected_updates(None);
+        pl.start("Smashing {expected} {item_name} {count}...");
+        assert_eq!(
+            logs.lines(),
+            [
+                "Smashing 1,024 pumpkins...",
+                "Smashing 1 pumpkin...",
+                "Smashing ? pumpkins {count}...",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_elapsed_prefix() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .elapsed_prefix(true)
+            .add_sink(logs.clone());
+        pl.start("Starting...");
+        clock.advance(Duration::from_secs(42 * 60 + 17));
+        pl.update_with_count(10);
+        pl.log_now();
+        clock.advance(Duration::from_secs(3600));
+        pl.done();
+        pl.info(format_args!("Bye"));
+        let lines = logs.lines();
+        assert_eq!(lines[0], "[+00:00:00] Starting...");
+        assert!(lines[1].starts_with("[+00:42:17] 10 items, "));
+        assert!(lines.iter().any(|line| line == "[+01:42:17] Completed."));
+        assert_eq!(lines.last().unwrap(), "[+01:42:17] Bye");
+        assert!(!pl.to_string().starts_with('['));
+    }
+
+    #[test]
+    fn test_status() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut frontier = 1_000;
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone())
+            .status(move || {
+                frontier *= 2;
+                format!("frontier={}", frontier)
+            });
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update_and_display();
+        pl.done();
+        let lines = logs.lines();
+        assert!(lines[0].ends_with("; frontier=2000"));
+        assert!(lines[2].ends_with("; frontier=4000"));
+    }
+
+    #[test]
+    fn test_batches() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).item_name("row").batches(4_000, 100);
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        for _ in 0..10 {
+            pl.update_batch(100);
+        }
+        assert!(pl
+            .to_string()
+            .starts_with("1,000 rows, 10/40 batches, 10s, 100.00 rows/s"));
+        pl.done();
+        assert!(pl.to_string().contains("[1,000 rows, 10 batches, "));
+    }
+
+    #[test]
+    fn test_pause_hint() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).target_rate(Some(100.0));
+        assert_eq!(pl.pause_hint(), Duration::ZERO);
+        pl.start("");
+        pl.update_with_count(200);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(pl.pause_hint(), Duration::from_millis(1500));
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(pl.pause_hint(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_correct() {
+        let mut pl = ProgressLogger::default();
+        pl.local_speed(true);
+        pl.start("");
+        pl.update_with_count(100);
+        pl.log_now();
+        pl.correct(-30);
+        assert_eq!(pl.count, 70);
+        assert_eq!(pl.last_count, 70);
+        pl.correct(-100);
+        assert_eq!(pl.count, 0);
+        pl.set_count(42);
+        assert_eq!(pl.count, 42);
+        let _ = pl.to_string();
+    }
+
+    #[test]
+    fn test_shared() {
+        let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+        pl.lock().start("");
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        pl.update();
+                    }
+                });
+            }
+        });
+        pl.lock().update();
+        pl.lock().stop();
+        assert_eq!(pl.lock().count, 4001);
+    }
+
+    #[test]
+    fn test_interrupted() {
+        let clock = ManualClock::new();
+        let logs = testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone())
+            .item_name("pumpkin");
+        pl.start("");
+        clock.advance(Duration::from_secs(75));
+        pl.update_with_count(150);
+        pl.interrupted();
+        let records = logs.records();
+        let last = &records[records.len() - 2..];
+        assert_eq!(last[0].level, Level::Warn);
+        assert_eq!(last[0].message, "Interrupted after 1m 15s, 150 pumpkins");
+        assert_eq!(last[1].level, Level::Warn);
+        assert_eq!(last[1].kind, RecordKind::Summary);
+        assert!(pl.elapsed() == Some(Duration::from_secs(75)));
+    }
 }
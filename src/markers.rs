@@ -0,0 +1,151 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLogger, RecordKind};
+use log::Level;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The identifier of the next activity.
+static NEXT_ACTIVITY_ID: AtomicU64 = AtomicU64::new(1);
+
+impl ProgressLogger {
+    /// Set whether to emit marker lines at the start and at the end of each
+    /// activity.
+    ///
+    /// Each time the logger is [started](crate::ProgressLog::start), it gets
+    /// a new [activity identifier](#method.activity_id), unique within the
+    /// process, and if markers are enabled it emits the line `BEGIN <id>
+    /// <msg>`, where `<msg>` is the start message (the line is just `BEGIN
+    /// <id>` if the message is empty). When the activity is
+    /// [done](crate::ProgressLog::done) or
+    /// [interrupted](ProgressLogger::interrupted), the logger emits the line
+    /// `END <id> <summary>`, where `<summary>` contains the final stats (in
+    /// [stable format](ProgressLogger::stable_format), if enabled). The
+    /// syntax of marker lines will not change across releases, so log
+    /// processors can pair starts with completions even when many activities
+    /// interleave in one process. Marker lines have kind
+    /// [`RecordKind::Marker`]. The default is false.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.markers(true);
+    /// pl.start("Indexing...");
+    /// // Logs "BEGIN <id> Indexing..."
+    /// pl.update();
+    /// pl.done();
+    /// // Logs "END <id> Elapsed: ..."
+    /// ```
+    pub fn markers(&mut self, markers: bool) -> &mut Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Return the identifier of the current activity, or [`None`] if the
+    /// logger has not been started.
+    ///
+    /// Identifiers are unique within the process.
+    pub fn activity_id(&self) -> Option<u64> {
+        self.activity_id
+    }
+
+    /// Assign a new identifier to the activity and emit the begin marker, if
+    /// enabled.
+    pub(crate) fn begin_activity(&mut self, msg: &str) {
+        let id = NEXT_ACTIVITY_ID.fetch_add(1, Ordering::Relaxed);
+        self.activity_id = Some(id);
+        if self.markers {
+            self.emit(
+                Level::Info,
+                RecordKind::Marker,
+                format_args!(
+                    "BEGIN {}{}{}",
+                    id,
+                    if msg.is_empty() { "" } else { " " },
+                    msg
+                ),
+            );
+        }
+    }
+
+    /// Emit the end marker, if enabled, at the given level.
+    pub(crate) fn end_activity(&self, level: Level) {
+        let (true, Some(id)) = (self.markers, self.activity_id) else {
+            return;
+        };
+        if self.stable_format {
+            self.emit(
+                level,
+                RecordKind::Marker,
+                format_args!("END {} {}", id, self.stable_line()),
+            );
+        } else {
+            self.emit(
+                level,
+                RecordKind::Marker,
+                format_args!("END {} {}", id, self),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::RecordKind;
+    use std::time::Duration;
+
+    #[test]
+    fn test_markers() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .clock(clock.clone())
+            .deterministic(true)
+            .markers(true);
+        assert_eq!(pl.activity_id(), None);
+        pl.start("Indexing...");
+        let id = pl.activity_id().unwrap();
+        clock.advance(Duration::from_secs(2));
+        pl.update_with_count(10);
+        pl.done();
+        let markers = logs
+            .records()
+            .into_iter()
+            .filter(|record| record.kind == RecordKind::Marker)
+            .map(|record| record.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            [
+                format!("BEGIN {} Indexing...", id),
+                format!(
+                    "END {} Elapsed: 2s [10 items, 5.00 items/s, 200.00 ms/item]",
+                    id
+                ),
+            ]
+        );
+
+        pl.stable_format(true);
+        pl.start("");
+        assert_ne!(pl.activity_id(), Some(id));
+        let id = pl.activity_id().unwrap();
+        pl.interrupted();
+        let records = logs.records();
+        assert_eq!(records[records.len() - 1].level, log::Level::Warn);
+        assert_eq!(
+            records[records.len() - 1].message,
+            format!(
+                "END {} pl/1 state=stopped count=0 elapsed_ms=0 speed=- expected=- percent=- eta_ms=-",
+                id
+            )
+        );
+    }
+}
@@ -0,0 +1,403 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::humanize;
+use crate::ProgressLogger;
+use std::time::{Duration, Instant};
+use sysinfo::{
+    ComponentExt, NetworkExt, NetworksExt, Pid, ProcessExt, ProcessRefreshKind, RefreshKind,
+    System, SystemExt,
+};
+
+/**
+
+A measure displayed by a [`ProgressLogger`] at each log.
+
+Measures are [attached](ProgressLogger::attach) to a logger, which samples
+them when it [refreshes](crate::ProgressLog::refresh), that is, before each
+log, and displays their last value after the progress information, as
+`name value`. Measures are not displayed in [deterministic
+mode](ProgressLogger::deterministic).
+
+This crate provides measures for the [resident memory](Rss), the [CPU
+usage](CpuUsage), the [load average](LoadAverage), the number of [open
+files](OpenFiles) of the process, and the [network throughput](NetworkIo)
+and the [temperature](Thermal) of the host.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::Measure;
+
+struct Frontier(usize);
+
+impl Measure for Frontier {
+    fn name(&self) -> &str {
+        "frontier"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+
+let mut pl = ProgressLogger::default();
+pl.attach(Frontier(42));
+```
+
+*/
+pub trait Measure: Send + Sync {
+    /// Return the name of the measure, displayed before its value.
+    fn name(&self) -> &str;
+
+    /// Sample the measure, returning its value formatted for display, or
+    /// [`None`] if the value is not available.
+    fn sample(&mut self) -> Option<String>;
+}
+
+/// A [`Measure`] of the resident-set size of the current process.
+#[derive(Debug)]
+pub struct Rss {
+    system: System,
+    pid: Pid,
+}
+
+impl Default for Rss {
+    fn default() -> Self {
+        Self {
+            system: System::new_with_specifics(RefreshKind::new()),
+            pid: Pid::from(std::process::id() as usize),
+        }
+    }
+}
+
+impl Measure for Rss {
+    fn name(&self) -> &str {
+        "RSS"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        self.system
+            .refresh_process_specifics(self.pid, ProcessRefreshKind::new());
+        let process = self.system.process(self.pid)?;
+        Some(humanize(process.memory() as _) + "B")
+    }
+}
+
+/// A [`Measure`] of the CPU usage of the current process since the previous
+/// sample, as a percentage of a core.
+///
+/// The CPU usage is currently available only on Linux.
+#[derive(Debug)]
+pub struct CpuUsage {
+    /// The time and the CPU time of the previous sample.
+    last: Option<(Instant, Duration)>,
+}
+
+impl Default for CpuUsage {
+    fn default() -> Self {
+        Self {
+            last: crate::utils::cpu_time().map(|cpu_time| (Instant::now(), cpu_time)),
+        }
+    }
+}
+
+impl Measure for CpuUsage {
+    fn name(&self) -> &str {
+        "CPU"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        let now = Instant::now();
+        let cpu_time = crate::utils::cpu_time()?;
+        let (last_time, last_cpu_time) = self.last.replace((now, cpu_time))?;
+        let usage = cpu_time.saturating_sub(last_cpu_time).as_secs_f64()
+            / now.saturating_duration_since(last_time).as_secs_f64();
+        usage.is_finite().then(|| format!("{:.1}%", 100.0 * usage))
+    }
+}
+
+/// A [`Measure`] of the load average of the system over 1, 5 and 15
+/// minutes.
+#[derive(Debug)]
+pub struct LoadAverage {
+    system: System,
+}
+
+impl Default for LoadAverage {
+    fn default() -> Self {
+        Self {
+            system: System::new_with_specifics(RefreshKind::new()),
+        }
+    }
+}
+
+impl Measure for LoadAverage {
+    fn name(&self) -> &str {
+        "load"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        let load = self.system.load_average();
+        Some(format!(
+            "{:.2}/{:.2}/{:.2}",
+            load.one, load.five, load.fifteen
+        ))
+    }
+}
+
+/// A [`Measure`] of the number of file descriptors open by the current
+/// process.
+///
+/// The number of file descriptors is currently available only on Linux, where
+/// it is read from `/proc/self/fd`.
+#[derive(Debug, Default)]
+pub struct OpenFiles;
+
+impl Measure for OpenFiles {
+    fn name(&self) -> &str {
+        "FDs"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        {
+            // Minus one for the descriptor used to read the directory
+            let count = std::fs::read_dir("/proc/self/fd").ok()?.count();
+            Some(count.saturating_sub(1).to_string())
+        }
+        #[cfg(not(target_os = "linux"))]
+        None
+    }
+}
+
+/// A [`Measure`] of the network throughput of the host since the previous
+/// sample, in bytes received and sent per second over all interfaces.
+///
+/// Network traffic cannot be attributed to a single process, so this measure
+/// includes the traffic of all processes on the host.
+#[derive(Debug)]
+pub struct NetworkIo {
+    system: System,
+    /// The time and the total bytes received and sent at the previous sample.
+    last: (Instant, u64, u64),
+}
+
+impl NetworkIo {
+    /// Return the total bytes received and sent over all interfaces.
+    fn totals(&self) -> (u64, u64) {
+        self.system
+            .networks()
+            .iter()
+            .fold((0, 0), |(received, sent), (_, network)| {
+                (
+                    received.saturating_add(network.total_received()),
+                    sent.saturating_add(network.total_transmitted()),
+                )
+            })
+    }
+}
+
+impl Default for NetworkIo {
+    fn default() -> Self {
+        let mut network_io = Self {
+            system: System::new_with_specifics(RefreshKind::new().with_networks_list()),
+            last: (Instant::now(), 0, 0),
+        };
+        network_io.system.refresh_networks();
+        let (received, sent) = network_io.totals();
+        network_io.last = (Instant::now(), received, sent);
+        network_io
+    }
+}
+
+impl Measure for NetworkIo {
+    fn name(&self) -> &str {
+        "net"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        let now = Instant::now();
+        self.system.refresh_networks();
+        let (received, sent) = self.totals();
+        let (last_time, last_received, last_sent) =
+            std::mem::replace(&mut self.last, (now, received, sent));
+        let seconds = now.saturating_duration_since(last_time).as_secs_f64();
+        if seconds == 0.0 {
+            return None;
+        }
+        Some(format!(
+            "{}B/s in, {}B/s out",
+            humanize(received.saturating_sub(last_received) as f64 / seconds),
+            humanize(sent.saturating_sub(last_sent) as f64 / seconds)
+        ))
+    }
+}
+
+/// A [`Measure`] of the CPU temperature of the host, flagging thermal
+/// throttling.
+///
+/// The temperature is that of the CPU package, if the sensors report it, or
+/// otherwise the highest temperature reported by the sensors. Throttling is
+/// currently detected only on Linux, where the measure checks whether the
+/// throttling counters in `/sys/devices/system/cpu` have increased since the
+/// previous sample.
+#[derive(Debug)]
+pub struct Thermal {
+    system: System,
+    /// The value of the throttling counters at the previous sample.
+    throttle_count: Option<u64>,
+}
+
+impl Thermal {
+    /// Return the sum of the package throttling counters of all CPUs.
+    fn throttle_count() -> Option<u64> {
+        #[cfg(target_os = "linux")]
+        {
+            let mut total = None;
+            for entry in std::fs::read_dir("/sys/devices/system/cpu").ok()?.flatten() {
+                let path = entry.path().join("thermal_throttle/package_throttle_count");
+                if let Some(count) = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|count| count.trim().parse::<u64>().ok())
+                {
+                    total = Some(total.unwrap_or(0_u64).saturating_add(count));
+                }
+            }
+            total
+        }
+        #[cfg(not(target_os = "linux"))]
+        None
+    }
+}
+
+impl Default for Thermal {
+    fn default() -> Self {
+        Self {
+            system: System::new_with_specifics(RefreshKind::new().with_components_list()),
+            throttle_count: Self::throttle_count(),
+        }
+    }
+}
+
+impl Measure for Thermal {
+    fn name(&self) -> &str {
+        "temp"
+    }
+
+    fn sample(&mut self) -> Option<String> {
+        self.system.refresh_components();
+        let components = self.system.components();
+        let temperature = components
+            .iter()
+            .find(|component| component.label().contains("Package"))
+            .map(|component| component.temperature())
+            .or_else(|| {
+                components
+                    .iter()
+                    .map(|component| component.temperature())
+                    .filter(|temperature| temperature.is_finite())
+                    .reduce(f32::max)
+            });
+        let throttle_count = Self::throttle_count();
+        let throttled = matches!(
+            (self.throttle_count, throttle_count),
+            (Some(last), Some(current)) if current > last
+        );
+        self.throttle_count = throttle_count;
+        match (temperature, throttled) {
+            (Some(temperature), false) => Some(format!("{:.1}°C", temperature)),
+            (Some(temperature), true) => Some(format!("{:.1}°C, throttled", temperature)),
+            (None, true) => Some("throttled".to_owned()),
+            (None, false) => None,
+        }
+    }
+}
+
+impl ProgressLogger {
+    /// Attach a [`Measure`], which will be displayed at each log.
+    ///
+    /// Measures are displayed in the order in which they are attached.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use dsi_progress_logger::{CpuUsage, LoadAverage, Rss};
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.attach(Rss::default())
+    ///     .attach(CpuUsage::default())
+    ///     .attach(LoadAverage::default());
+    /// ```
+    pub fn attach(&mut self, measure: impl Measure + 'static) -> &mut Self {
+        self.measures.push((Box::new(measure), None));
+        self
+    }
+
+    /// Sample the attached measures.
+    pub(crate) fn sample_measures(&mut self) {
+        for (measure, value) in &mut self.measures {
+            *value = measure.sample();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    struct Constant(&'static str, Option<&'static str>);
+
+    impl Measure for Constant {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn sample(&mut self) -> Option<String> {
+            self.1.map(str::to_owned)
+        }
+    }
+
+    #[test]
+    fn test_measures() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .attach(Constant("depth", Some("7")))
+            .attach(Constant("frontier", None));
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(10);
+        pl.refresh();
+        assert!(pl.to_string().ends_with("; depth 7; frontier N/A"));
+
+        pl.deterministic(true);
+        assert!(!pl.to_string().contains("depth"));
+    }
+
+    #[test]
+    fn test_builtin_measures() {
+        assert!(Rss::default().sample().is_some());
+        assert!(LoadAverage::default().sample().is_some());
+        let mut network_io = NetworkIo::default();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(network_io.sample().unwrap().ends_with("B/s out"));
+        // Containers and virtual machines usually have no sensors
+        if let Some(temperature) = Thermal::default().sample() {
+            assert!(temperature.contains("°C") || temperature == "throttled");
+        }
+        #[cfg(target_os = "linux")]
+        {
+            assert!(OpenFiles.sample().is_some());
+            let mut cpu_usage = CpuUsage::default();
+            std::thread::sleep(Duration::from_millis(10));
+            assert!(cpu_usage.sample().unwrap().ends_with('%'));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::{humanize, Exclusive};
+use crate::{ProgressLogger, RecordKind};
+use log::Level;
+use std::fmt::{Formatter, Result};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessExt, SystemExt};
+
+impl ProgressLogger {
+    /// Set the amount of available memory, in bytes, under which the logger
+    /// warns that the process is at risk of being killed for lack of memory.
+    ///
+    /// If, at a log, the [available
+    /// memory](sysinfo::System::available_memory) (which takes into account
+    /// cgroup limits) is below the threshold, the logger emits a line at the
+    /// [warning](Level::Warn) level with the available memory and calls the
+    /// closure set with [`on_low_memory`](#method.on_low_memory), if any,
+    /// giving the process a chance to shed caches. The threshold is effective
+    /// only if [memory is displayed](crate::ProgressLog::display_memory). The
+    /// default is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.display_memory(true)
+    ///     .low_memory(Some(1 << 30))
+    ///     .on_low_memory(|pl| log::warn!("Shedding caches at {} items", pl.count()));
+    /// ```
+    pub fn low_memory(&mut self, threshold: Option<u64>) -> &mut Self {
+        self.low_memory = threshold;
+        self
+    }
+
+    /// Set a closure that will be called with the logger at each log at which
+    /// the available memory is below the [threshold](#method.low_memory).
+    pub fn on_low_memory(
+        &mut self,
+        hook: impl FnMut(&ProgressLogger) + Send + 'static,
+    ) -> &mut Self {
+        self.on_low_memory = Some(Exclusive::new(Box::new(hook)));
+        self
+    }
+
+    /// Warn and call the low-memory closure, if any, if the available memory
+    /// is below the threshold.
+    pub(crate) fn check_low_memory(&mut self) {
+        let (Some(threshold), Some(system)) = (self.low_memory, &self.system) else {
+            return;
+        };
+        if self.deterministic || system.available_memory() >= threshold {
+            return;
+        }
+        self.emit(
+            Level::Warn,
+            RecordKind::Info,
+            format_args!(
+                "Low memory: {}B available, below {}B",
+                humanize(system.available_memory() as _),
+                humanize(threshold as _)
+            ),
+        );
+        if let Some(mut hook) = self.on_low_memory.take() {
+            hook.get_mut()(self);
+            self.on_low_memory = Some(hook);
+        }
+    }
+
+    /// Return the current resident-set size of the process, or zero if memory
+    /// information is not displayed or not available.
+    fn resident_memory(&self) -> u64 {
+        self.system
+            .as_ref()
+            .and_then(|system| system.process(self.pid))
+            .map(|process| process.memory())
+            .unwrap_or(0)
+    }
+
+    /// Record the current resident-set size as the one at the start of the
+    /// next log interval.
+    pub(crate) fn update_last_memory(&mut self) {
+        self.last_memory = self.resident_memory();
+    }
+
+    /// Format the change of the resident-set size during the current log
+    /// interval and, if it is growing, the estimated time before the
+    /// available memory is exhausted at the current growth rate.
+    ///
+    /// The available memory takes into account the limits of the cgroup of
+    /// the process, if any.
+    pub(crate) fn fmt_memory_growth(&self, f: &mut Formatter<'_>, now: Instant) -> Result {
+        let Some(system) = &self.system else {
+            return Ok(());
+        };
+        let memory = self.resident_memory();
+        if memory == 0 || self.last_memory == 0 {
+            return Ok(());
+        }
+        let (sign, delta) = if memory >= self.last_memory {
+            ('+', memory - self.last_memory)
+        } else {
+            ('-', self.last_memory - memory)
+        };
+        f.write_fmt(format_args!(
+            "; res mem {}{}B {}",
+            sign,
+            humanize(delta as _),
+            self.messages.this_interval
+        ))?;
+        let elapsed = now.saturating_duration_since(self.last_log_time);
+        let seconds_to_limit =
+            system.available_memory() as f64 / (delta as f64 / elapsed.as_secs_f64());
+        if sign == '+' {
+            if let Ok(time_to_limit) = Duration::try_from_secs_f64(seconds_to_limit) {
+                f.write_fmt(format_args!(
+                    ", memory limit in ~{}",
+                    self.fmt_duration(time_to_limit)
+                ))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use log::Level;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_low_memory() {
+        let logs = crate::testing::CapturedLogs::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hook_calls = calls.clone();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .display_memory(true)
+            .low_memory(Some(u64::MAX))
+            .on_low_memory(move |_| {
+                hook_calls.fetch_add(1, Ordering::Relaxed);
+            });
+        pl.start("");
+        pl.log_now();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(logs.records().iter().any(
+            |record| record.level == Level::Warn && record.message.starts_with("Low memory: ")
+        ));
+        pl.low_memory(Some(0));
+        pl.log_now();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_memory_growth() {
+        let mut pl = ProgressLogger::default();
+        pl.display_memory(true);
+        pl.start("");
+        pl.log_now();
+        let mut buffer = Vec::<u8>::with_capacity(1 << 24);
+        buffer.resize(1 << 24, 1);
+        pl.update();
+        pl.refresh();
+        assert!(pl.to_string().contains("B this interval"));
+        drop(buffer);
+    }
+}
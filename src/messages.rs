@@ -0,0 +1,127 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+
+/**
+
+The fixed strings of the output of a [`ProgressLogger`].
+
+The built-in catalog is [`Messages::ENGLISH`], which is also the default.
+To localize the output, set a catalog with [`ProgressLogger::messages`]. Item
+names are pluralized with English rules, so non-English products should
+set an [item name](crate::ProgressLog::item_name) that is invariant, or
+already in the plural form.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::Messages;
+
+let mut pl = ProgressLogger::default();
+pl.messages(Messages {
+    completed: "Terminé.",
+    elapsed: "Durée",
+    done: "fait",
+    to_go: "restants",
+    to_end: "avant la fin",
+    ..Messages::ENGLISH
+});
+```
+
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Messages {
+    /// The message logged when the logger is stopped (`Completed.`).
+    pub completed: &'static str,
+    /// The message logged, followed by the elapsed time and the count, when
+    /// the logger is [interrupted](ProgressLogger::interrupted) (`Interrupted
+    /// after`).
+    pub interrupted: &'static str,
+    /// The label of the elapsed time in the final stats (`Elapsed`).
+    pub elapsed: &'static str,
+    /// The label of the percentage of completion (`done`).
+    pub done: &'static str,
+    /// The label of the number of items to process (`to go`).
+    pub to_go: &'static str,
+    /// The label of the estimated time to completion (`to end`).
+    pub to_end: &'static str,
+    /// The label of the increments during the log interval (`this
+    /// interval`).
+    pub this_interval: &'static str,
+    /// The label of the number of items in flight (`in flight`).
+    pub in_flight: &'static str,
+    /// The label of the item being processed (`processing`).
+    pub processing: &'static str,
+}
+
+impl Messages {
+    /// The built-in English catalog.
+    pub const ENGLISH: Messages = Messages {
+        completed: "Completed.",
+        interrupted: "Interrupted after",
+        elapsed: "Elapsed",
+        done: "done",
+        to_go: "to go",
+        to_end: "to end",
+        this_interval: "this interval",
+        in_flight: "in flight",
+        processing: "processing",
+    };
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+impl ProgressLogger {
+    /// Set the catalog of the fixed strings of the output.
+    ///
+    /// The default is [`Messages::ENGLISH`].
+    pub fn messages(&mut self, messages: Messages) -> &mut Self {
+        self.messages = messages;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_messages() {
+        let clock = ManualClock::new();
+        let logs = crate::testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone())
+            .messages(Messages {
+                completed: "Terminé.",
+                elapsed: "Durée",
+                done: "fait",
+                to_go: "restants",
+                to_end: "avant la fin",
+                ..Messages::ENGLISH
+            })
+            .expected_updates(Some(40));
+        pl.start("");
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(10);
+        assert!(pl
+            .to_string()
+            .contains("; 25.00% fait, 30 restants, 2s avant la fin"));
+        pl.done();
+        assert!(logs.lines().contains(&"Terminé.".to_owned()));
+        assert!(pl.to_string().starts_with("Durée: 1s ["));
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::Transport;
+use mpi::topology::SimpleCommunicator;
+use mpi::traits::*;
+use mpi::{Rank, Tag};
+use std::io;
+
+/**
+
+A [`Transport`] sending counts in MPI messages to the process of a given rank
+(by default, the process of rank 0), which aggregates them.
+
+Messages are tagged with [`MpiTransport::TAG`], so that they do not interfere
+with the other messages of the computation. This transport is available with
+the `mpi` feature, which requires an MPI installation.
+
+# Examples
+
+```no_run
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::{AggregateProgress, MpiTransport, WorkerProgress};
+use mpi::traits::*;
+
+let universe = mpi::initialize().unwrap();
+let world = universe.world();
+let rank = world.rank();
+if rank == 0 {
+    let mut pl = AggregateProgress::new(MpiTransport::new(world));
+    pl.item_name("pumpkin");
+    pl.start("Smashing pumpkins on the cluster...");
+    // until the workers are done
+    pl.poll().unwrap();
+    pl.done();
+} else {
+    let mut worker = WorkerProgress::new(MpiTransport::new(world), rank as usize);
+    for _ in 0..100 {
+        // do something on each pumpkin
+        worker.update();
+    }
+    worker.done().unwrap();
+}
+```
+
+*/
+pub struct MpiTransport {
+    world: SimpleCommunicator,
+    aggregator: Rank,
+}
+
+impl MpiTransport {
+    /// The tag of the messages carrying counts.
+    pub const TAG: Tag = 0x5052;
+
+    /// Create a new transport using the given communicator.
+    pub fn new(world: SimpleCommunicator) -> Self {
+        Self {
+            world,
+            aggregator: 0,
+        }
+    }
+
+    /// Set the rank of the process aggregating the counts. The default is
+    /// zero.
+    pub fn aggregator(mut self, aggregator: Rank) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+}
+
+impl Transport for MpiTransport {
+    fn send(&self, rank: usize, count: usize) -> io::Result<()> {
+        let message = [rank as u64, count as u64];
+        self.world
+            .process_at_rank(self.aggregator)
+            .send_with_tag(&message[..], Self::TAG);
+        Ok(())
+    }
+
+    fn receive(&self, counts: &mut dyn FnMut(usize, usize)) -> io::Result<()> {
+        while let Some((message, _)) = self
+            .world
+            .any_process()
+            .immediate_matched_probe_with_tag(Self::TAG)
+        {
+            let (message, _) = message.matched_receive_vec::<u64>();
+            if let [rank, count] = message[..] {
+                counts(
+                    rank.try_into().unwrap_or(usize::MAX),
+                    count.try_into().unwrap_or(usize::MAX),
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{Record, RecordKind, Sample, Sink};
+use opentelemetry::global::{self, BoxedSpan, BoxedTracer};
+use opentelemetry::metrics::{Counter, Gauge, Histogram};
+use opentelemetry::trace::{Span, Tracer};
+use opentelemetry::KeyValue;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The name of the instrumentation scope of the instruments and of the span.
+const SCOPE: &str = "dsi-progress-logger";
+
+/**
+
+A [`Sink`] exporting progress as OpenTelemetry instruments.
+
+The sink uses the global meter and tracer providers of the
+[`opentelemetry`] crate, and records, with an `item` attribute containing
+the name of an item:
+
+- the number of items in the `progress.items` counter;
+- the speed over the last log interval in the `progress.rate` gauge;
+- the speed over each log interval in the `progress.interval_speed` histogram.
+
+Moreover, the activity between [`start`](crate::ProgressLog::start) and
+[`done`](crate::ProgressLog::done) is wrapped in a span with the given name,
+whose attributes contain the final count and elapsed time.
+
+This sink is available only with the `otel` feature.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::OtelSink;
+
+let mut pl = ProgressLogger::default();
+pl.add_sink(OtelSink::new("smash pumpkins"));
+pl.start("Smashing pumpkins...");
+for _ in 0..100 {
+    // do something on each pumpkin
+    pl.update();
+}
+pl.done();
+```
+
+*/
+pub struct OtelSink {
+    span_name: Cow<'static, str>,
+    tracer: BoxedTracer,
+    span: Mutex<Option<BoxedSpan>>,
+    items: Counter<u64>,
+    rate: Gauge<f64>,
+    interval_speed: Histogram<f64>,
+    /// The count at the last sample, to compute the increments of the counter.
+    last_count: AtomicUsize,
+}
+
+impl OtelSink {
+    /// Create a new sink wrapping the activity in a span with the given name.
+    pub fn new(span_name: impl Into<Cow<'static, str>>) -> Self {
+        let meter = global::meter(SCOPE);
+        Self {
+            span_name: span_name.into(),
+            tracer: global::tracer(SCOPE),
+            span: Mutex::new(None),
+            items: meter
+                .u64_counter("progress.items")
+                .with_description("Number of items processed")
+                .with_unit("{item}")
+                .build(),
+            rate: meter
+                .f64_gauge("progress.rate")
+                .with_description("Speed over the last log interval")
+                .with_unit("{item}/s")
+                .build(),
+            interval_speed: meter
+                .f64_histogram("progress.interval_speed")
+                .with_description("Speed over each log interval")
+                .with_unit("{item}/s")
+                .build(),
+            last_count: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Sink for OtelSink {
+    fn emit(&self, _record: &Record<'_>) {}
+
+    fn sample(&self, sample: &Sample<'_>) {
+        let attributes = [KeyValue::new("item", sample.item_name.to_owned())];
+        let mut span = self.span.lock().unwrap_or_else(|e| e.into_inner());
+
+        if sample.kind == RecordKind::Start {
+            *span = Some(self.tracer.start(self.span_name.clone()));
+            self.last_count.store(sample.count, Ordering::Relaxed);
+        }
+
+        let last_count = self.last_count.swap(sample.count, Ordering::Relaxed);
+        self.items
+            .add(sample.count.saturating_sub(last_count) as u64, &attributes);
+
+        if sample.kind == RecordKind::Progress {
+            if let Some(interval_speed) = sample.interval_speed {
+                self.rate.record(interval_speed, &attributes);
+                self.interval_speed.record(interval_speed, &attributes);
+            }
+        }
+
+        if sample.kind == RecordKind::Summary {
+            if let Some(mut span) = span.take() {
+                span.set_attribute(KeyValue::new("item", sample.item_name.to_owned()));
+                span.set_attribute(KeyValue::new("count", sample.count as i64));
+                span.set_attribute(KeyValue::new("elapsed_secs", sample.elapsed.as_secs_f64()));
+                span.end();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_span_lifecycle() {
+        let clock = ManualClock::new();
+        let sink = Arc::new(OtelSink::new("test"));
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(sink.clone());
+        pl.start("");
+        assert!(sink.span.lock().unwrap().is_some());
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(100);
+        assert_eq!(sink.last_count.load(Ordering::Relaxed), 100);
+        pl.done();
+        assert!(sink.span.lock().unwrap().is_none());
+    }
+}
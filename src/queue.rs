@@ -0,0 +1,152 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::Exclusive;
+use crate::{ProgressLogger, Source};
+use std::fmt::{Debug, Formatter, Result};
+
+/// The depth of a queue attached to a [`ProgressLogger`], with its
+/// watermarks during the current log interval.
+pub(crate) struct QueueDepth {
+    /// A closure returning the current depth.
+    len: Source<usize>,
+    /// The last depth sampled.
+    depth: usize,
+    /// The minimum depth sampled during the current log interval.
+    min: usize,
+    /// The maximum depth sampled during the current log interval.
+    max: usize,
+}
+
+impl QueueDepth {
+    /// Sample the depth, updating the watermarks.
+    fn sample(&mut self) {
+        self.depth = self.len.get_mut()();
+        self.min = self.min.min(self.depth);
+        self.max = self.max.max(self.depth);
+    }
+}
+
+impl Debug for QueueDepth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.debug_struct("QueueDepth")
+            .field("depth", &self.depth)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressLogger {
+    /// Attach a queue, specified by a closure returning its length, whose
+    /// depth will be displayed at each log together with the minimum and
+    /// maximum depth during the log interval.
+    ///
+    /// The depth is sampled at each log and, between logs, at most once per
+    /// [state interval](#method.state_interval) (by default, 60 times per
+    /// second) when the logger checks the time, so watermarks reveal the
+    /// imbalance between producers and consumers even when the queue is
+    /// drained between two logs, while the closure is not called at each
+    /// [`update`](crate::ProgressLog::update).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::collections::VecDeque;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let queue = Arc::new(Mutex::new(VecDeque::<String>::new()));
+    /// let mut pl = ProgressLogger::default();
+    /// let depth = queue.clone();
+    /// pl.queue_depth(move || depth.lock().unwrap().len());
+    /// ```
+    pub fn queue_depth(&mut self, len: impl FnMut() -> usize + Send + 'static) -> &mut Self {
+        let mut queue = QueueDepth {
+            len: Exclusive::new(Box::new(len)),
+            depth: 0,
+            min: usize::MAX,
+            max: 0,
+        };
+        queue.sample();
+        self.queue = Some(queue);
+        self
+    }
+
+    /// Sample the depth of the queue, if any.
+    pub(crate) fn sample_queue(&mut self) {
+        if let Some(queue) = &mut self.queue {
+            queue.sample();
+        }
+    }
+
+    /// Start a new log interval for the watermarks of the queue, if any.
+    pub(crate) fn reset_queue_watermarks(&mut self) {
+        if let Some(queue) = &mut self.queue {
+            queue.min = queue.depth;
+            queue.max = queue.depth;
+        }
+    }
+
+    /// Format the depth of the queue, if any.
+    pub(crate) fn fmt_queue_depth(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(queue) = &self.queue {
+            f.write_fmt(format_args!(
+                "; queue {} [min {}, max {}]",
+                self.fmt_count(queue.depth),
+                self.fmt_count(queue.min),
+                self.fmt_count(queue.max)
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_queue_depth() {
+        let clock = ManualClock::new();
+        let queue = Arc::new(AtomicUsize::new(10));
+        let samples = Arc::new(AtomicUsize::new(0));
+        let mut pl = ProgressLogger::default();
+        {
+            let queue = queue.clone();
+            let samples = samples.clone();
+            pl.clock(clock.clone())
+                .deterministic(true)
+                .queue_depth(move || {
+                    samples.fetch_add(1, Ordering::Relaxed);
+                    queue.load(Ordering::Relaxed)
+                });
+        }
+        pl.start("");
+        for len in [40, 3, 12] {
+            queue.store(len, Ordering::Relaxed);
+            clock.advance(Duration::from_millis(20));
+            pl.update();
+        }
+        assert!(pl.to_string().ends_with("; queue 12 [min 3, max 40]"));
+        pl.log_now();
+        queue.store(20, Ordering::Relaxed);
+        clock.advance(Duration::from_millis(20));
+        pl.update();
+        assert!(pl.to_string().ends_with("; queue 20 [min 12, max 20]"));
+        // Updates within the state interval do not sample the depth
+        let before = samples.load(Ordering::Relaxed);
+        queue.store(0, Ordering::Relaxed);
+        for _ in 0..100 {
+            pl.update();
+        }
+        assert_eq!(samples.load(Ordering::Relaxed), before);
+        assert!(pl.to_string().ends_with("; queue 20 [min 12, max 20]"));
+    }
+}
@@ -0,0 +1,298 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/**
+
+A reader deriving progress from its position in a seekable source.
+
+The count of the logger is the position in the source, and its expected
+number of updates is the length of the source. Unlike counting bytes or
+records, this approach gives correct percentages even when the size of
+records varies wildly, or when the reader is wrapped by a decoder (e.g., of
+compressed data) that reads ahead.
+
+The count is set at each read and at each seek, after which the logger
+checks whether it is time to log.
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::ProgressSeekReader;
+use std::io::{BufRead, BufReader, Cursor};
+
+let data = Cursor::new(b"one\ntwo\nthree\n".to_vec());
+let mut reader = BufReader::new(ProgressSeekReader::new(data, ProgressLogger::default())?);
+reader.get_mut().logger_mut().start("Reading records...");
+for line in (&mut reader).lines() {
+    // process the record
+    line?;
+}
+reader.get_mut().logger_mut().done();
+#     Ok(())
+# }
+```
+
+*/
+pub struct ProgressSeekReader<R, P = ProgressLogger> {
+    reader: R,
+    pl: P,
+    /// The current position in the source.
+    position: u64,
+}
+
+impl<R: Read + Seek, P: ProgressLog> ProgressSeekReader<R, P> {
+    /// Create a new reader, setting the item name of the given logger to
+    /// `byte` and its expected number of updates to the length of the
+    /// source.
+    pub fn new(mut reader: R, mut pl: P) -> io::Result<Self> {
+        let position = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(position))?;
+        pl.item_name("byte")
+            .expected_updates(Some(len.try_into().unwrap_or(usize::MAX)));
+        pl.set_count(position.try_into().unwrap_or(usize::MAX));
+        Ok(Self {
+            reader,
+            pl,
+            position,
+        })
+    }
+
+    /// Set the count of the logger to the current position and check
+    /// whether it is time to log.
+    fn sync(&mut self) {
+        self.pl
+            .set_count(self.position.try_into().unwrap_or(usize::MAX));
+        self.pl.update_with_count(0);
+    }
+
+    /// Return the logger.
+    pub fn logger(&self) -> &P {
+        &self.pl
+    }
+
+    /// Return the logger mutably.
+    pub fn logger_mut(&mut self) -> &mut P {
+        &mut self.pl
+    }
+
+    /// Return the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Return the underlying reader and the logger, consuming this reader.
+    pub fn into_inner(self) -> (R, P) {
+        (self.reader, self.pl)
+    }
+}
+
+impl<R: Read + Seek, P: ProgressLog> Read for ProgressSeekReader<R, P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.position = self.position.saturating_add(n as u64);
+        self.sync();
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek, P: ProgressLog> Seek for ProgressSeekReader<R, P> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = self.reader.seek(pos)?;
+        self.sync();
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
+/// A compressed input whose position is tracked by a
+/// [`ProgressDecompressedReader`].
+///
+/// This reader is passed to the closure building the decoder in
+/// [`ProgressDecompressedReader::new`].
+#[derive(Debug)]
+pub struct CompressedInput<R> {
+    reader: R,
+    position: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CompressedInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.position.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/**
+
+A reader of decompressed data tracking both the position in the compressed
+input and the number of decompressed bytes.
+
+When reading through a decoder, the number of decompressed bytes diverges from
+the position in the compressed input: the former is the natural measure of
+throughput, whereas only the latter makes it possible to estimate completion.
+This reader sets the count of its logger to the number of decompressed bytes
+read, and its [completed fraction](ProgressLogger::completed_fraction) to the
+position in the compressed input divided by its length.
+
+# Examples
+
+With a decoder such as `flate2::read::GzDecoder`:
+```ignore
+let file = std::fs::File::open("data.gz")?;
+let mut reader = ProgressDecompressedReader::new(file, ProgressLogger::default(), GzDecoder::new)?;
+reader.logger_mut().start("Decompressing...");
+std::io::copy(&mut reader, &mut std::io::sink())?;
+reader.logger_mut().done();
+```
+
+*/
+pub struct ProgressDecompressedReader<D> {
+    decoder: D,
+    pl: ProgressLogger,
+    /// The position in the compressed input.
+    position: Arc<AtomicU64>,
+    /// The length of the compressed input.
+    len: u64,
+}
+
+impl<D: Read> ProgressDecompressedReader<D> {
+    /// Create a new reader decompressing the given input with the decoder
+    /// returned by the given closure.
+    ///
+    /// The item name of the logger is set to `byte`, and its expected number
+    /// of updates is cleared.
+    pub fn new<R: Read + Seek>(
+        mut input: R,
+        mut pl: ProgressLogger,
+        decoder: impl FnOnce(CompressedInput<R>) -> D,
+    ) -> io::Result<Self> {
+        let start = input.stream_position()?;
+        let len = input.seek(SeekFrom::End(0))?;
+        input.seek(SeekFrom::Start(start))?;
+        pl.item_name("byte").expected_updates(None);
+        let position = Arc::new(AtomicU64::new(start));
+        Ok(Self {
+            decoder: decoder(CompressedInput {
+                reader: input,
+                position: position.clone(),
+            }),
+            pl,
+            position,
+            len,
+        })
+    }
+
+    /// Return the logger.
+    pub fn logger(&self) -> &ProgressLogger {
+        &self.pl
+    }
+
+    /// Return the logger mutably.
+    pub fn logger_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+
+    /// Return the position in the compressed input.
+    pub fn compressed_position(&self) -> u64 {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    /// Return the decoder and the logger, consuming this reader.
+    pub fn into_inner(self) -> (D, ProgressLogger) {
+        (self.decoder, self.pl)
+    }
+}
+
+impl<D: Read> Read for ProgressDecompressedReader<D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.decoder.read(buf)?;
+        let fraction = self.compressed_position() as f64 / self.len as f64;
+        self.pl
+            .completed_fraction(Some(fraction).filter(|_| self.len != 0));
+        self.pl.update_with_count(n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    #[test]
+    fn test_seek_reader() -> io::Result<()> {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone());
+        let mut data = Cursor::new(vec![0; 1000]);
+        data.set_position(100);
+        let mut reader = ProgressSeekReader::new(data, pl)?;
+        assert_eq!(reader.logger().count(), 100);
+        reader.logger_mut().start("");
+
+        let mut buf = [0; 150];
+        reader.read_exact(&mut buf)?;
+        assert_eq!(reader.logger().count(), 250);
+        reader.seek(SeekFrom::Current(250))?;
+        assert_eq!(reader.logger().count(), 500);
+        clock.advance(Duration::from_secs(10));
+        reader.read_exact(&mut buf)?;
+        assert!(reader
+            .logger()
+            .to_string()
+            .contains("65.00% done, 350 to go"));
+        Ok(())
+    }
+
+    /// A toy decoder repeating each byte of the input ten times.
+    struct Repeat<R>(R);
+
+    impl<R: Read> Read for Repeat<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut byte = [0];
+            if buf.len() < 10 || self.0.read(&mut byte)? == 0 {
+                return Ok(0);
+            }
+            buf[..10].fill(byte[0]);
+            Ok(10)
+        }
+    }
+
+    #[test]
+    fn test_decompressed_reader() -> io::Result<()> {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone());
+        let mut reader = ProgressDecompressedReader::new(Cursor::new(vec![1; 100]), pl, Repeat)?;
+        reader.logger_mut().start("");
+        let mut buf = [0; 10];
+        for _ in 0..25 {
+            reader.read_exact(&mut buf)?;
+        }
+        assert_eq!(reader.compressed_position(), 25);
+        assert_eq!(reader.logger().count(), 250);
+        clock.advance(Duration::from_secs(10));
+        let display = reader.logger().to_string();
+        assert!(display.starts_with("250 bytes, 10s, 25.00 bytes/s"));
+        assert!(display.ends_with("; 25.00% done, 30s to end"));
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::handle::Inner;
+use crate::{ProgressLogger, ProgressLoggerHandle, RecordKind};
+use log::Level;
+use std::sync::{Arc, Mutex, Once, PoisonError, Weak};
+
+/// The registered loggers, with their names.
+static REGISTRY: Mutex<Vec<(String, Weak<Inner>)>> = Mutex::new(Vec::new());
+
+impl ProgressLoggerHandle {
+    /// Register the logger under the given name in the global registry of
+    /// loggers, replacing the logger previously registered under the same
+    /// name, if any.
+    ///
+    /// Registered loggers can be retrieved with [`registered_loggers`], and
+    /// their progress can be logged at once with [`dump_all`] or, if the
+    /// process panics, with the hook installed by [`log_on_panic`].
+    ///
+    /// The registry holds a weak reference to the logger, which is thus
+    /// unregistered when all its handles are dropped.
+    pub fn register(&self, name: impl AsRef<str>) -> &Self {
+        let name = name.as_ref();
+        let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+        registry.retain(|(other, inner)| other != name && inner.strong_count() != 0);
+        registry.push((name.to_owned(), Arc::downgrade(&self.0)));
+        self
+    }
+}
+
+/// Return the registered loggers that are still alive, with their names, in
+/// order of registration.
+pub fn registered_loggers() -> Vec<(String, ProgressLoggerHandle)> {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter_map(|(name, inner)| Some((name.clone(), ProgressLoggerHandle(inner.upgrade()?))))
+        .collect()
+}
+
+/// Call the given closure with the name of each registered logger that has
+/// been started and has not been stopped, and the logger.
+///
+/// If `blocking` is false, loggers that are locked (e.g., by a panicking
+/// thread) are skipped.
+fn for_each_active(blocking: bool, mut f: impl FnMut(&str, &ProgressLogger)) {
+    let loggers = if blocking {
+        registered_loggers()
+    } else {
+        let Ok(registry) = REGISTRY.try_lock() else {
+            return;
+        };
+        registry
+            .iter()
+            .filter_map(|(name, inner)| {
+                Some((name.clone(), ProgressLoggerHandle(inner.upgrade()?)))
+            })
+            .collect()
+    };
+    for (name, pl) in loggers {
+        let pl = if blocking {
+            pl.0.pl.lock().unwrap_or_else(PoisonError::into_inner)
+        } else {
+            match pl.0.pl.try_lock() {
+                Ok(pl) => pl,
+                Err(_) => continue,
+            }
+        };
+        if pl.start_time.is_some() && pl.stop_time.is_none() {
+            f(&name, &pl);
+        }
+    }
+}
+
+/**
+
+Log, at the [info](Level::Info) level, the name and the progress of all
+[registered](ProgressLoggerHandle::register) loggers that are running.
+
+This function makes it possible to inspect at once all the activities of a
+process, for example from a signal handler, a watchdog, or a debugging
+console. Each line is emitted by the logger it describes, so it is also sent
+to the logger sinks.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+
+let crawler = ProgressLoggerHandle::new(ProgressLogger::default());
+crawler.register("crawler").lock().start("Crawling...");
+let parser = ProgressLoggerHandle::new(ProgressLogger::default());
+parser.register("parser").lock().start("Parsing...");
+// Logs "crawler: ..." and "parser: ..."
+dsi_progress_logger::dump_all();
+```
+
+*/
+pub fn dump_all() {
+    for_each_active(true, |name, pl| {
+        pl.emit(
+            Level::Info,
+            RecordKind::Info,
+            format_args!("{}: {}", name, pl),
+        )
+    });
+}
+
+/**
+
+Install a panic hook logging, at the [error](Level::Error) level, the name
+and the progress of all [registered](ProgressLoggerHandle::register) loggers
+that are running, so that the logs of a crashed job show exactly how far it
+got.
+
+The hook chains to the previous one, which is invoked first. Loggers that are
+locked when the panic happens are skipped. Calling this function more than
+once has no further effect.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+
+dsi_progress_logger::log_on_panic();
+let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+pl.register("crawler").lock().start("Crawling...");
+for _ in 0..1000 {
+    pl.update();
+}
+pl.done();
+```
+
+*/
+pub fn log_on_panic() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            for_each_active(false, |name, pl| {
+                pl.emit(
+                    Level::Error,
+                    RecordKind::Info,
+                    format_args!("Progress of {} at panic: {}", name, pl),
+                )
+            });
+        }));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use log::Level;
+    use std::time::Duration;
+
+    #[test]
+    fn test_log_on_panic() {
+        super::log_on_panic();
+        let clock = ManualClock::new();
+        let logs = crate::testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .add_sink(logs.clone());
+        let pl = ProgressLoggerHandle::new(pl);
+        pl.register("test_log_on_panic").lock().start("");
+        clock.advance(Duration::from_secs(1));
+        pl.lock().update_with_count(10);
+        assert!(std::thread::spawn(|| panic!("test panic")).join().is_err());
+        assert!(logs
+            .records()
+            .iter()
+            .any(|record| record.level == Level::Error
+                && record.message
+                    == "Progress of test_log_on_panic at panic: 10 items, 1s, 10.00 items/s, 100.00 ms/item"));
+    }
+
+    #[test]
+    fn test_dump_all() {
+        let logs = crate::testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.deterministic(true).add_sink(logs.clone());
+        let pl = ProgressLoggerHandle::new(pl);
+        pl.register("test_dump_all");
+        super::dump_all();
+        assert!(logs.lines().is_empty());
+        pl.lock().start("");
+        assert!(super::registered_loggers()
+            .iter()
+            .any(|(name, _)| name == "test_dump_all"));
+        super::dump_all();
+        assert!(logs
+            .lines()
+            .iter()
+            .any(|line| line.starts_with("test_dump_all: 0 items")));
+        drop(pl);
+        assert!(!super::registered_loggers()
+            .iter()
+            .any(|(name, _)| name == "test_dump_all"));
+    }
+}
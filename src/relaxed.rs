@@ -0,0 +1,100 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// An atomic counter padded to avoid false sharing.
+#[repr(align(128))]
+#[derive(Debug, Default)]
+pub(crate) struct Slot(pub(crate) AtomicUsize);
+
+/**
+
+A per-thread counter whose value is folded into the count of a
+[`ProgressLogger`] only when the logger logs.
+
+Relaxed counters are obtained from
+[`ProgressLogger::relaxed_counter`]. Each counter has its own cache-padded
+slot, written only by the counter itself with a plain store: thus, an update
+requires no synchronization at all, and no cache line is shared among threads.
+The price to pay is bounded staleness: the logger sees the increments only when
+it reads the slots, that is, when logging, when calling
+[`refresh`](crate::ProgressLog::refresh), and when stopping. Thus, relaxed
+counters are usually paired with a [`Ticker`](crate::Ticker).
+
+Increments are never lost: dropping a counter keeps its contribution.
+
+# Example
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+
+stderrlog::new().verbosity(2).init()?;
+let mut pl = ProgressLogger::default();
+pl.item_name("pumpkin");
+let counters: Vec<_> = (0..4).map(|_| pl.relaxed_counter()).collect();
+pl.start("Smashing pumpkins in parallel...");
+let ticker = pl.spawn_ticker();
+std::thread::scope(|s| {
+    for mut counter in counters {
+        s.spawn(move || {
+            for _ in 0..1000 {
+                // do something on each pumpkin
+                counter.update();
+            }
+        });
+    }
+});
+ticker.done();
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug)]
+pub struct RelaxedCounter {
+    /// The local count.
+    count: usize,
+    /// The slot publishing the local count to the logger.
+    slot: Arc<Slot>,
+}
+
+impl RelaxedCounter {
+    /// Increase the count.
+    #[inline(always)]
+    pub fn update(&mut self) {
+        self.update_with_count(1);
+    }
+
+    /// Increase the count by the given amount.
+    #[inline(always)]
+    pub fn update_with_count(&mut self, count: usize) {
+        self.count = self.count.wrapping_add(count);
+        self.slot.0.store(self.count, Ordering::Relaxed);
+    }
+}
+
+impl ProgressLogger {
+    /// Return a new [`RelaxedCounter`] whose increments are folded into the
+    /// count of this logger when it logs.
+    ///
+    /// Each thread should use its own counter.
+    pub fn relaxed_counter(&mut self) -> RelaxedCounter {
+        let slot = Arc::new(Slot::default());
+        self.relaxed.push(slot.clone());
+        RelaxedCounter { count: 0, slot }
+    }
+
+    /// Return the sum of the values of the relaxed counters.
+    pub(crate) fn relaxed_sum(&self) -> usize {
+        self.relaxed.iter().fold(0, |sum, slot| {
+            sum.wrapping_add(slot.0.load(Ordering::Relaxed))
+        })
+    }
+}
@@ -0,0 +1,264 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::{cpu_time, humanize};
+use crate::{ProgressLog, ProgressLogger};
+use std::fmt::{Display, Formatter, Result};
+use std::time::Duration;
+
+/// The multi-line final report of a stopped [`ProgressLogger`].
+///
+/// See [`ProgressLogger::detailed_report`].
+pub(crate) struct DetailedReport<'a>(pub(crate) &'a ProgressLogger);
+
+impl Display for DetailedReport<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pl = self.0;
+        let (Some(start_time), Some(stop_time)) = (pl.start_time, pl.stop_time) else {
+            return write!(f, "ProgressLogger not stopped");
+        };
+        let elapsed = stop_time.saturating_duration_since(start_time);
+
+        f.write_str("Report:")?;
+        write!(
+            f,
+            "\n  {:<16}{} {}",
+            "items:",
+            pl.fmt_count(pl.count),
+            pl.item_name_for(pl.count)
+        )?;
+        write!(f, "\n  {:<16}{}", "elapsed:", pl.fmt_duration(elapsed))?;
+        write!(f, "\n  {:<16}", "average speed:")?;
+        pl.fmt_timing_speed(f, elapsed.as_secs_f64() / pl.count as f64)?;
+        write!(f, "\n  {:<16}", "peak speed:")?;
+        pl.fmt_timing_speed(f, 1.0 / pl.peak_speed)?;
+
+        if pl.deterministic {
+            return Ok(());
+        }
+        if pl.system.is_some() && pl.peak_memory != 0 {
+            write!(
+                f,
+                "\n  {:<16}{}B",
+                "peak mem:",
+                humanize(pl.peak_memory as _)
+            )?;
+        }
+        if let (Some(start_cpu_time), Some(cpu_time)) = (pl.start_cpu_time, cpu_time()) {
+            write!(
+                f,
+                "\n  {:<16}{}",
+                "CPU time:",
+                pl.fmt_duration(cpu_time.saturating_sub(start_cpu_time))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The totals of the runs of a [`ProgressLogger`] in [accumulate
+/// mode](ProgressLogger::accumulate), excluding the current run.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct GrandTotal {
+    /// The number of runs.
+    runs: usize,
+    /// The number of items of all runs.
+    count: usize,
+    /// The elapsed time of all runs.
+    elapsed: Duration,
+    /// The elapsed time of the shortest run.
+    min_elapsed: Option<Duration>,
+    /// The elapsed time of the longest run.
+    max_elapsed: Duration,
+}
+
+impl GrandTotal {
+    /// Add a run to the totals.
+    fn add(&mut self, count: usize, elapsed: Duration) {
+        self.runs = self.runs.saturating_add(1);
+        self.count = self.count.saturating_add(count);
+        self.elapsed = self.elapsed.saturating_add(elapsed);
+        self.min_elapsed = Some(self.min_elapsed.map_or(elapsed, |min| min.min(elapsed)));
+        self.max_elapsed = self.max_elapsed.max(elapsed);
+    }
+}
+
+/// The multi-line report of all runs of a [`ProgressLogger`] in [accumulate
+/// mode](ProgressLogger::accumulate).
+///
+/// Returned by [`ProgressLogger::grand_total_report`].
+pub struct GrandTotalReport<'a>(&'a ProgressLogger);
+
+impl Display for GrandTotalReport<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pl = self.0;
+        let mut total = pl.grand_total;
+        if let Some(elapsed) = pl.elapsed() {
+            total.add(pl.count, elapsed);
+        }
+
+        f.write_str("Grand total:")?;
+        write!(f, "\n  {:<16}{}", "runs:", pl.fmt_count(total.runs))?;
+        write!(
+            f,
+            "\n  {:<16}{} {}",
+            "items:",
+            pl.fmt_count(total.count),
+            pl.item_name_for(total.count)
+        )?;
+        write!(
+            f,
+            "\n  {:<16}{}",
+            "elapsed:",
+            pl.fmt_duration(total.elapsed)
+        )?;
+        write!(f, "\n  {:<16}", "average speed:")?;
+        pl.fmt_timing_speed(f, total.elapsed.as_secs_f64() / total.count as f64)?;
+        if let Some(min_elapsed) = total.min_elapsed.filter(|_| total.runs != 0) {
+            write!(
+                f,
+                "\n  {:<16}{} average, {} min, {} max",
+                "per run:",
+                pl.fmt_duration(total.elapsed.div_f64(total.runs as f64)),
+                pl.fmt_duration(min_elapsed),
+                pl.fmt_duration(total.max_elapsed)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ProgressLogger {
+    /// Set whether to accumulate the totals of successive runs.
+    ///
+    /// In accumulate mode, each time the logger is
+    /// [restarted](crate::ProgressLog::start) or
+    /// [reset](crate::ProgressLog::reset), the count and the elapsed time of
+    /// the previous run are added to grand totals, which can be displayed with
+    /// [`grand_total_report`](ProgressLogger::grand_total_report). This mode
+    /// is useful when the same logger times each iteration of an outer loop.
+    ///
+    /// Calling this method clears the grand totals. The default is false.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.accumulate(true);
+    /// for round in 0..3 {
+    ///     pl.start(format!("Round {}...", round));
+    ///     for _ in 0..100 {
+    ///         pl.update();
+    ///     }
+    ///     pl.done();
+    /// }
+    /// pl.info(format_args!("{}", pl.grand_total_report()));
+    /// ```
+    pub fn accumulate(&mut self, accumulate: bool) -> &mut Self {
+        self.accumulate = accumulate;
+        self.grand_total = GrandTotal::default();
+        self
+    }
+
+    /// Return a report summarizing all runs in [accumulate
+    /// mode](ProgressLogger::accumulate), including the current one, if any.
+    pub fn grand_total_report(&self) -> GrandTotalReport<'_> {
+        GrandTotalReport(self)
+    }
+
+    /// Add the current run, if any, to the grand totals, if in accumulate
+    /// mode.
+    pub(crate) fn accumulate_run(&mut self) {
+        if !self.accumulate {
+            return;
+        }
+        if let Some(elapsed) = self.elapsed() {
+            self.grand_total.add(self.count, elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_detailed_report() {
+        let clock = ManualClock::new();
+        let logs = crate::testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .detailed_report(true)
+            .completed_message(false)
+            .add_sink(logs.clone());
+        pl.item_name("pumpkin");
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(250);
+        pl.log_now();
+        clock.advance(Duration::from_secs(90));
+        pl.update_with_count(750);
+        pl.done();
+        assert_eq!(
+            logs.lines().last().unwrap(),
+            "Report:\n  \
+             items:          1,000 pumpkins\n  \
+             elapsed:        1m 40s\n  \
+             average speed:  10.00 pumpkins/s, 100.00 ms/pumpkin\n  \
+             peak speed:     25.00 pumpkins/s, 40.00 ms/pumpkin"
+        );
+    }
+
+    #[test]
+    fn test_grand_total_report() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true).accumulate(true);
+        for round in 1..=3 {
+            pl.start("");
+            clock.advance(Duration::from_secs(round));
+            pl.update_with_count(10);
+            pl.done();
+        }
+        assert_eq!(
+            pl.grand_total_report().to_string(),
+            "Grand total:\n  \
+             runs:           3\n  \
+             items:          30 items\n  \
+             elapsed:        6s\n  \
+             average speed:  5.00 items/s, 200.00 ms/item\n  \
+             per run:        2s average, 1s min, 3s max"
+        );
+        pl.accumulate(true);
+        assert!(pl
+            .grand_total_report()
+            .to_string()
+            .contains("runs:           1\n"));
+    }
+
+    #[test]
+    fn test_grand_total_many_runs() {
+        let mut total = super::GrandTotal {
+            runs: (1 << 32) - 1,
+            ..Default::default()
+        };
+        total.add(0, Duration::from_secs(1));
+        assert_eq!(total.runs, 1 << 32);
+        total.runs = usize::MAX;
+        total.add(0, Duration::from_secs(1));
+        assert_eq!(total.runs, usize::MAX);
+
+        let pl = ProgressLogger {
+            grand_total: total,
+            ..Default::default()
+        };
+        assert!(pl.grand_total_report().to_string().contains("per run:"));
+    }
+}
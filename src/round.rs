@@ -0,0 +1,204 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+use pluralizer::pluralize;
+use std::fmt::{Formatter, Result};
+use std::time::Instant;
+
+impl ProgressLogger {
+    /// Set the expected number of rounds of an iterative algorithm.
+    ///
+    /// Rounds are started with [`new_round`](crate::ProgressLog::new_round);
+    /// if the number of rounds is known, it is displayed together with the
+    /// current round, as in `round 2/5`. The default is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("node").rounds(Some(5));
+    /// pl.start("Computing PageRank...");
+    /// for _ in 0..5 {
+    ///     pl.new_round();
+    ///     for _ in 0..1000 {
+    ///         pl.light_update();
+    ///     }
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn rounds(&mut self, rounds: Option<usize>) -> &mut Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Set the name of the convergence metric of an iterative algorithm
+    /// (e.g., `residual`), and the threshold under which the algorithm
+    /// converges.
+    ///
+    /// The metric is fed with
+    /// [`record_metric`](crate::ProgressLog::record_metric), and displayed
+    /// with its name. If a threshold is set, the logger estimates the number
+    /// of rounds to convergence by extrapolating the decay of the metric
+    /// between the last two rounds in which it was recorded, assuming that it
+    /// decays geometrically. The default name is `metric`, and the default
+    /// threshold is [`None`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.convergence_metric("residual", Some(1E-6));
+    /// pl.start("Computing PageRank...");
+    /// let mut residual = 1.0;
+    /// while residual > 1E-6 {
+    ///     pl.new_round();
+    ///     residual /= 10.0;
+    ///     pl.record_metric(residual);
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn convergence_metric(
+        &mut self,
+        name: impl AsRef<str>,
+        threshold: Option<f64>,
+    ) -> &mut Self {
+        self.metric_name = name.as_ref().to_owned();
+        self.convergence_threshold = threshold;
+        self
+    }
+
+    /// Return the estimated number of rounds to convergence, if it can be
+    /// estimated.
+    fn rounds_to_convergence(&self) -> Option<f64> {
+        let threshold = self.convergence_threshold?;
+        let (_, metric) = self.metric?;
+        if metric <= threshold {
+            return Some(0.0);
+        }
+        let ratio = metric / self.prev_metric?;
+        let rounds = (threshold / metric).ln() / ratio.ln();
+        (ratio > 0.0 && ratio < 1.0 && rounds.is_finite()).then(|| rounds.ceil())
+    }
+
+    /// Format the last value of the convergence metric, if any, and the
+    /// estimated number of rounds to convergence, if the logger is running.
+    pub(crate) fn fmt_metric(&self, f: &mut Formatter<'_>) -> Result {
+        let Some((_, metric)) = self.metric else {
+            return Ok(());
+        };
+        f.write_fmt(format_args!("; {} {:.2e}", self.metric_name, metric))?;
+        if self.stop_time.is_none() {
+            match self.rounds_to_convergence() {
+                Some(rounds) if rounds <= 0.0 => f.write_str(", converged")?,
+                Some(rounds) => f.write_fmt(format_args!(
+                    ", ~{} {} to convergence",
+                    rounds,
+                    pluralize("round", rounds as isize, false)
+                ))?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the current round, with its count and speed, if a round has
+    /// been started.
+    pub(crate) fn fmt_round(&self, f: &mut Formatter<'_>, now: Instant) -> Result {
+        let Some(round_start_time) = self.round_start_time else {
+            return Ok(());
+        };
+        f.write_fmt(format_args!("; round {}", self.fmt_count(self.round)))?;
+        if let Some(rounds) = self.rounds {
+            f.write_fmt(format_args!("/{}", self.fmt_count(rounds)))?;
+        }
+        let count = self.count.saturating_sub(self.round_start_count);
+        let elapsed = now.saturating_duration_since(round_start_time);
+        f.write_fmt(format_args!(
+            ": {} {}, {}, ",
+            self.fmt_count(count),
+            self.item_name_for(count),
+            self.fmt_duration(elapsed)
+        ))?;
+        self.fmt_timing_speed(f, elapsed.as_secs_f64() / count as f64)
+    }
+
+    /// Format the number of rounds, if a round has been started.
+    pub(crate) fn fmt_rounds(&self, f: &mut Formatter<'_>) -> Result {
+        if self.round != 0 {
+            f.write_fmt(format_args!(
+                "; {} {}",
+                self.fmt_count(self.round),
+                pluralize("round", self.round as isize, false)
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rounds() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .item_name("node")
+            .rounds(Some(3));
+        pl.start("");
+        pl.new_round();
+        clock.advance(Duration::from_secs(2));
+        pl.update_with_count(100);
+        assert_eq!(
+            pl.to_string(),
+            "100 nodes, 2s, 50.00 nodes/s, 20.00 ms/node; \
+             round 1/3: 100 nodes, 2s, 50.00 nodes/s, 20.00 ms/node"
+        );
+        pl.new_round();
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(25);
+        assert!(pl
+            .to_string()
+            .ends_with("; round 2/3: 25 nodes, 1s, 25.00 nodes/s, 40.00 ms/node"));
+        pl.done();
+        assert!(pl.to_string().ends_with("; 2 rounds"));
+        pl.start("");
+        assert_eq!(pl.to_string(), "0 nodes, 0ms, n/a");
+    }
+
+    #[test]
+    fn test_convergence_metric() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .convergence_metric("residual", Some(1E-6));
+        pl.start("");
+        pl.new_round();
+        pl.record_metric(0.5);
+        pl.record_metric(1E-2);
+        assert!(pl
+            .to_string()
+            .ends_with("; round 1: 0 items, 0ms, n/a; residual 1.00e-2"));
+        pl.new_round();
+        pl.record_metric(1E-3);
+        assert!(pl
+            .to_string()
+            .ends_with("; residual 1.00e-3, ~3 rounds to convergence"));
+        pl.new_round();
+        pl.record_metric(1E-7);
+        assert!(pl.to_string().ends_with("; residual 1.00e-7, converged"));
+        pl.done();
+        assert!(pl.to_string().ends_with("; 3 rounds; residual 1.00e-7"));
+    }
+}
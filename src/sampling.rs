@@ -0,0 +1,110 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+
+impl ProgressLogger {
+    /// Set the sampling period, that is, the number of items represented by
+    /// a call to [`update`](crate::ProgressLog::update).
+    ///
+    /// In loops so hot that even the increment of
+    /// [`light_update`](crate::ProgressLog::light_update) is too expensive,
+    /// you can call `update` only once every *n* items: with a sampling
+    /// period of *n*, each call increases the count by *n*, so that the
+    /// count, the speed and the time to completion are extrapolated from the
+    /// sampled updates. Extrapolated counts are displayed with a `~` prefix
+    /// until the exact count is set with
+    /// [`done_with_count`](crate::ProgressLog::done_with_count). Periods
+    /// smaller than two disable sampling. The default is [`None`].
+    ///
+    /// The period applies only to `update`, here and in
+    /// [`ProgressLoggerHandle::update`](crate::ProgressLoggerHandle::update) and
+    /// [`BackgroundLogger::update`](crate::BackgroundLogger::update): all
+    /// other methods, such as [`light_update`](crate::ProgressLog::light_update)
+    /// and [`adaptive_update`](crate::ProgressLog::adaptive_update), count
+    /// exactly the given number of items, or one item.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.sampling(Some(1024));
+    /// pl.start("Hashing...");
+    /// let mut hash = 0_u64;
+    /// for i in 0..1_000_000_u64 {
+    ///     hash = hash.rotate_left(5) ^ i;
+    ///     if i % 1024 == 0 {
+    ///         pl.update();
+    ///     }
+    /// }
+    /// pl.done_with_count(1_000_000);
+    /// ```
+    pub fn sampling(&mut self, period: Option<usize>) -> &mut Self {
+        self.sampling = period.unwrap_or(1).max(1);
+        self
+    }
+
+    /// Return the prefix marking extrapolated counts.
+    pub(crate) fn approximate_marker(&self) -> &'static str {
+        if self.sampling > 1 && !self.reconciled {
+            "~"
+        } else {
+            ""
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sampling() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .log_interval(Duration::from_secs(1))
+            .sampling(Some(100));
+        pl.start("");
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(100));
+            pl.update();
+        }
+        assert_eq!(pl.count(), 1000);
+        assert!(pl.to_string().starts_with("~1,000 items, "));
+        clock.advance(Duration::from_millis(100));
+        pl.done_with_count(987);
+        assert_eq!(pl.count(), 987);
+        assert!(pl.to_string().contains(" [987 items, "));
+
+        pl.start("");
+        pl.update();
+        pl.done();
+        assert!(pl.to_string().contains(" [~100 items, "));
+
+        pl.sampling(None);
+        pl.start("");
+        pl.update();
+        assert!(pl.to_string().starts_with("1 item, "));
+    }
+
+    #[test]
+    fn test_sampling_adaptive_update() {
+        let mut pl = ProgressLogger::default();
+        pl.sampling(Some(10));
+        pl.start("");
+        for _ in 0..5000 {
+            pl.adaptive_update();
+        }
+        assert_eq!(pl.count(), 5000);
+        pl.update();
+        assert_eq!(pl.count(), 5010);
+    }
+}
@@ -0,0 +1,296 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use pluralizer::pluralize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::Duration;
+
+/// The state shared by the workers of a [`ProgressScope`].
+#[derive(Debug, Default)]
+struct Workers {
+    /// The number of running workers.
+    active: AtomicUsize,
+    /// The number of workers that panicked.
+    aborted: AtomicUsize,
+    /// The number of items processed by the workers that panicked.
+    aborted_items: AtomicUsize,
+    /// Whether the closure passed to [`ProgressLogger::scope`] has returned.
+    finished: AtomicBool,
+}
+
+/**
+
+A wrapper around a [`std::thread::Scope`] spawning workers whose progress is
+combined in a [`ProgressLogger`].
+
+See [`ProgressLogger::scope`].
+
+*/
+pub struct ProgressScope<'scope, 'env: 'scope> {
+    scope: &'scope Scope<'scope, 'env>,
+    counter: Arc<AtomicUsize>,
+    workers: Arc<Workers>,
+}
+
+impl<'scope, 'env> ProgressScope<'scope, 'env> {
+    /// Spawn a scoped thread running the given closure, which receives a
+    /// [`ScopedWorker`] handle to record its progress.
+    pub fn spawn<F, T>(&self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&mut ScopedWorker) -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.workers.active.fetch_add(1, Ordering::SeqCst);
+        let mut worker = ScopedWorker {
+            count: 0,
+            pending: 0,
+            counter: self.counter.clone(),
+            workers: self.workers.clone(),
+        };
+        self.scope.spawn(move || f(&mut worker))
+    }
+
+    /// Return the underlying scope.
+    pub fn scope(&self) -> &'scope Scope<'scope, 'env> {
+        self.scope
+    }
+}
+
+/**
+
+A handle recording the progress of a worker spawned by a [`ProgressScope`].
+
+Increments are accumulated locally and added to the count of the logger every
+[`ScopedWorker::FLUSH_THRESHOLD`] items, and when the handle is dropped. If
+the worker panics, the worker and the items it processed are recorded as
+aborted work.
+
+*/
+pub struct ScopedWorker {
+    /// The number of items processed by this worker.
+    count: usize,
+    /// The number of items not yet added to the counter.
+    pending: usize,
+    counter: Arc<AtomicUsize>,
+    workers: Arc<Workers>,
+}
+
+impl ScopedWorker {
+    /// The number of pending items triggering an update of the count of the
+    /// logger.
+    pub const FLUSH_THRESHOLD: usize = 1 << 10;
+
+    /// Increase the count by one.
+    #[inline(always)]
+    pub fn update(&mut self) {
+        self.update_with_count(1);
+    }
+
+    /// Increase the count by the given value.
+    #[inline(always)]
+    pub fn update_with_count(&mut self, count: usize) {
+        self.count = self.count.saturating_add(count);
+        self.pending = self.pending.saturating_add(count);
+        if self.pending >= Self::FLUSH_THRESHOLD {
+            self.flush();
+        }
+    }
+
+    /// Add the pending items to the count of the logger.
+    pub fn flush(&mut self) {
+        self.counter.fetch_add(self.pending, Ordering::Relaxed);
+        self.pending = 0;
+    }
+
+    /// Return the number of items processed by this worker.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Drop for ScopedWorker {
+    fn drop(&mut self) {
+        self.flush();
+        if thread::panicking() {
+            self.workers.aborted.fetch_add(1, Ordering::SeqCst);
+            self.workers
+                .aborted_items
+                .fetch_add(self.count, Ordering::SeqCst);
+        }
+        self.workers.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Marks the closure passed to [`ProgressLogger::scope`] as finished and wakes
+/// up the ticker thread when dropped, including during unwinding.
+struct FinishGuard<'a> {
+    workers: &'a Workers,
+    ticker: &'a thread::Thread,
+}
+
+impl Drop for FinishGuard<'_> {
+    fn drop(&mut self) {
+        self.workers.finished.store(true, Ordering::SeqCst);
+        self.ticker.unpark();
+    }
+}
+
+impl ProgressLogger {
+    /// Run the given closure with a [`ProgressScope`] spawning scoped worker
+    /// threads, while logging their combined progress from a background
+    /// thread.
+    ///
+    /// The logger must have been [started](ProgressLog::start). The counts of
+    /// the workers are added to the count of the logger through its
+    /// [counter](#method.counter). When all workers are finished, the logger
+    /// reports the number of workers that panicked and the number of items
+    /// they processed, if any; the panics are then propagated as usual by
+    /// [`std::thread::scope`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.item_name("node");
+    /// pl.start("Visiting nodes...");
+    /// pl.scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|w| {
+    ///             for _ in 0..1000 {
+    ///                 // visit a node
+    ///                 w.update();
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// pl.done();
+    /// ```
+    pub fn scope<'env, F, T>(&'env mut self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&ProgressScope<'scope, 'env>) -> T,
+    {
+        let counter = self.counter();
+        let workers = Arc::new(Workers::default());
+        let poll_interval = self.log_interval.min(Duration::from_millis(100));
+        let pl = self;
+
+        thread::scope(|scope| {
+            let ticker = {
+                let workers = workers.clone();
+                scope.spawn(move || {
+                    while !workers.finished.load(Ordering::SeqCst)
+                        || workers.active.load(Ordering::SeqCst) != 0
+                    {
+                        pl.update_with_count(0);
+                        thread::park_timeout(poll_interval);
+                    }
+                    pl.refresh();
+                    let aborted = workers.aborted.load(Ordering::SeqCst);
+                    if aborted != 0 {
+                        let aborted_items = workers.aborted_items.load(Ordering::SeqCst);
+                        pl.info(format_args!(
+                            "{} {} panicked after processing {} {}",
+                            aborted,
+                            pluralize("worker", aborted as isize, false),
+                            pl.fmt_count(aborted_items),
+                            pl.item_name_for(aborted_items),
+                        ));
+                    }
+                })
+            };
+            // Stop the ticker even if the closure panics, as otherwise
+            // std::thread::scope would wait for it forever.
+            let _finish = FinishGuard {
+                workers: &workers,
+                ticker: ticker.thread(),
+            };
+            f(&ProgressScope {
+                scope,
+                counter,
+                workers: workers.clone(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::CapturedLogs;
+
+    #[test]
+    fn test_scope() {
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        pl.start("");
+        let total: usize = pl.scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|i| {
+                    s.spawn(move |w| {
+                        for _ in 0..1000 * (i + 1) {
+                            w.update();
+                        }
+                        w.count()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        assert_eq!(total, 10_000);
+        pl.done();
+        logs.assert_final_count(10_000);
+    }
+
+    #[test]
+    fn test_scope_panic() {
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        pl.start("");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pl.scope(|s| {
+                s.spawn(|w| {
+                    w.update_with_count(10);
+                });
+                s.spawn(|w| {
+                    w.update_with_count(5);
+                    panic!("worker failure");
+                });
+            })
+        }));
+        assert!(result.is_err());
+        assert!(logs
+            .lines()
+            .contains(&"1 worker panicked after processing 5 items".to_owned()));
+        pl.done();
+        logs.assert_final_count(15);
+    }
+
+    #[test]
+    fn test_scope_closure_panic() {
+        let logs = CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        pl.start("");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pl.scope(|s| {
+                s.spawn(|w| {
+                    w.update_with_count(7);
+                });
+                panic!("closure failure");
+            })
+        }));
+        assert!(result.is_err());
+        pl.done();
+        logs.assert_final_count(7);
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::humanize;
+use crate::ProgressLogger;
+use std::fmt::{Formatter, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A secondary counter attached to a [`ProgressLogger`], such as the number
+/// of bytes read by another layer.
+pub(crate) struct SecondaryCounter {
+    /// The unit of the counter (e.g., `B`).
+    unit: String,
+    /// The counter.
+    counter: Arc<AtomicUsize>,
+    /// The value of the counter when the logger was started.
+    base: usize,
+}
+
+impl ProgressLogger {
+    /// Attach a secondary counter, incremented by another layer (e.g., the
+    /// number of bytes read by a parser), whose unit will be displayed after
+    /// the values derived from it.
+    ///
+    /// At each log and in the final stats, the logger displays the ratio
+    /// between the secondary counter and the count (e.g., bytes per item) and
+    /// the speed of the secondary counter, both computed from the start of
+    /// the logger. Changes in the ratio between runs reveal regressions such
+    /// as larger records or parsing amplification.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let bytes = Arc::new(AtomicUsize::new(0));
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone())
+    ///     .deterministic(true)
+    ///     .item_name("record")
+    ///     .secondary_counter("B", bytes.clone());
+    /// pl.start("");
+    /// clock.advance(Duration::from_secs(2));
+    /// bytes.fetch_add(256_000, Ordering::Relaxed);
+    /// pl.update_with_count(1000);
+    /// assert!(pl.to_string().ends_with("; 256.00B/record, 128.00kB/s"));
+    /// ```
+    pub fn secondary_counter(
+        &mut self,
+        unit: impl AsRef<str>,
+        counter: Arc<AtomicUsize>,
+    ) -> &mut Self {
+        let base = counter.load(Ordering::Relaxed);
+        self.secondary = Some(SecondaryCounter {
+            unit: unit.as_ref().to_owned(),
+            counter,
+            base,
+        });
+        self
+    }
+
+    /// Record the current value of the secondary counter, if any, as the
+    /// value at the start of the logger.
+    pub(crate) fn reset_secondary(&mut self) {
+        if let Some(secondary) = &mut self.secondary {
+            secondary.base = secondary.counter.load(Ordering::Relaxed);
+        }
+    }
+
+    /// Format the ratio between the secondary counter, if any, and the count,
+    /// and its speed, given the elapsed time.
+    pub(crate) fn fmt_secondary(&self, f: &mut Formatter<'_>, elapsed: Duration) -> Result {
+        let Some(secondary) = &self.secondary else {
+            return Ok(());
+        };
+        let value = secondary
+            .counter
+            .load(Ordering::Relaxed)
+            .wrapping_sub(secondary.base) as f64;
+        let ratio = value / self.count as f64;
+        if ratio.is_finite() {
+            f.write_fmt(format_args!(
+                "; {}{}/{}, ",
+                humanize(ratio),
+                secondary.unit,
+                self.item_singular
+            ))?;
+        } else {
+            f.write_fmt(format_args!(
+                "; n/a {}/{}, ",
+                secondary.unit, self.item_singular
+            ))?;
+        }
+        let speed = value / elapsed.as_secs_f64();
+        if speed.is_finite() {
+            f.write_fmt(format_args!("{}{}/s", humanize(speed), secondary.unit))
+        } else {
+            f.write_str("n/a")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_secondary_counter() {
+        let bytes = Arc::new(AtomicUsize::new(1_000));
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .deterministic(true)
+            .secondary_counter("B", bytes.clone());
+        pl.start("");
+        assert!(pl.to_string().ends_with("; n/a B/item, n/a"));
+        clock.advance(Duration::from_secs(4));
+        bytes.fetch_add(1_000, Ordering::Relaxed);
+        pl.update_with_count(10);
+        assert!(pl.to_string().ends_with("; 100.00B/item, 250.00B/s"));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; 100.00B/item, 250.00B/s"));
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLoggerError, ProgressLoggerHandle};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::flag;
+use signal_hook::iterator::Signals;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of attempts to lock the logger after a signal.
+const LOCK_ATTEMPTS: usize = 100;
+/// The delay between attempts to lock the logger after a signal.
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+impl ProgressLoggerHandle {
+    /// Spawn a thread that, when the process receives `SIGINT` (e.g., Ctrl-C)
+    /// or `SIGTERM` (e.g., from a scheduler), [interrupts](crate::ProgressLogger::interrupted)
+    /// the logger, so that the partial stats are logged, and exits the
+    /// process with status 128 plus the number of the signal.
+    ///
+    /// If another thread holds the lock of the logger for about a second, the
+    /// partial stats are not logged, but the process exits anyway. A second
+    /// signal terminates the process immediately, as if no handler had been
+    /// installed.
+    ///
+    /// This method is available with the `signals` feature on Unix platforms.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+    /// pl.exit_on_signals()?;
+    /// pl.lock().start("Crawling...");
+    /// for _ in 0..1000 {
+    ///     pl.update();
+    /// }
+    /// pl.done();
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn exit_on_signals(&self) -> Result<(), ProgressLoggerError> {
+        let interrupted = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM] {
+            // Handlers run in order of registration, so the default action
+            // runs only from the second signal on
+            flag::register_conditional_default(signal, interrupted.clone())?;
+            flag::register(signal, interrupted.clone())?;
+        }
+        let mut signals = Signals::new([SIGINT, SIGTERM])?;
+        let pl = self.clone();
+        std::thread::Builder::new()
+            .name("progress-logger-signals".into())
+            .spawn(move || {
+                if let Some(signal) = signals.forever().next() {
+                    match (0..LOCK_ATTEMPTS).find_map(|attempt| {
+                        if attempt != 0 {
+                            std::thread::sleep(LOCK_RETRY_DELAY);
+                        }
+                        pl.try_lock()
+                    }) {
+                        Some(mut pl) => pl.interrupted(),
+                        None => log::error!(
+                            "Interrupted by signal {}; the logger is locked by another thread",
+                            signal
+                        ),
+                    }
+                    std::process::exit(128 + signal);
+                }
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLoggerError;
+use log::Level;
+use std::fmt::Arguments;
+use std::time::{Duration, SystemTime};
+
+/// The kind of a line of output of a [`ProgressLogger`](crate::ProgressLogger).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RecordKind {
+    /// The message passed to [`start`](crate::ProgressLog::start).
+    Start,
+    /// A progress line.
+    Progress,
+    /// The `Completed.` line printed by [`done`](crate::ProgressLog::done).
+    Completed,
+    /// The final stats printed by [`done`](crate::ProgressLog::done).
+    Summary,
+    /// A message passed to [`info`](crate::ProgressLog::info).
+    Info,
+    /// A `BEGIN` or `END` [marker](crate::ProgressLogger::markers).
+    Marker,
+}
+
+/// A line of output of a [`ProgressLogger`](crate::ProgressLogger).
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Record<'a> {
+    /// The level of the line.
+    pub level: Level,
+    /// The kind of the line.
+    pub kind: RecordKind,
+    /// The count of the logger when the line was emitted.
+    pub count: usize,
+    /// The content of the line.
+    pub args: Arguments<'a>,
+}
+
+/// The numeric state of a [`ProgressLogger`](crate::ProgressLogger) at the
+/// time of a line of output.
+///
+/// Samples are passed to [`Sink::sample`] when the logger starts, at each
+/// progress line and when the logger is done, making it possible to export
+/// progress to metrics systems without parsing text.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Sample<'a> {
+    /// The kind of the line: [`Start`](RecordKind::Start),
+    /// [`Progress`](RecordKind::Progress) or [`Summary`](RecordKind::Summary).
+    pub kind: RecordKind,
+    /// The [identifier of the run](crate::ProgressLogger::run_id), if set.
+    pub run_id: Option<&'a str>,
+    /// The name of an item.
+    pub item_name: &'a str,
+    /// The wall-clock time of the sample, as given by the
+    /// [clock](crate::ProgressLogger::clock) of the logger.
+    pub time: SystemTime,
+    /// The number of items.
+    pub count: usize,
+    /// The elapsed time since the logger was started.
+    pub elapsed: Duration,
+    /// The average speed in items per second, if defined.
+    pub speed: Option<f64>,
+    /// The number of items processed since the last progress line.
+    pub interval_count: usize,
+    /// The speed in items per second since the last progress line, if defined.
+    pub interval_speed: Option<f64>,
+    /// The expected number of updates, if known.
+    pub expected_updates: Option<usize>,
+    /// The estimated time to completion, if the expected number of updates
+    /// is known.
+    pub time_to_end: Option<Duration>,
+    /// The resident memory of the process in bytes, if memory display is
+    /// enabled and the logger is not in deterministic mode.
+    pub memory: Option<u64>,
+}
+
+/**
+
+A destination for the output of a [`ProgressLogger`](crate::ProgressLogger).
+
+The output of a logger is always sent to the [`log`] crate; additionally, it is
+sent to all sinks added with
+[`ProgressLogger::add_sink`](crate::ProgressLogger::add_sink). Sinks are shared
+by [clones](crate::ProgressLog::clone) of a logger.
+
+*/
+pub trait Sink: Send + Sync {
+    /// Emit a line of output.
+    fn emit(&self, record: &Record<'_>);
+
+    /// Receive the numeric state of the logger.
+    ///
+    /// The default implementation does nothing.
+    fn sample(&self, _sample: &Sample<'_>) {}
+
+    /// Return and clear the first error that happened while writing the
+    /// output since the previous call, if any.
+    ///
+    /// Sinks must not panic or propagate errors while writing their output,
+    /// so that logging never interrupts the instrumented code; sinks that
+    /// can fail should instead record the error and return it here. The
+    /// default implementation returns [`None`].
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        None
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for std::sync::Arc<S> {
+    fn emit(&self, record: &Record<'_>) {
+        (**self).emit(record)
+    }
+
+    fn sample(&self, sample: &Sample<'_>) {
+        (**self).sample(sample)
+    }
+
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        (**self).take_error()
+    }
+}
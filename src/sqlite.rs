@@ -0,0 +1,209 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLoggerError, Record, RecordKind, Sample, Sink};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The schema of the database.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS samples (
+    run_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    time_ms INTEGER,
+    item_name TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    elapsed_ms INTEGER NOT NULL,
+    speed REAL,
+    interval_count INTEGER NOT NULL,
+    interval_speed REAL,
+    expected_updates INTEGER,
+    time_to_end_ms INTEGER,
+    memory INTEGER
+);
+CREATE INDEX IF NOT EXISTS samples_run_id ON samples (run_id);
+";
+
+/**
+
+A [`Sink`] appending samples to an SQLite database, making it possible to
+keep a local store of the progress of experiments across runs.
+
+When the logger starts, at each progress line, and when the logger is done,
+the sink appends a row to the table `samples`, keyed by the run identifier
+passed at creation, with the kind of the sample (`start`, `progress` or
+`summary`), its time in milliseconds since the Unix epoch, and the fields of
+[`Sample`]; durations are in milliseconds. The final summary of a run is thus
+the row of kind `summary`. The database and the table are created if they do
+not exist.
+
+Errors never interrupt logging; they are recorded and can be retrieved with
+[`ProgressLogger::check_sinks`](crate::ProgressLogger::check_sinks).
+
+This sink is available with the `sqlite` feature.
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::SqliteSink;
+
+let path = std::env::temp_dir().join("experiments.db");
+let mut pl = ProgressLogger::default();
+pl.add_sink(SqliteSink::open(&path, "pagerank-0.85")?);
+pl.start("Computing PageRank...");
+pl.done();
+pl.check_sinks()?;
+#     std::fs::remove_file(path)?;
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug)]
+pub struct SqliteSink {
+    connection: Mutex<Connection>,
+    run_id: String,
+    /// The first error since the last call to [`Sink::take_error`].
+    error: Mutex<Option<rusqlite::Error>>,
+}
+
+impl SqliteSink {
+    /// Open (or create) the database at the given path, appending samples
+    /// keyed by the given run identifier.
+    pub fn open(
+        path: impl AsRef<Path>,
+        run_id: impl AsRef<str>,
+    ) -> Result<Self, ProgressLoggerError> {
+        Self::with_connection(Connection::open(path)?, run_id)
+    }
+
+    /// Use the given connection, appending samples keyed by the given run
+    /// identifier.
+    pub fn with_connection(
+        connection: Connection,
+        run_id: impl AsRef<str>,
+    ) -> Result<Self, ProgressLoggerError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            run_id: run_id.as_ref().to_owned(),
+            error: Mutex::new(None),
+        })
+    }
+
+    fn insert(&self, sample: &Sample<'_>) -> rusqlite::Result<()> {
+        let kind = match sample.kind {
+            RecordKind::Start => "start",
+            RecordKind::Summary => "summary",
+            _ => "progress",
+        };
+        let millis = |duration: Duration| i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+        let integer = |value: u64| i64::try_from(value).unwrap_or(i64::MAX);
+        self.connection
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT INTO samples VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    self.run_id,
+                    kind,
+                    sample.time.duration_since(UNIX_EPOCH).ok().map(millis),
+                    sample.item_name,
+                    integer(sample.count as u64),
+                    millis(sample.elapsed),
+                    sample.speed,
+                    integer(sample.interval_count as u64),
+                    sample.interval_speed,
+                    sample.expected_updates.map(|x| integer(x as u64)),
+                    sample.time_to_end.map(millis),
+                    sample.memory.map(integer),
+                ],
+            )
+            .map(|_| ())
+    }
+}
+
+impl Sink for SqliteSink {
+    fn emit(&self, _record: &Record<'_>) {}
+
+    fn sample(&self, sample: &Sample<'_>) {
+        if let Err(error) = self.insert(sample) {
+            self.error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(error);
+        }
+    }
+
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        self.error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .map(ProgressLoggerError::Sqlite)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_sqlite() -> Result<(), ProgressLoggerError> {
+        let path = std::env::temp_dir().join(format!("dsi-pl-test-{}.db", std::process::id()));
+        let clock = ManualClock::new();
+        for run_id in ["a", "b"] {
+            let mut pl = ProgressLogger::default();
+            pl.clock(clock.clone())
+                .item_name("pumpkin")
+                .add_sink(SqliteSink::open(&path, run_id)?);
+            pl.start("");
+            clock.advance(Duration::from_secs(10));
+            pl.update_with_count(125);
+            clock.advance(Duration::from_secs(10));
+            pl.done_with_count(250);
+            pl.check_sinks()?;
+        }
+
+        let connection = Connection::open(&path)?;
+        let rows = connection
+            .prepare(
+                "SELECT kind, item_name, count, elapsed_ms, speed FROM samples WHERE run_id = 'b'",
+            )?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<f64>>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        assert_eq!(
+            rows,
+            [
+                ("start".into(), "pumpkin".into(), 0, 0, None),
+                ("progress".into(), "pumpkin".into(), 125, 10_000, Some(12.5)),
+                ("summary".into(), "pumpkin".into(), 250, 20_000, Some(12.5)),
+            ]
+        );
+        let runs: i64 =
+            connection.query_row("SELECT COUNT(DISTINCT run_id) FROM samples", [], |row| {
+                row.get(0)
+            })?;
+        assert_eq!(runs, 2);
+        drop(connection);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
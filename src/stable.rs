@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger};
+use std::fmt::{Display, Formatter, Result};
+
+/// The version of the [stable format](ProgressLogger::stable_format).
+pub const STABLE_FORMAT_VERSION: u32 = 1;
+
+impl ProgressLogger {
+    /// Set whether progress lines and final stats are written in a stable,
+    /// machine-parseable format.
+    ///
+    /// The usual output is meant for humans, and its content may change
+    /// across releases. In stable format, progress lines and final stats
+    /// (including the detailed report) are instead single lines whose field
+    /// order and syntax will not change within a format version:
+    ///
+    /// ```text
+    /// pl/1 state=running count=1500 elapsed_ms=2000 speed=750.000 expected=3000 percent=50.00 eta_ms=1998
+    /// ```
+    ///
+    /// - The first token is `pl/` followed by the [format
+    ///   version](STABLE_FORMAT_VERSION); any incompatible change to the
+    ///   format will increase the version.
+    /// - The remaining tokens are `key=value` pairs separated by a space, in
+    ///   the order above:
+    ///   - `state`: `running` or `stopped`;
+    ///   - `count`: the count;
+    ///   - `elapsed_ms`: the elapsed time in milliseconds;
+    ///   - `speed`: the speed in items per second, with three decimal
+    ///     digits;
+    ///   - `expected`: the [expected number of
+    ///     updates](crate::ProgressLog::expected_updates);
+    ///   - `percent`: the percentage of completion, with two decimal digits;
+    ///   - `eta_ms`: the estimated milliseconds to completion.
+    /// - Values that are not available are written as `-`.
+    /// - Numbers never use thousands separators or units, and decimal
+    ///   numbers always use a dot as decimal separator.
+    ///
+    /// Future versions of the format may append new fields at the end of the
+    /// line without changing the version, so parsers should ignore unknown
+    /// trailing fields. Other lines, such as the start message, are not
+    /// affected. The default is false.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut pl = ProgressLogger::default();
+    /// pl.clock(clock.clone())
+    ///     .deterministic(true)
+    ///     .stable_format(true)
+    ///     .expected_updates(Some(3000));
+    /// pl.start("Parsing...");
+    /// clock.advance(Duration::from_secs(2));
+    /// pl.update_with_count(1500);
+    /// assert_eq!(
+    ///     pl.stable_line().to_string(),
+    ///     "pl/1 state=running count=1500 elapsed_ms=2000 speed=750.000 expected=3000 percent=50.00 eta_ms=1998"
+    /// );
+    /// ```
+    pub fn stable_format(&mut self, stable_format: bool) -> &mut Self {
+        self.stable_format = stable_format;
+        self
+    }
+
+    /// Return the current state of the logger in [stable
+    /// format](#method.stable_format).
+    pub fn stable_line(&self) -> impl Display + '_ {
+        StableLine(self)
+    }
+}
+
+/// The current state of a logger in [stable
+/// format](ProgressLogger::stable_format).
+struct StableLine<'a>(&'a ProgressLogger);
+
+/// A value displayed as `-` if missing.
+struct Value<T>(Option<T>);
+
+impl<T: Display> Display for Value<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+/// A decimal number with the given number of decimal digits, displayed as
+/// `-` if missing or not finite.
+struct Decimal(Option<f64>, usize);
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0.filter(|value| value.is_finite()) {
+            Some(value) => f.write_fmt(format_args!("{:.*}", self.1, value)),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+impl Display for StableLine<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pl = self.0;
+        let (count, speed, percent, millis_to_end) = pl.kv_fields();
+        let elapsed = pl.elapsed().unwrap_or_default();
+        f.write_fmt(format_args!(
+            "pl/{} state={} count={} elapsed_ms={} speed={} expected={} percent={} eta_ms={}",
+            STABLE_FORMAT_VERSION,
+            if pl.stop_time.is_some() {
+                "stopped"
+            } else {
+                "running"
+            },
+            count,
+            elapsed.as_millis(),
+            Decimal(speed, 3),
+            Value(pl.expected_updates),
+            Decimal(percent, 2),
+            Value(millis_to_end),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stable_format() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .clock(clock.clone())
+            .deterministic(true)
+            .stable_format(true)
+            .detailed_report(true)
+            .log_interval(Duration::from_secs(1));
+        pl.start("Parsing...");
+        assert_eq!(
+            pl.stable_line().to_string(),
+            "pl/1 state=running count=0 elapsed_ms=0 speed=- expected=- percent=- eta_ms=-"
+        );
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(1_000_000);
+        clock.advance(Duration::from_secs(1));
+        pl.done();
+        assert_eq!(
+            logs.lines()[1..],
+            [
+                "pl/1 state=running count=1000000 elapsed_ms=1000 speed=1000000.000 expected=- percent=- eta_ms=-",
+                "Completed.",
+                "pl/1 state=stopped count=1000000 elapsed_ms=2000 speed=500000.000 expected=- percent=- eta_ms=-",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stable_format_elapsed_prefix() {
+        let logs = crate::testing::CapturedLogs::new();
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone())
+            .clock(clock.clone())
+            .deterministic(true)
+            .stable_format(true)
+            .elapsed_prefix(true)
+            .log_interval(Duration::from_secs(1));
+        pl.start("Parsing...");
+        clock.advance(Duration::from_secs(1));
+        pl.update_with_count(1_000_000);
+        clock.advance(Duration::from_secs(1));
+        pl.done();
+        assert_eq!(
+            logs.lines()[1..],
+            [
+                "pl/1 state=running count=1000000 elapsed_ms=1000 speed=1000000.000 expected=- percent=- eta_ms=-",
+                "[+00:00:02] Completed.",
+                "pl/1 state=stopped count=1000000 elapsed_ms=2000 speed=500000.000 expected=- percent=- eta_ms=-",
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::ProgressLogger;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+/// The state of a [`ProgressLogger`] at some point in time.
+///
+/// See [`SharedState`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Snapshot {
+    /// The name of an item.
+    pub item_name: String,
+    /// Whether the logger has been started.
+    pub started: bool,
+    /// Whether the logger has been stopped.
+    pub stopped: bool,
+    /// The number of items.
+    pub count: usize,
+    /// The elapsed time since the logger was started.
+    pub elapsed: Duration,
+    /// The average speed in items per second, if defined.
+    pub speed: Option<f64>,
+    /// The expected number of updates, if known.
+    pub expected_updates: Option<usize>,
+    /// The completed fraction of the activity, between 0 and 1, if the
+    /// expected number of updates is known.
+    pub fraction: Option<f64>,
+    /// The estimated time to completion, if the expected number of updates
+    /// is known.
+    pub time_to_end: Option<Duration>,
+}
+
+/**
+
+A handle to a [`Snapshot`] of a [`ProgressLogger`] kept up to date by the
+logger.
+
+The handle is returned by [`ProgressLogger::shared_state`], and it is
+designed for the render loops of graphical or text interfaces, which can
+read the [snapshot](#method.snapshot) at each frame without touching the
+logger and without causing any output.
+
+The logger updates the snapshot when it logs, when it is started or stopped,
+and, when it checks the time (i.e., at each [`update`](crate::ProgressLog::update)),
+at most once every [state interval](ProgressLogger::state_interval).
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+
+let mut pl = ProgressLogger::default();
+let state = pl.shared_state();
+pl.start("Smashing pumpkins...");
+std::thread::spawn(move || {
+    // in the render loop
+    let snapshot = state.snapshot();
+    println!("{} items", snapshot.count);
+});
+for _ in 0..100 {
+    // do something on each pumpkin
+    pl.update();
+}
+pl.done();
+```
+
+*/
+#[derive(Debug, Clone, Default)]
+pub struct SharedState(Arc<RwLock<Snapshot>>);
+
+impl SharedState {
+    /// Return a copy of the current snapshot.
+    pub fn snapshot(&self) -> Snapshot {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl ProgressLogger {
+    /// Return a handle to a [`Snapshot`] of the logger that the logger will
+    /// keep up to date.
+    ///
+    /// All calls return handles to the same snapshot.
+    pub fn shared_state(&mut self) -> SharedState {
+        let now = self.now();
+        let state = self.state.get_or_insert_with(SharedState::default).clone();
+        self.update_state(now);
+        state
+    }
+
+    /// Set the minimum interval between updates of the [shared
+    /// state](#method.shared_state), and between samples of the [depth of the
+    /// queue](#method.queue_depth), happening when the logger checks the
+    /// time.
+    ///
+    /// The default is 1/60 of a second.
+    pub fn state_interval(&mut self, state_interval: Duration) -> &mut Self {
+        self.state_interval = state_interval;
+        self
+    }
+
+    /// Update the shared state, if any.
+    pub(crate) fn update_state(&mut self, now: Instant) {
+        self.next_state_time = now
+            .checked_add(self.state_interval)
+            .unwrap_or(self.next_log_time);
+        let Some(state) = &self.state else {
+            return;
+        };
+        let elapsed = match (self.start_time, self.stop_time) {
+            (Some(start_time), Some(stop_time)) => stop_time.saturating_duration_since(start_time),
+            (Some(start_time), None) => now.saturating_duration_since(start_time),
+            _ => Duration::ZERO,
+        };
+        let snapshot = Snapshot {
+            item_name: self.item_name.clone(),
+            started: self.start_time.is_some(),
+            stopped: self.stop_time.is_some(),
+            count: self.count,
+            elapsed,
+            speed: Some(self.count as f64 / elapsed.as_secs_f64())
+                .filter(|speed| speed.is_finite()),
+            expected_updates: self.expected_updates,
+            fraction: self
+                .expected_updates
+                .map(|expected_updates| self.count as f64 / expected_updates as f64)
+                .or(self.completed_fraction),
+            time_to_end: self
+                .expected_updates
+                .map(|expected_updates| self.time_to_end(expected_updates, elapsed)),
+        };
+        *state.0.write().unwrap_or_else(PoisonError::into_inner) = snapshot;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_shared_state() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).expected_updates(Some(100));
+        let state = pl.shared_state();
+        assert!(!state.snapshot().started);
+
+        pl.start("");
+        assert!(state.snapshot().started);
+        pl.update();
+        // Not yet time to update the state
+        assert_eq!(state.snapshot().count, 0);
+        clock.advance(Duration::from_millis(20));
+        pl.update();
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.count, 2);
+        assert_eq!(snapshot.fraction, Some(0.02));
+        assert_eq!(snapshot.speed, Some(100.0));
+
+        pl.update();
+        pl.done();
+        let snapshot = state.snapshot();
+        assert!(snapshot.stopped);
+        assert_eq!(snapshot.count, 3);
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::udp_socket;
+use crate::{ProgressLoggerError, Record, RecordKind, Sample, Sink};
+use std::fmt::Write;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+
+/**
+
+A [`Sink`] sending progress to a StatsD server over UDP.
+
+At each progress line, and when the logger is done, the sink sends in a
+single datagram the gauges `items.count` (the number of items), `items.rate`
+(the speed in items per second over the last log interval) and, if memory
+display is enabled, `memory.rss` (the resident memory of the process in
+bytes), prefixed by the [prefix](#method.prefix) of the sink.
+
+If [tags](#method.tag) are set, they are appended in the DogStatsD format
+(e.g., `|#job:crawl,host:a`). Errors, such as an unreachable server, never
+interrupt logging; the first one is recorded and can be retrieved with
+[`ProgressLogger::check_sinks`](crate::ProgressLogger::check_sinks).
+
+# Examples
+
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::StatsdSink;
+
+let mut pl = ProgressLogger::default();
+pl.add_sink(
+    StatsdSink::new("127.0.0.1:8125")?
+        .prefix("crawler")
+        .tag("job", "crawl"),
+);
+#     Ok(())
+# }
+```
+
+*/
+#[derive(Debug)]
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+    tags: String,
+    /// The first error since the last call to [`Sink::take_error`].
+    error: Mutex<Option<io::Error>>,
+}
+
+impl StatsdSink {
+    /// Create a new sink sending datagrams to the given address.
+    pub fn new(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = udp_socket(addr)?;
+        Ok(Self {
+            socket,
+            prefix: String::new(),
+            tags: String::new(),
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Set the prefix of the names of the gauges, which will be separated
+    /// by a dot from the names.
+    pub fn prefix(mut self, prefix: impl AsRef<str>) -> Self {
+        self.prefix = prefix.as_ref().to_owned();
+        if !self.prefix.is_empty() {
+            self.prefix.push('.');
+        }
+        self
+    }
+
+    /// Add a DogStatsD tag.
+    pub fn tag(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.tags
+            .push_str(if self.tags.is_empty() { "|#" } else { "," });
+        self.tags.push_str(key.as_ref());
+        self.tags.push(':');
+        self.tags.push_str(value.as_ref());
+        self
+    }
+
+    /// Return the datagram for the given sample.
+    fn datagram(&self, sample: &Sample<'_>) -> String {
+        let mut datagram = String::new();
+        let mut gauge = |name: &str, value: &dyn std::fmt::Display| {
+            if !datagram.is_empty() {
+                datagram.push('\n');
+            }
+            let _ = write!(datagram, "{}{}:{}|g{}", self.prefix, name, value, self.tags);
+        };
+        gauge("items.count", &sample.count);
+        if let Some(interval_speed) = sample.interval_speed {
+            gauge("items.rate", &interval_speed);
+        }
+        if let Some(memory) = sample.memory {
+            gauge("memory.rss", &memory);
+        }
+        datagram
+    }
+}
+
+impl Sink for StatsdSink {
+    fn emit(&self, _record: &Record<'_>) {}
+
+    fn sample(&self, sample: &Sample<'_>) {
+        if sample.kind == RecordKind::Start {
+            return;
+        }
+        if let Err(error) = self.socket.send(self.datagram(sample).as_bytes()) {
+            self.error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(error);
+        }
+    }
+
+    fn take_error(&self) -> Option<ProgressLoggerError> {
+        self.error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .map(ProgressLoggerError::Io)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_statsd() -> io::Result<()> {
+        let server = UdpSocket::bind("127.0.0.1:0")?;
+        server.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).add_sink(
+            StatsdSink::new(server.local_addr()?)?
+                .prefix("test")
+                .tag("job", "a")
+                .tag("host", "b"),
+        );
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(125);
+
+        let mut buf = [0; 1024];
+        let len = server.recv(&mut buf)?;
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            "test.items.count:125|g|#job:a,host:b\ntest.items.rate:12.5|g|#job:a,host:b"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::utils::{cpu_time, rfc3339};
+use crate::ProgressLogger;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// The format of a [`Summary`] written by [`Summary::write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A JSON object; missing values are written as `null`.
+    Json,
+    /// A TOML table; missing values are omitted, and times are written as
+    /// offset date-times.
+    Toml,
+}
+
+/**
+
+A machine-readable summary of the activity of a [`ProgressLogger`].
+
+Summaries are returned by [`ProgressLogger::summary`] and written to a file
+by [`ProgressLogger::write_summary`], so that benchmark harnesses and
+experiment trackers can collect results without parsing logs.
+
+*/
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// The identifier of the run, if set.
+    pub run_id: Option<String>,
+    /// The metadata attached to the run.
+    pub metadata: Vec<(String, String)>,
+    /// The name of an item.
+    pub item_name: String,
+    /// The number of items.
+    pub count: usize,
+    /// The elapsed time, up to the stop time if the logger has been stopped.
+    pub elapsed: Duration,
+    /// The average speed in items per second, if defined.
+    pub speed: Option<f64>,
+    /// The highest speed in items per second over a log interval, if defined.
+    pub peak_speed: Option<f64>,
+    /// The wall-clock time at which the logger was started.
+    pub start_time: Option<SystemTime>,
+    /// The wall-clock time at which the logger was stopped, if it was.
+    pub stop_time: Option<SystemTime>,
+    /// The peak resident memory of the process in bytes, if memory display
+    /// is enabled.
+    pub peak_memory: Option<u64>,
+    /// The CPU time consumed by the process since the logger was started, if
+    /// available.
+    pub cpu_time: Option<Duration>,
+}
+
+impl Summary {
+    /// Write the summary to the given writer in the given format.
+    pub fn write(&self, mut writer: impl Write, format: Format) -> io::Result<()> {
+        let fields: [(&str, Option<Value>); 10] = [
+            ("run_id", self.run_id.as_deref().map(Value::Str)),
+            ("item_name", Some(Value::Str(&self.item_name))),
+            ("count", Some(Value::Int(self.count as u64))),
+            (
+                "elapsed_secs",
+                Some(Value::Float(self.elapsed.as_secs_f64())),
+            ),
+            ("speed", self.speed.map(Value::Float)),
+            ("peak_speed", self.peak_speed.map(Value::Float)),
+            ("start_time", self.start_time.map(Value::Time)),
+            ("stop_time", self.stop_time.map(Value::Time)),
+            ("peak_memory", self.peak_memory.map(Value::Int)),
+            (
+                "cpu_time_secs",
+                self.cpu_time.map(|t| Value::Float(t.as_secs_f64())),
+            ),
+        ];
+
+        match format {
+            Format::Json => {
+                writeln!(writer, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    let sep = if i + 1 < fields.len() || !self.metadata.is_empty() {
+                        ","
+                    } else {
+                        ""
+                    };
+                    match value {
+                        Some(Value::Str(s)) => {
+                            writeln!(writer, "  \"{}\": \"{}\"{}", key, escape(s), sep)?
+                        }
+                        Some(Value::Int(n)) => writeln!(writer, "  \"{}\": {}{}", key, n, sep)?,
+                        Some(Value::Float(x)) => writeln!(writer, "  \"{}\": {:?}{}", key, x, sep)?,
+                        Some(Value::Time(t)) => {
+                            writeln!(writer, "  \"{}\": \"{}\"{}", key, rfc3339(*t), sep)?
+                        }
+                        None => writeln!(writer, "  \"{}\": null{}", key, sep)?,
+                    }
+                }
+                if !self.metadata.is_empty() {
+                    writeln!(writer, "  \"metadata\": {{")?;
+                    for (i, (key, value)) in self.metadata.iter().enumerate() {
+                        let sep = if i + 1 < self.metadata.len() { "," } else { "" };
+                        writeln!(
+                            writer,
+                            "    \"{}\": \"{}\"{}",
+                            escape(key),
+                            escape(value),
+                            sep
+                        )?;
+                    }
+                    writeln!(writer, "  }}")?;
+                }
+                writeln!(writer, "}}")
+            }
+            Format::Toml => {
+                for (key, value) in &fields {
+                    match value {
+                        Some(Value::Str(s)) => writeln!(writer, "{} = \"{}\"", key, escape(s))?,
+                        Some(Value::Int(n)) => writeln!(writer, "{} = {}", key, n)?,
+                        Some(Value::Float(x)) => writeln!(writer, "{} = {:?}", key, x)?,
+                        Some(Value::Time(t)) => writeln!(writer, "{} = {}", key, rfc3339(*t))?,
+                        None => {}
+                    }
+                }
+                if !self.metadata.is_empty() {
+                    writeln!(writer, "\n[metadata]")?;
+                    for (key, value) in &self.metadata {
+                        writeln!(writer, "\"{}\" = \"{}\"", escape(key), escape(value))?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A value of a field of a [`Summary`].
+enum Value<'a> {
+    Str(&'a str),
+    Int(u64),
+    Float(f64),
+    Time(SystemTime),
+}
+
+/// Escape a string for JSON and TOML basic strings.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl ProgressLogger {
+    /// Return a summary of the activity of the logger, or `None` if the
+    /// logger has not been started.
+    ///
+    /// The summary can be requested while the logger is running, but it is
+    /// meant to be requested after [`done`](crate::ProgressLog::done).
+    pub fn summary(&self) -> Option<Summary> {
+        let elapsed = self
+            .stop_time
+            .unwrap_or_else(|| self.now())
+            .saturating_duration_since(self.start_time?);
+        let finite = |x: f64| Some(x).filter(|x| x.is_finite());
+        Some(Summary {
+            run_id: self.run_id.clone(),
+            metadata: self.metadata.clone(),
+            item_name: self.item_name.clone(),
+            count: self.count,
+            elapsed,
+            speed: finite(self.count as f64 / elapsed.as_secs_f64()),
+            peak_speed: Some(self.peak_speed).filter(|&speed| speed > 0.0),
+            start_time: self.start_system_time,
+            stop_time: self.stop_system_time,
+            peak_memory: Some(self.peak_memory)
+                .filter(|&mem| mem != 0 && self.system.is_some() && !self.deterministic),
+            cpu_time: match (self.start_cpu_time, self.deterministic) {
+                (Some(start_cpu_time), false) => {
+                    cpu_time().map(|t| t.saturating_sub(start_cpu_time))
+                }
+                _ => None,
+            },
+        })
+    }
+
+    /// Write a [summary](#method.summary) of the activity of the logger to a
+    /// file in the given format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput)
+    /// if the logger has not been started, or any error that happened while
+    /// writing the file.
+    ///
+    /// # Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dsi_progress_logger::prelude::*;
+    /// use dsi_progress_logger::Format;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.start("Smashing pumpkins...");
+    /// pl.update_with_count(100);
+    /// pl.done();
+    /// let path = std::env::temp_dir().join("pumpkins.json");
+    /// pl.write_summary(&path, Format::Json)?;
+    /// #     std::fs::remove_file(path)?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn write_summary(&self, path: impl AsRef<Path>, format: Format) -> io::Result<()> {
+        let summary = self
+            .summary()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "logger not started"))?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        summary.write(&mut writer, format)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    fn summary() -> Summary {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.item_name("\"pumpkin\"");
+        pl.start("");
+        clock.advance(Duration::from_secs(10));
+        pl.update_with_count(250);
+        pl.log_now();
+        clock.advance(Duration::from_secs(90));
+        pl.update_with_count(750);
+        pl.done();
+        pl.summary().unwrap()
+    }
+
+    #[test]
+    fn test_json() {
+        let mut out = Vec::new();
+        summary().write(&mut out, Format::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"{
+  "run_id": null,
+  "item_name": "\"pumpkin\"",
+  "count": 1000,
+  "elapsed_secs": 100.0,
+  "speed": 10.0,
+  "peak_speed": 25.0,
+  "start_time": "1970-01-01T00:00:00Z",
+  "stop_time": "1970-01-01T00:01:40Z",
+  "peak_memory": null,
+  "cpu_time_secs": null
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_toml() {
+        let mut out = Vec::new();
+        summary().write(&mut out, Format::Toml).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"item_name = "\"pumpkin\""
+count = 1000
+elapsed_secs = 100.0
+speed = 10.0
+peak_speed = 25.0
+start_time = 1970-01-01T00:00:00Z
+stop_time = 1970-01-01T00:01:40Z
+"#
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut pl = ProgressLogger::default();
+        pl.run_id("42")
+            .metadata("git", "deadbeef")
+            .metadata("input", "a \"b\"");
+        pl.start("");
+        pl.done();
+        let mut summary = pl.summary().unwrap();
+        summary.start_time = None;
+        summary.stop_time = None;
+        summary.cpu_time = None;
+        summary.peak_speed = None;
+        summary.speed = None;
+        let mut out = Vec::new();
+        summary.write(&mut out, Format::Json).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.starts_with("{\n  \"run_id\": \"42\",\n"));
+        assert!(json.ends_with(
+            "\"cpu_time_secs\": null,\n  \"metadata\": {\n    \"git\": \"deadbeef\",\n    \
+             \"input\": \"a \\\"b\\\"\"\n  }\n}\n"
+        ));
+        let mut out = Vec::new();
+        summary.write(&mut out, Format::Toml).unwrap();
+        let toml = String::from_utf8(out).unwrap();
+        assert!(toml.starts_with("run_id = \"42\"\n"));
+        assert!(
+            toml.ends_with("\n[metadata]\n\"git\" = \"deadbeef\"\n\"input\" = \"a \\\"b\\\"\"\n")
+        );
+    }
+
+    #[test]
+    fn test_not_started() {
+        let pl = ProgressLogger::default();
+        assert!(pl.summary().is_none());
+        let err = pl
+            .write_summary(std::env::temp_dir().join("never.json"), Format::Json)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
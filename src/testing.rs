@@ -0,0 +1,141 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+/*!
+
+Utilities to test code instrumented with a [`ProgressLogger`](crate::ProgressLogger).
+
+[`CapturedLogs`] is a [`Sink`] recording the output of a logger, so that it is
+possible to make assertions about it without setting up a global logger. Paired
+with a [`ManualClock`](crate::ManualClock), it makes tests fully deterministic.
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::testing::CapturedLogs;
+use std::time::Duration;
+
+let logs = CapturedLogs::new();
+let clock = ManualClock::new();
+let mut pl = ProgressLogger::default();
+pl.add_sink(logs.clone()).clock(clock.clone());
+pl.start("Smashing pumpkins...");
+for _ in 0..100 {
+    clock.advance(Duration::from_secs(1));
+    pl.update();
+}
+pl.done();
+
+logs.assert_logged_at_least(9);
+logs.assert_final_count(100);
+```
+
+*/
+
+use crate::{Record, RecordKind, Sink};
+use log::Level;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A line of output recorded by [`CapturedLogs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    /// The level of the line.
+    pub level: Level,
+    /// The kind of the line.
+    pub kind: RecordKind,
+    /// The count of the logger when the line was emitted.
+    pub count: usize,
+    /// The content of the line.
+    pub message: String,
+}
+
+/// A [`Sink`] recording all lines of output.
+///
+/// Clones share the recorded lines, so you can add a clone to a logger and
+/// inspect the output using the original.
+#[derive(Debug, Clone, Default)]
+pub struct CapturedLogs(Arc<Mutex<Vec<CapturedRecord>>>);
+
+impl CapturedLogs {
+    /// Create a new empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<CapturedRecord>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the recorded lines.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.lock().clone()
+    }
+
+    /// Return the content of the recorded lines.
+    pub fn lines(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .map(|record| record.message.clone())
+            .collect()
+    }
+
+    /// Return the number of recorded progress lines.
+    pub fn progress_lines(&self) -> usize {
+        self.lock()
+            .iter()
+            .filter(|record| record.kind == RecordKind::Progress)
+            .count()
+    }
+
+    /// Return the count of the last final stats recorded, if any.
+    pub fn final_count(&self) -> Option<usize> {
+        self.lock()
+            .iter()
+            .rev()
+            .find(|record| record.kind == RecordKind::Summary)
+            .map(|record| record.count)
+    }
+
+    /// Discard all recorded lines.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Assert that at least `n` progress lines have been recorded.
+    #[track_caller]
+    pub fn assert_logged_at_least(&self, n: usize) {
+        let progress_lines = self.progress_lines();
+        assert!(
+            progress_lines >= n,
+            "expected at least {} progress lines, found {}: {:#?}",
+            n,
+            progress_lines,
+            self.lines()
+        );
+    }
+
+    /// Assert that final stats have been recorded, and that their count is `count`.
+    #[track_caller]
+    pub fn assert_final_count(&self, count: usize) {
+        assert_eq!(
+            self.final_count(),
+            Some(count),
+            "unexpected final count: {:#?}",
+            self.lines()
+        );
+    }
+}
+
+impl Sink for CapturedLogs {
+    fn emit(&self, record: &Record<'_>) {
+        self.lock().push(CapturedRecord {
+            level: record.level,
+            kind: record.kind,
+            count: record.count,
+            message: record.args.to_string(),
+        });
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger, ProgressLoggerGuard, ProgressLoggerHandle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The minimum time a ticker waits between two checks.
+const MIN_TICK: Duration = Duration::from_millis(10);
+
+/**
+
+A background thread logging periodically the progress of a [`ProgressLogger`].
+
+A ticker is useful when the count of the logger comes from an external source,
+such as a [watch closure](ProgressLogger::watch) or an attached
+[counter](ProgressLogger::counter): in this case, no update call is necessary
+to obtain a log line at each interval.
+
+The ticker takes ownership of the logger, which remains accessible through
+[`logger`](#method.logger), or through [handles](#method.handle)
+that can be passed to other threads. The thread is stopped when calling
+[`stop`](#method.stop) or [`done`](#method.done), or when the ticker is dropped.
+
+*/
+pub struct Ticker {
+    /// The logger, shared with the thread.
+    pl: ProgressLoggerHandle,
+    /// Whether the thread should stop.
+    stop: Arc<AtomicBool>,
+    /// The thread logging progress.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Ticker {
+    /// Spawn a ticker for the given logger.
+    pub fn new(pl: ProgressLogger) -> Self {
+        let pl = ProgressLoggerHandle::new(pl);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let pl = pl.clone();
+            let stop = stop.clone();
+            thread::spawn(move || loop {
+                let timeout = {
+                    let mut pl = pl.lock();
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if pl.start_time.is_some() && pl.stop_time.is_none() {
+                        pl.log_if();
+                        pl.ticker_timeout()
+                    } else {
+                        pl.log_interval
+                    }
+                };
+                thread::park_timeout(timeout);
+            })
+        };
+
+        Self {
+            pl,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Return a guard giving access to the logger.
+    ///
+    /// The ticker cannot log while the guard is alive.
+    pub fn logger(&self) -> ProgressLoggerGuard<'_> {
+        self.pl.lock()
+    }
+
+    /// Return a handle to the logger.
+    pub fn handle(&self) -> ProgressLoggerHandle {
+        self.pl.clone()
+    }
+
+    /// Stop the thread and return the logger.
+    pub fn stop(mut self) -> ProgressLogger {
+        self.join();
+        let pl = std::mem::take(&mut *self.logger());
+        pl
+    }
+
+    /// Stop the thread and call [`done`](ProgressLog::done) on the logger.
+    pub fn done(self) {
+        self.stop().done();
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            thread.thread().unpark();
+            // The thread does not panic, except in logging backends
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+impl ProgressLogger {
+    /// Return the time to wait before the next log, according to the
+    /// [clock](ProgressLogger::clock) of the logger.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn time_to_next_log(&self) -> Duration {
+        self.next_log_time.saturating_duration_since(self.now())
+    }
+
+    /// Return the time a ticker should wait before checking again whether
+    /// it is time to log.
+    ///
+    /// If the time of the next log has passed without logging, as it happens
+    /// when there are fewer than [`min_items_per_log`](ProgressLogger::min_items_per_log)
+    /// new items, the ticker checks again after a tenth of the log interval,
+    /// but never sooner than [`MIN_TICK`].
+    pub(crate) fn ticker_timeout(&self) -> Duration {
+        let now = self.now();
+        self.next_check_time(now).saturating_duration_since(now)
+    }
+
+    /// Return the time at which it should be checked again whether it is
+    /// time to log, given the current time.
+    ///
+    /// See [`ticker_timeout`](ProgressLogger::ticker_timeout).
+    pub(crate) fn next_check_time(&self, now: Instant) -> Instant {
+        if self.next_log_time > now {
+            self.next_log_time
+        } else {
+            now.checked_add((self.log_interval / 10).max(MIN_TICK))
+                .unwrap_or(now)
+        }
+    }
+
+    /// Move this logger into a new [`Ticker`].
+    pub fn spawn_ticker(self) -> Ticker {
+        Ticker::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A system clock counting the number of times it is read.
+    #[derive(Default, Clone)]
+    struct CountingClock(Arc<AtomicUsize>);
+
+    impl Clock for CountingClock {
+        fn now(&self) -> Instant {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Instant::now()
+        }
+    }
+
+    #[test]
+    fn test_min_items_per_log() {
+        let clock = CountingClock::default();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone())
+            .log_interval(Duration::from_millis(1))
+            .min_items_per_log(usize::MAX);
+        pl.start("");
+        let ticker = pl.spawn_ticker();
+        thread::sleep(Duration::from_millis(200));
+        ticker.stop();
+        // At most one wakeup every 10ms, with a few clock reads each
+        assert!(clock.0.load(Ordering::Relaxed) < 200);
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{Clock, ProgressLogger};
+use std::fmt::{Formatter, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/**
+
+A guard accumulating the time elapsed until it is dropped into a timer of a
+[`ProgressLogger`].
+
+See [`ProgressLogger::timer`].
+
+*/
+#[must_use = "the time is accumulated when the guard is dropped"]
+pub struct TimerGuard {
+    /// The accumulated nanoseconds of the timer.
+    nanos: Arc<AtomicU64>,
+    clock: Option<Arc<dyn Clock>>,
+    start: Instant,
+}
+
+impl TimerGuard {
+    fn now(&self) -> Instant {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => Instant::now(),
+        }
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        let elapsed = self.now().saturating_duration_since(self.start);
+        self.nanos.fetch_add(
+            elapsed.as_nanos().try_into().unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+impl ProgressLogger {
+    /// Start the timer with the given name, returning a guard that will add
+    /// the time elapsed until it is dropped to the timer.
+    ///
+    /// The logger displays at each log and in the final stats the time
+    /// accumulated by each timer as a percentage of the elapsed time, in the
+    /// order in which the timers were first started. Timers thus provide a
+    /// coarse profile of the instrumented loop. Timers are cleared when the
+    /// logger is [started](crate::ProgressLog::start) again.
+    ///
+    /// The guard does not borrow the logger, so the logger can be updated
+    /// while a timer is running.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use dsi_progress_logger::prelude::*;
+    ///
+    /// let mut pl = ProgressLogger::default();
+    /// pl.start("Processing blocks...");
+    /// for _ in 0..100 {
+    ///     {
+    ///         let _io = pl.timer("io");
+    ///         // read a block
+    ///     }
+    ///     let _cpu = pl.timer("cpu");
+    ///     // process the block
+    ///     pl.update();
+    /// }
+    /// pl.done();
+    /// ```
+    pub fn timer(&mut self, name: &str) -> TimerGuard {
+        let nanos = match self.timers.iter().find(|(timer, _)| timer == name) {
+            Some((_, nanos)) => nanos.clone(),
+            None => {
+                let nanos = Arc::new(AtomicU64::new(0));
+                self.timers.push((name.to_owned(), nanos.clone()));
+                nanos
+            }
+        };
+        TimerGuard {
+            nanos,
+            clock: self.clock.clone(),
+            start: self.now(),
+        }
+    }
+
+    /// Clear the accumulated time of all timers.
+    pub(crate) fn reset_timers(&mut self) {
+        for (_, nanos) in &self.timers {
+            nanos.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Format the time accumulated by the timers, if any, as a percentage of
+    /// the given elapsed time.
+    pub(crate) fn fmt_timers(&self, f: &mut Formatter<'_>, elapsed: Duration) -> Result {
+        for (i, (name, nanos)) in self.timers.iter().enumerate() {
+            let fraction = Duration::from_nanos(nanos.load(Ordering::Relaxed)).as_secs_f64()
+                / elapsed.as_secs_f64();
+            f.write_str(if i == 0 { "; time in " } else { ", " })?;
+            if fraction.is_finite() {
+                f.write_fmt(format_args!("{} {:.2}%", name, 100.0 * fraction))?;
+            } else {
+                f.write_fmt(format_args!("{} n/a", name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timers() {
+        let clock = ManualClock::new();
+        let mut pl = ProgressLogger::default();
+        pl.clock(clock.clone()).deterministic(true);
+        pl.start("");
+        for _ in 0..4 {
+            {
+                let _io = pl.timer("io");
+                clock.advance(Duration::from_millis(100));
+            }
+            let _cpu = pl.timer("cpu");
+            clock.advance(Duration::from_millis(150));
+            pl.update();
+        }
+        assert!(pl.to_string().ends_with("; time in io 40.00%, cpu 60.00%"));
+        clock.advance(Duration::from_secs(1));
+        pl.done();
+        assert!(pl.to_string().ends_with("]; time in io 20.00%, cpu 30.00%"));
+        pl.start("");
+        assert!(pl.to_string().ends_with("; time in io n/a, cpu n/a"));
+    }
+}
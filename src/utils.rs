@@ -5,8 +5,13 @@
  * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
  */
 
-#[derive(Debug, Copy, Clone)]
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// A unit of time used to display speeds and durations.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TimeUnit {
     NanoSeconds,
     MicroSeconds,
@@ -15,10 +20,22 @@ pub enum TimeUnit {
     Minutes,
     Hours,
     Days,
+    Weeks,
+    /// A user-defined unit, given by a label and its length in seconds
+    /// (e.g., `TimeUnit::Custom { label: "shift", seconds: 28800.0 }`).
+    ///
+    /// Custom units are never chosen automatically; when set as the
+    /// [time unit](crate::ProgressLog::time_unit) of a logger, they are used
+    /// also to display elapsed time and time to completion.
+    Custom {
+        label: &'static str,
+        seconds: f64,
+    },
 }
 
 impl TimeUnit {
-    pub const VALUES: [TimeUnit; 7] = [
+    /// The predefined units, in increasing order of length.
+    pub const VALUES: [TimeUnit; 8] = [
         TimeUnit::NanoSeconds,
         TimeUnit::MicroSeconds,
         TimeUnit::MilliSeconds,
@@ -26,6 +43,7 @@ impl TimeUnit {
         TimeUnit::Minutes,
         TimeUnit::Hours,
         TimeUnit::Days,
+        TimeUnit::Weeks,
     ];
 
     pub fn label(&self) -> &'static str {
@@ -37,6 +55,8 @@ impl TimeUnit {
             TimeUnit::Minutes => "m",
             TimeUnit::Hours => "h",
             TimeUnit::Days => "d",
+            TimeUnit::Weeks => "w",
+            TimeUnit::Custom { label, .. } => label,
         }
     }
 
@@ -49,6 +69,8 @@ impl TimeUnit {
             TimeUnit::Minutes => 60.0,
             TimeUnit::Hours => 3600.0,
             TimeUnit::Days => 86400.0,
+            TimeUnit::Weeks => 604800.0,
+            TimeUnit::Custom { seconds, .. } => *seconds,
         }
     }
 
@@ -67,7 +89,7 @@ impl TimeUnit {
                 return *unit;
             }
         }
-        TimeUnit::Days
+        TimeUnit::Weeks
     }
 
     pub fn pretty_print(milliseconds: u128) -> String {
@@ -79,7 +101,12 @@ impl TimeUnit {
 
         let mut seconds = milliseconds / 1000;
 
-        for unit in [TimeUnit::Days, TimeUnit::Hours, TimeUnit::Minutes] {
+        for unit in [
+            TimeUnit::Weeks,
+            TimeUnit::Days,
+            TimeUnit::Hours,
+            TimeUnit::Minutes,
+        ] {
             let to_seconds = unit.as_seconds() as u128;
             if seconds >= to_seconds {
                 result.push_str(&format!("{}{} ", seconds / to_seconds, unit.label(),));
@@ -106,12 +133,184 @@ pub fn scale(mut val: f64) -> (f64, &'static str) {
 }
 
 pub fn humanize(val: f64) -> String {
-    let (val, unit) = scale(val);
-    format!("{:.2}{}", val, unit)
+    Humanized(val).to_string()
 }
 
-#[cfg(test)]
+/// A value displayed as by [`humanize`], without allocating.
+pub(crate) struct Humanized(pub f64);
+
+impl std::fmt::Display for Humanized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (val, unit) = scale(self.0);
+        write!(f, "{:.2}{}", val, unit)
+    }
+}
+
+/// A count formatted on the stack, without separators if a time unit is set.
+pub(crate) enum FmtCount {
+    /// A count with thousands separators.
+    Separated(num_format::Buffer),
+    /// A count without separators.
+    Plain(usize),
+}
+
+impl std::fmt::Display for FmtCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FmtCount::Separated(buffer) => f.write_str(buffer),
+            FmtCount::Plain(count) => std::fmt::Display::fmt(count, f),
+        }
+    }
+}
+
+/// The prefix `[+hh:mm:ss] ` with the elapsed time in seconds, or the empty
+/// string.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ElapsedPrefix(pub(crate) Option<u64>);
+
+impl std::fmt::Display for ElapsedPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(seconds) => write!(
+                f,
+                "[+{:02}:{:02}:{:02}] ",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A value accessed only through exclusive references.
+///
+/// The value is wrapped in a mutex that is never locked, so that a
+/// [`ProgressLogger`](crate::ProgressLogger) storing a value that is [`Send`]
+/// but not [`Sync`] (e.g., a closure capturing a [`Cell`](std::cell::Cell))
+/// is still [`Sync`].
+pub(crate) struct Exclusive<T>(Mutex<T>);
+
+impl<T> Exclusive<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(Mutex::new(value))
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Return a pseudorandom number in [0 . . 1).
+///
+/// The number is derived from the random keys of a new
+/// [`RandomState`](std::collections::hash_map::RandomState), which differ at
+/// each call.
+pub(crate) fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let hash = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// Estimate the milliseconds to completion given the count, the number of
+/// expected updates and the elapsed time.
+///
+/// The estimate is the number of items to go times the elapsed time,
+/// divided by the count plus one. The computation uses nanoseconds and
+/// explicit saturating `u128` arithmetic, so it never overflows, even for
+/// year-scale extrapolations or enormous counts: if the product of the items
+/// to go and the elapsed nanoseconds does not fit in a `u128`, the elapsed
+/// time is divided first.
+pub(crate) fn millis_to_end(count: usize, expected_updates: usize, elapsed: Duration) -> u128 {
+    let to_go = expected_updates.saturating_sub(count) as u128;
+    let nanos = elapsed.as_nanos();
+    let divisor = count as u128 + 1;
+    let nanos_to_end = match to_go.checked_mul(nanos) {
+        Some(product) => product / divisor,
+        None => (nanos / divisor)
+            .saturating_mul(to_go)
+            .saturating_add((nanos % divisor).saturating_mul(to_go) / divisor),
+    };
+    nanos_to_end / 1_000_000
+}
+
+/// Convert milliseconds into a duration, saturating at [`u64::MAX`]
+/// milliseconds.
+pub(crate) fn millis_to_duration(millis: u128) -> Duration {
+    Duration::from_millis(millis.try_into().unwrap_or(u64::MAX))
+}
 
+/// Return the CPU time (user and system) consumed so far by the current
+/// process, if available.
+///
+/// The CPU time is currently available only on Linux, where it is read from
+/// `/proc/self/stat` (which reports it in units of 1/100 of a second).
+pub fn cpu_time() -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+        // The command name might contain spaces, so we skip past it
+        let mut fields = stat.get(stat.rfind(')')? + 1..)?.split_whitespace();
+        let utime: u64 = fields.nth(11)?.parse().ok()?;
+        let stime: u64 = fields.next()?.parse().ok()?;
+        Some(Duration::from_millis(
+            utime.saturating_add(stime).saturating_mul(10),
+        ))
+    }
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+/// Return a non-blocking UDP socket connected to the given address.
+pub fn udp_socket(addr: impl ToSocketAddrs) -> io::Result<UdpSocket> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send to"))?;
+    let local: SocketAddr = if addr.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(addr)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// Format a wall-clock time in RFC 3339 format, in UTC, with second precision.
+pub fn rfc3339(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i128,
+        Err(err) => -(err.duration().as_secs() as i128),
+    };
+    let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i128;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
     #[test]
@@ -121,7 +320,124 @@ mod test {
         assert_eq!(scale(1_000_000_000.0), (1.0, "G"));
     }
     #[test]
+    fn test_pretty_print() {
+        assert_eq!(TimeUnit::pretty_print(999), "999ms");
+        assert_eq!(TimeUnit::pretty_print(61_000), "1m 1s");
+        assert_eq!(TimeUnit::pretty_print(90_061_000), "1d 1h 1m 1s");
+        assert_eq!(TimeUnit::pretty_print(1_296_000_000), "2w 1d 0s");
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        use std::time::Duration;
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(1_710_765_296)),
+            "2024-03-18T12:34:56Z"
+        );
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            rfc3339(UNIX_EPOCH - Duration::from_secs(1)),
+            "1969-12-31T23:59:59Z"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cpu_time() {
+        let before = cpu_time().unwrap();
+        let mut x = 0_u64;
+        for i in 0..10_000_000 {
+            x = std::hint::black_box(x.wrapping_add(i));
+        }
+        assert!(cpu_time().unwrap() >= before);
+    }
+
+    #[test]
+    fn test_millis_to_end() {
+        let second = Duration::from_secs(1);
+        assert_eq!(millis_to_end(0, 0, second), 0);
+        assert_eq!(millis_to_end(9, 19, second), 1000);
+        assert_eq!(millis_to_end(20, 10, second), 0);
+        assert_eq!(millis_to_end(0, 10, Duration::ZERO), 0);
+        assert_eq!(millis_to_end(99, 199, Duration::from_micros(1500)), 1);
+
+        // A century-long run that has not counted any item yet
+        let century = Duration::from_secs(100 * 365 * 86400);
+        assert_eq!(
+            millis_to_end(0, usize::MAX, century),
+            usize::MAX as u128 * century.as_millis()
+        );
+        // The product of items to go and nanoseconds does not fit in a u128
+        assert_eq!(
+            millis_to_end(1, usize::MAX, Duration::MAX),
+            u128::MAX / 1_000_000
+        );
+        let to_go = (usize::MAX - (1 << 40)) as u128;
+        let quotient = Duration::MAX.as_nanos() / ((1 << 40) + 1);
+        let millis = millis_to_end(1 << 40, usize::MAX, Duration::MAX);
+        assert!(millis >= quotient * to_go / 1_000_000);
+        assert!(millis <= (quotient + 1) * to_go / 1_000_000);
+        assert_eq!(millis_to_end(usize::MAX, usize::MAX, Duration::MAX), 0);
+        assert_eq!(
+            millis_to_end(usize::MAX - 1, usize::MAX, Duration::MAX),
+            Duration::MAX.as_nanos() / usize::MAX as u128 / 1_000_000
+        );
+    }
+
+    #[test]
+    fn test_millis_to_end_properties() {
+        let counts = [
+            0,
+            1,
+            2,
+            1000,
+            u32::MAX as usize,
+            usize::MAX / 2,
+            usize::MAX - 1,
+            usize::MAX,
+        ];
+        let durations = [
+            Duration::ZERO,
+            Duration::from_nanos(1),
+            Duration::from_millis(999),
+            Duration::from_secs(86400 * 365 * 1000),
+            Duration::from_secs(u64::MAX / 2),
+            Duration::MAX,
+        ];
+        for &count in &counts {
+            for &expected_updates in &counts {
+                for &elapsed in &durations {
+                    let millis = millis_to_end(count, expected_updates, elapsed);
+                    // The estimate cannot exceed the time needed at the
+                    // speed of one item per elapsed time
+                    let to_go = expected_updates.saturating_sub(count) as u128;
+                    assert!(millis <= to_go.saturating_mul(elapsed.as_millis() + 1));
+                    if to_go == 0 {
+                        assert_eq!(millis, 0);
+                    }
+                    // More items to go never decrease the estimate
+                    assert!(
+                        millis_to_end(count, expected_updates.saturating_add(1), elapsed) >= millis
+                    );
+                    // More elapsed time never decreases the estimate
+                    assert!(
+                        millis_to_end(count, expected_updates, elapsed.saturating_mul(2)) >= millis
+                    );
+                    let _ = millis_to_duration(millis);
+                }
+            }
+        }
+        assert_eq!(
+            millis_to_duration(u128::MAX),
+            Duration::from_millis(u64::MAX)
+        );
+    }
 
+    #[test]
     fn test_humanize() {
         assert_eq!(humanize(1000.0), "1.00k");
         assert_eq!(humanize(12_345.0), "12.35k");
@@ -0,0 +1,252 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Inria
+ * SPDX-FileCopyrightText: 2023 Sebastiano Vigna
+ *
+ * SPDX-License-Identifier: Apache-2.0 OR LGPL-2.1-or-later
+ */
+
+use crate::{ProgressLog, ProgressLogger, ProgressLoggerHandle, RecordKind, TimeUnit};
+use log::Level;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The minimum period between two checks of the watchdog.
+const MIN_POLL: Duration = Duration::from_millis(10);
+
+/**
+
+A background thread watching a [`ProgressLoggerHandle`] and reacting if no
+update arrives within a deadline.
+
+If the logger is running and its count does not change for the deadline, the
+watchdog logs, at the [error](Level::Error) level, the last known state of
+the logger, and then either aborts the process (see [`new`](#method.new)) or
+calls a closure (see [`with_callback`](#method.with_callback)), so that hung
+jobs do not hold resources silently. The watchdog never blocks on the lock of
+the logger: a lock found held by another thread at every check for the whole
+deadline is treated as a stall, too. The watchdog reacts once per stall: it
+reacts again only if the count changes and stalls again.
+
+The thread is stopped when the watchdog is dropped.
+
+# Examples
+
+```rust
+use dsi_progress_logger::prelude::*;
+use dsi_progress_logger::Watchdog;
+use std::time::Duration;
+
+let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+let _watchdog = Watchdog::new(&pl, Duration::from_secs(3600));
+pl.lock().start("Crawling...");
+for _ in 0..1000 {
+    pl.update();
+}
+pl.done();
+```
+
+*/
+pub struct Watchdog {
+    /// Whether the thread should stop.
+    stop: Arc<AtomicBool>,
+    /// The thread watching the logger.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Spawn a watchdog aborting the process if the given logger makes no
+    /// progress within the given deadline.
+    pub fn new(pl: &ProgressLoggerHandle, deadline: Duration) -> Self {
+        Self::with_callback(pl, deadline, |_| std::process::abort())
+    }
+
+    /// Spawn a watchdog calling the given closure if the given logger makes
+    /// no progress within the given deadline.
+    ///
+    /// The closure receives the logger, or [`None`] if the lock of the
+    /// logger has been found held by another thread at every check for the
+    /// whole deadline.
+    pub fn with_callback(
+        pl: &ProgressLoggerHandle,
+        deadline: Duration,
+        mut on_stall: impl FnMut(Option<&ProgressLogger>) + Send + 'static,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let pl = pl.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut last_count = None;
+                let mut last_change = pl.now();
+                let mut stalled = false;
+                // When the lock was first found held by another thread, if it
+                // has been held at every check since then
+                let mut contended_since = None;
+                while !stop.load(Ordering::Acquire) {
+                    let now = pl.now();
+                    let timed_out = now.saturating_duration_since(last_change) >= deadline;
+                    match pl.try_lock() {
+                        Some(mut pl) => {
+                            contended_since = None;
+                            pl.refresh();
+                            let count = pl.count();
+                            if pl.start_time.is_none() || pl.stop_time.is_some() {
+                                last_count = None;
+                            } else if last_count != Some(count) {
+                                last_count = Some(count);
+                                last_change = now;
+                                stalled = false;
+                            } else if !stalled && timed_out {
+                                stalled = true;
+                                pl.emit(
+                                    Level::Error,
+                                    RecordKind::Info,
+                                    format_args!(
+                                        "No progress for {}: {}",
+                                        pl.fmt_duration(deadline),
+                                        *pl
+                                    ),
+                                );
+                                on_stall(Some(&pl));
+                            }
+                        }
+                        // The logger is locked by another thread, which might
+                        // be hung while holding the lock
+                        None => {
+                            let locked =
+                                now.saturating_duration_since(*contended_since.get_or_insert(now));
+                            if last_count.is_some() && !stalled && timed_out && locked >= deadline {
+                                stalled = true;
+                                log::error!(
+                                    "No progress for {}: the logger has been locked for {}",
+                                    TimeUnit::pretty_print(deadline.as_millis()),
+                                    TimeUnit::pretty_print(locked.as_millis()),
+                                );
+                                on_stall(None);
+                            }
+                        }
+                    }
+                    thread::park_timeout((deadline / 4).max(MIN_POLL));
+                }
+            })
+        };
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            thread.thread().unpark();
+            // The thread does not panic, except in logging backends and in
+            // the callback
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_watchdog() {
+        let logs = crate::testing::CapturedLogs::new();
+        let mut pl = ProgressLogger::default();
+        pl.add_sink(logs.clone());
+        let pl = ProgressLoggerHandle::new(pl);
+        let stalls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let watchdog = {
+            let stalls = stalls.clone();
+            Watchdog::with_callback(&pl, Duration::from_millis(100), move |_| {
+                stalls.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(stalls.load(Ordering::Relaxed), 0);
+        pl.lock().start("");
+        pl.update();
+        thread::sleep(Duration::from_millis(500));
+        drop(watchdog);
+        assert_eq!(stalls.load(Ordering::Relaxed), 1);
+        assert!(logs
+            .records()
+            .iter()
+            .any(|record| record.level == Level::Error
+                && record
+                    .message
+                    .starts_with("No progress for 100ms: 1 item, ")));
+    }
+
+    #[test]
+    fn test_watchdog_guard_updates() {
+        let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+        let stalls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let watchdog = {
+            let stalls = stalls.clone();
+            Watchdog::with_callback(&pl, Duration::from_millis(100), move |_| {
+                stalls.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        pl.lock().start("");
+        for _ in 0..60 {
+            pl.lock().update();
+            thread::sleep(Duration::from_millis(10));
+        }
+        drop(watchdog);
+        assert_eq!(stalls.load(Ordering::Relaxed), 0);
+        pl.done();
+    }
+
+    #[test]
+    fn test_watchdog_held_lock() {
+        let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+        let stalls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let watchdog = {
+            let stalls = stalls.clone();
+            Watchdog::with_callback(&pl, Duration::from_millis(100), move |pl| {
+                assert!(pl.is_none());
+                stalls.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        pl.lock().start("");
+        for _ in 0..15 {
+            pl.update();
+            thread::sleep(Duration::from_millis(10));
+        }
+        let guard = pl.lock();
+        thread::sleep(Duration::from_millis(500));
+        drop(watchdog);
+        drop(guard);
+        assert_eq!(stalls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_watchdog_short_lock() {
+        let pl = ProgressLoggerHandle::new(ProgressLogger::default());
+        let stalls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let watchdog = {
+            let stalls = stalls.clone();
+            Watchdog::with_callback(&pl, Duration::from_millis(200), move |_| {
+                stalls.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        pl.lock().start("");
+        pl.update();
+        thread::sleep(Duration::from_millis(150));
+        // The count does not change for more than the deadline, but the lock
+        // is held for less than the deadline
+        let mut guard = pl.lock();
+        thread::sleep(Duration::from_millis(150));
+        guard.update();
+        drop(guard);
+        drop(watchdog);
+        assert_eq!(stalls.load(Ordering::Relaxed), 0);
+    }
+}